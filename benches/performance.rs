@@ -35,7 +35,7 @@ fn benchmark_search(c: &mut Criterion) {
 }
 
 fn benchmark_template_rendering(c: &mut Criterion) {
-    let engine = TemplateEngine::new();
+    let mut engine = TemplateEngine::new();
     let template = "Hello {{name}}, your {{type}} order for {{item}} is {{status}}!";
     let mut variables = HashMap::new();
     variables.insert("name".to_string(), "John Doe".to_string());
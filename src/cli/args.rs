@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand, ValueEnum};
+use crate::database::{converters::Tool, interchange::DataFormat};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(
@@ -17,13 +19,17 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub verbose: bool,
     
-    /// Specify custom config file path
-    #[arg(short, long, global = true, value_name = "FILE")]
+    /// Use this file as the user config file
+    #[arg(long, global = true, env = "EDISONPROMPT_CONFIG", value_name = "FILE")]
     pub config: Option<PathBuf>,
     
     /// Disable colored output
     #[arg(long, global = true)]
     pub no_color: bool,
+    
+    /// Use this prompt library instead of the active one
+    #[arg(long, global = true, env = "EDISONPROMPT_LIBRARY", value_name = "NAME")]
+    pub library: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -45,12 +51,22 @@ pub enum Commands {
         /// Force overwrite if prompt exists
         #[arg(short, long)]
         force: bool,
+        
+        /// Mark a variable secret: never stored, asked for without echo or
+        /// read from SOURCE (env:NAME or cmd:COMMAND) when rendering
+        #[arg(long = "secret", value_name = "NAME[=SOURCE]", value_parser = parse_secret)]
+        secrets: Vec<(String, Option<String>)>,
+        
+        /// Save even if the content looks like it holds secrets or personal data
+        #[arg(long)]
+        allow_secrets: bool,
     },
     
     /// Retrieve and render a prompt with variables
     Get {
-        /// Prompt name to retrieve
-        name: String,
+        /// Prompt name to retrieve (a unique prefix is enough); omit to pick
+        /// from a list
+        name: Option<String>,
         
         /// Variable values in key=value format
         #[arg(long = "var", value_parser = parse_variable)]
@@ -63,6 +79,23 @@ pub enum Commands {
         /// Output raw content without rendering
         #[arg(short, long)]
         raw: bool,
+        
+        /// Restore the previous clipboard contents (or clear it) after this
+        /// long, e.g. 30s or 2m
+        #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "copy")]
+        clear_after: Option<Duration>,
+        
+        /// Print a kept render again instead of rendering: the last one, or
+        /// N renders ago (see clipboard.history)
+        #[arg(
+            long,
+            value_name = "N",
+            num_args = 0..=1,
+            default_missing_value = "1",
+            value_parser = clap::value_parser!(u32).range(1..),
+            conflicts_with_all = ["name", "variables", "raw"]
+        )]
+        paste_last: Option<u32>,
     },
     
     /// List prompts with optional filtering
@@ -71,39 +104,122 @@ pub enum Commands {
         #[arg(short, long)]
         tag: Option<String>,
         
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "table")]
-        format: OutputFormat,
+        /// List the prompts matching a saved search
+        #[arg(long, conflicts_with = "tag")]
+        collection: Option<String>,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
         
         /// Limit number of results
         #[arg(short, long)]
         limit: Option<usize>,
         
-        /// Sort by field
-        #[arg(short, long, value_enum, default_value = "name")]
-        sort: SortField,
+        /// Sort by field [default: name]
+        #[arg(short, long, value_enum)]
+        sort: Option<SortField>,
         
         /// Show only names
         #[arg(long)]
         names_only: bool,
+        
+        /// Put pinned prompts first
+        #[arg(long)]
+        pinned: bool,
+        
+        /// Show only favorite prompts
+        #[arg(long)]
+        favorites: bool,
     },
     
     /// Search prompts by content using full-text search
     Search {
         /// Search query
-        query: String,
+        #[arg(required_unless_present = "saved")]
+        query: Option<String>,
         
-        /// Highlight search terms in results
-        #[arg(long)]
+        /// Run a saved search; a QUERY given as well narrows it further
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["fuzzy", "semantic"])]
+        saved: Option<String>,
+        
+        /// Highlight search terms in results [default: the search.highlight setting]
+        #[arg(long, overrides_with = "no_highlight")]
         highlight: bool,
         
-        /// Limit number of results  
-        #[arg(short, long, default_value = "50")]
+        /// Do not highlight search terms
+        #[arg(long)]
+        no_highlight: bool,
+        
+        /// Typo-tolerant matching on names and content
+        #[arg(long, conflicts_with = "highlight")]
+        fuzzy: bool,
+        
+        /// Match by meaning using local embeddings instead of keywords
+        #[arg(long, conflicts_with_all = ["highlight", "fuzzy"])]
+        semantic: bool,
+        
+        /// Show the components of each result's score
+        #[arg(long)]
+        explain: bool,
+        
+        /// Limit number of results [default: the search.limit setting]
+        #[arg(short, long)]
+        limit: Option<usize>,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Find prompts similar in meaning to an existing prompt
+    Similar {
+        /// Prompt name to compare against
+        name: String,
+        
+        /// Limit number of results
+        #[arg(short, long, default_value = "10")]
         limit: usize,
         
-        /// Output format
-        #[arg(short, long, value_enum, default_value = "table")]
-        format: OutputFormat,
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Rebuild the embedding vectors used for semantic search
+    Reindex,
+    
+    /// Pin a prompt so it is listed and offered first
+    Pin {
+        /// Prompt name (a unique prefix is enough)
+        name: String,
+    },
+    
+    /// Unpin a prompt
+    Unpin {
+        /// Prompt name (a unique prefix is enough)
+        name: String,
+    },
+    
+    /// Mark a prompt as a favorite
+    Favorite {
+        /// Prompt name (a unique prefix is enough)
+        name: String,
+        
+        /// Remove the favorite mark instead
+        #[arg(long)]
+        remove: bool,
+    },
+    
+    /// List recently rendered prompts, most recent first
+    Recent {
+        /// Limit number of results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
     },
     
     /// Edit an existing prompt in your editor
@@ -114,6 +230,10 @@ pub enum Commands {
         /// Skip confirmation prompt
         #[arg(short, long)]
         yes: bool,
+        
+        /// Save even if the content looks like it holds secrets or personal data
+        #[arg(long)]
+        allow_secrets: bool,
     },
     
     /// Delete a prompt permanently
@@ -130,7 +250,7 @@ pub enum Commands {
         force: bool,
     },
     
-    /// Export prompts to JSON format
+    /// Export prompts to JSON, YAML or TOML
     Export {
         /// Output file path (stdout if not specified)
         #[arg(short, long)]
@@ -143,11 +263,23 @@ pub enum Commands {
         /// Pretty print JSON
         #[arg(short, long)]
         pretty: bool,
+        
+        /// File format [default: from the output file extension, else json]
+        #[arg(short, long, value_enum)]
+        format: Option<FileFormat>,
+        
+        /// Encrypt the export with a passphrase; import asks for it
+        #[arg(long)]
+        encrypt: bool,
+        
+        /// Write the JSON Schema of the export format instead of prompts
+        #[arg(long, conflicts_with_all = ["tag", "pretty", "format", "encrypt"])]
+        schema: bool,
     },
     
-    /// Import prompts from JSON format
+    /// Import prompts from JSON, YAML or TOML, or from other prompt tools
     Import {
-        /// Input file path (stdin if not specified)
+        /// Input file path, or directory for fabric and markdown (stdin if not specified)
         #[arg(short, long)]
         input: Option<PathBuf>,
         
@@ -155,9 +287,143 @@ pub enum Commands {
         #[arg(short, long, value_enum, default_value = "skip")]
         merge: MergeStrategy,
         
-        /// Dry run - show what would be imported
+        /// Dry run - import, report and roll back
         #[arg(long)]
         dry_run: bool,
+        
+        /// File format [default: from the input file extension, else json]
+        #[arg(short, long, value_enum)]
+        format: Option<FileFormat>,
+        
+        /// Convert another tool's prompt files instead of an edisonprompt export
+        #[arg(long, value_enum, conflicts_with = "format")]
+        from: Option<ImportTool>,
+        
+        /// Import what can be imported instead of rolling back on the first failure
+        #[arg(long)]
+        continue_on_error: bool,
+        
+        /// Print the import report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Save even if the content looks like it holds secrets or personal data
+        #[arg(long)]
+        allow_secrets: bool,
+    },
+    
+    /// Snapshot the library database, safely even while it is in use
+    Backup {
+        /// Backup file [default: a timestamped file in backup.directory]
+        path: Option<PathBuf>,
+        
+        /// Compress with gzip [default: backup.compress when no path is given]
+        #[arg(short = 'z', long)]
+        compress: bool,
+    },
+    
+    /// Replace the library database with a backup after checking it
+    Restore {
+        /// Backup file, compressed or not
+        file: PathBuf,
+        
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    
+    /// Manage saved searches (collections)
+    Collection {
+        #[command(subcommand)]
+        action: CollectionAction,
+    },
+    
+    /// Find exact and near-duplicate prompts, optionally merging them
+    Dedupe {
+        /// Minimum content similarity (0.0-1.0) for near duplicates
+        #[arg(long, default_value = "0.8", value_parser = parse_threshold)]
+        threshold: f64,
+        
+        /// Merge each group into one prompt, unioning tags and variables
+        #[arg(long)]
+        merge: bool,
+        
+        /// Keep the oldest prompt of each group without asking
+        #[arg(short, long)]
+        yes: bool,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Manage prompt libraries, each with its own database
+    Library {
+        #[command(subcommand)]
+        action: LibraryAction,
+    },
+    
+    /// Share the library through a git repository
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+    
+    /// Encrypt prompt content at rest under a passphrase
+    Encryption {
+        #[command(subcommand)]
+        action: EncryptionAction,
+    },
+    
+    /// Copy prompts into another library
+    Copy {
+        /// Prompt names (a unique prefix is enough)
+        #[arg(required = true)]
+        names: Vec<String>,
+        
+        /// Library to copy the prompts into
+        #[arg(long, value_name = "LIBRARY")]
+        to_library: String,
+        
+        /// Remove the prompts from the current library after copying
+        #[arg(long = "move")]
+        move_prompts: bool,
+        
+        /// Replace prompts with the same name in the target library
+        #[arg(short, long)]
+        force: bool,
+    },
+    
+    /// Show usage statistics: top and unused prompts, usage over time
+    Stats {
+        /// Number of days to report on
+        #[arg(short, long, default_value = "30", value_parser = clap::value_parser!(u32).range(1..))]
+        days: u32,
+        
+        /// Number of prompts to list as top and unused
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Check the configuration, database, clipboard and editor
+    Doctor {
+        /// Rebuild the search index and vacuum the database first
+        #[arg(long)]
+        fix: bool,
+        
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Show and change settings, and where each one comes from
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
     
     /// Generate shell completions
@@ -166,6 +432,198 @@ pub enum Commands {
         #[arg(value_enum)]
         shell: Shell,
     },
+    
+    /// Restore the clipboard after `get --clear-after`; started by
+    /// edisonprompt itself
+    #[command(name = "clipboard-restore", hide = true)]
+    ClipboardRestore,
+}
+
+#[derive(Subcommand)]
+pub enum LibraryAction {
+    /// List libraries, marking the active one
+    List,
+    
+    /// Create a library with its own database file
+    Create {
+        /// Library name
+        #[arg(value_parser = validate_prompt_name)]
+        name: String,
+        
+        /// Database file [default: <data dir>/<name>.db]
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    
+    /// Make a library the active one
+    Use {
+        /// Library name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    /// Connect the library to a git remote, or the path of a repository
+    /// such as a bare one on a shared drive
+    Init {
+        /// Git remote URL or repository path
+        remote: String,
+    },
+    
+    /// Commit the library and push it to the remote
+    Push,
+    
+    /// Merge the remote's prompts into the library by prompt id
+    Pull,
+}
+
+#[derive(Subcommand)]
+pub enum EncryptionAction {
+    /// Encrypt the content and variable defaults of every prompt
+    Enable,
+    
+    /// Decrypt every prompt and store the library in the clear
+    Disable,
+    
+    /// Show whether the library is encrypted and unlocked
+    Status,
+    
+    /// Print a shell command that keeps the library unlocked in this
+    /// shell: eval "$(edisonprompt encryption unlock)"
+    Unlock,
+    
+    /// Print a shell command that forgets the unlocked key:
+    /// eval "$(edisonprompt encryption lock)"
+    Lock,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the effective value of a setting, e.g. `search.limit`
+    Get {
+        /// Setting key, in section.key form
+        key: String,
+        
+        /// Also print which layer the value comes from
+        #[arg(long)]
+        show_origin: bool,
+    },
+    
+    /// Change a setting in the user config file
+    Set {
+        /// Setting key, in section.key form
+        key: String,
+        
+        /// New value; parsed as the setting's type
+        value: String,
+    },
+    
+    /// Remove a setting from the user config file
+    Unset {
+        /// Setting key, in section.key form
+        key: String,
+    },
+    
+    /// List every effective setting
+    List {
+        /// Also print which layer each value comes from
+        #[arg(long)]
+        show_origin: bool,
+    },
+    
+    /// Open the user config file in your editor
+    Edit,
+    
+    /// Check every layer for unknown keys and invalid values
+    Validate,
+    
+    /// Print the config file of each layer
+    Path,
+}
+
+#[derive(Subcommand)]
+pub enum CollectionAction {
+    /// Save a named query; its results are recomputed every time it runs
+    Save {
+        /// Collection name
+        #[arg(value_parser = validate_prompt_name)]
+        name: String,
+        
+        /// Full-text search query
+        #[arg(short, long)]
+        query: Option<String>,
+        
+        /// Require these tags (all of them)
+        #[arg(short, long, value_delimiter = ',')]
+        tags: Vec<String>,
+        
+        /// Sort field for `list --collection`
+        #[arg(short, long, value_enum)]
+        sort: Option<SortField>,
+        
+        /// Maximum number of results
+        #[arg(short, long)]
+        limit: Option<usize>,
+    },
+    
+    /// List saved searches
+    List {
+        /// Output format [default: the output.format setting]
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+    },
+    
+    /// Delete a saved search
+    Delete {
+        /// Collection name
+        name: String,
+    },
+}
+
+/// Format of export and import files
+#[derive(ValueEnum, Clone, Copy)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl From<FileFormat> for DataFormat {
+    fn from(format: FileFormat) -> Self {
+        match format {
+            FileFormat::Json => DataFormat::Json,
+            FileFormat::Yaml => DataFormat::Yaml,
+            FileFormat::Toml => DataFormat::Toml,
+        }
+    }
+}
+
+/// Prompt tools `import --from` can convert
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ImportTool {
+    /// promptfoo prompt files or promptfooconfig.yaml
+    Promptfoo,
+    /// LangChain / LangSmith hub prompt JSON
+    Langchain,
+    /// Fabric patterns/<name>/system.md directories
+    Fabric,
+    /// Markdown with optional YAML front matter
+    Markdown,
+    /// CSV with name,content,tags columns
+    Csv,
+}
+
+impl From<ImportTool> for Tool {
+    fn from(tool: ImportTool) -> Self {
+        match tool {
+            ImportTool::Promptfoo => Tool::Promptfoo,
+            ImportTool::Langchain => Tool::Langchain,
+            ImportTool::Fabric => Tool::Fabric,
+            ImportTool::Markdown => Tool::Markdown,
+            ImportTool::Csv => Tool::Csv,
+        }
+    }
 }
 
 #[derive(ValueEnum, Clone)]
@@ -175,6 +633,13 @@ pub enum OutputFormat {
     Plain,
 }
 
+impl OutputFormat {
+    /// The `output.format` setting; validation guarantees it is known
+    pub fn from_config(name: &str) -> Self {
+        Self::from_str(name, true).unwrap_or(OutputFormat::Table)
+    }
+}
+
 #[derive(ValueEnum, Clone)]
 pub enum SortField {
     Name,
@@ -187,6 +652,13 @@ pub enum MergeStrategy {
     Skip,
     Overwrite,
     Rename,
+    /// Keep whichever copy was updated last
+    Newest,
+    /// Keep the local prompt and add the incoming tags to it
+    #[value(name = "union-tags")]
+    UnionTags,
+    /// Show each conflict and ask whether to keep, take or edit it
+    Interactive,
 }
 
 #[derive(ValueEnum, Clone)]
@@ -211,10 +683,67 @@ fn validate_prompt_name(name: &str) -> Result<String, String> {
     Ok(name.to_string())
 }
 
+fn parse_secret(s: &str) -> Result<(String, Option<String>), String> {
+    match s.split_once('=') {
+        Some((name, source)) => {
+            crate::template::SecretSource::parse(source)?;
+            Ok((name.to_string(), Some(source.to_string())))
+        }
+        None => Ok((s.to_string(), None)),
+    }
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: u64 = number.parse().map_err(|_| format!("'{}' is not a duration such as 30s or 2m", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(value)),
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "m" => Ok(Duration::from_secs(value.saturating_mul(60))),
+        "h" => Ok(Duration::from_secs(value.saturating_mul(3600))),
+        _ => Err(format!("unknown unit '{}': use ms, s, m or h", unit)),
+    }
+}
+
+fn parse_threshold(s: &str) -> Result<f64, String> {
+    let value: f64 = s.parse().map_err(|_| format!("'{}' is not a number", s))?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err("Threshold must be between 0.0 and 1.0".to_string());
+    }
+    Ok(value)
+}
+
 fn parse_variable(s: &str) -> Result<(String, String), String> {
     let parts: Vec<&str> = s.splitn(2, '=').collect();
     if parts.len() != 2 {
         return Err("Variable must be in key=value format".to_string());
     }
     Ok((parts[0].to_string(), parts[1].to_string()))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_format_from_config() {
+        assert!(matches!(OutputFormat::from_config("JSON"), OutputFormat::Json));
+        assert!(matches!(OutputFormat::from_config("plain"), OutputFormat::Plain));
+        assert!(matches!(OutputFormat::from_config("fancy"), OutputFormat::Table));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("-5s").is_err());
+        assert!(parse_duration("1.5s").is_err());
+        assert!(parse_duration("10d").unwrap_err().contains("unknown unit 'd'"));
+    }
+}
@@ -2,23 +2,32 @@ use crate::{
     clipboard::ClipboardManager,
     database::Database,
     error::{PromptedsError, Result},
-    template::TemplateEngine,
+    template::{scan::Scanner, TemplateEngine},
     database::models::{Prompt, Variable},
     utils,
 };
 use std::io::{self, Read, Write};
 
+/// Flags that change how a prompt is added
+pub struct AddOptions {
+    pub interactive: bool,
+    pub tags: Vec<String>,
+    pub force: bool,
+    /// Secret variables with their optional source
+    pub secrets: Vec<(String, Option<String>)>,
+}
+
 pub struct AddCommand;
 
 impl AddCommand {
     pub fn execute(
         database: &mut Database,
         clipboard: &mut ClipboardManager,
+        scanner: &Scanner,
         name: String,
-        interactive: bool,
-        tags: Vec<String>,
-        force: bool,
+        options: AddOptions,
     ) -> Result<()> {
+        let AddOptions { interactive, tags, force, secrets } = options;
         // Check if prompt already exists
         if !force && database.prompt_exists(&name)? {
             return Err(PromptedsError::PromptAlreadyExists { name });
@@ -34,11 +43,21 @@ impl AddCommand {
         // Extract variables from template
         let template_engine = TemplateEngine::new();
         let variable_names = template_engine.extract_variables(&content)?;
+        if let Some((name, _)) = secrets.iter().find(|(name, _)| !variable_names.contains(name)) {
+            return Err(PromptedsError::InvalidVariableName {
+                name: name.clone(),
+                reason: "the prompt does not use it".to_string(),
+            });
+        }
+        let secret = |name: &str| secrets.iter().find(|(secret, _)| secret == name).map(|(_, source)| source.clone());
         let variables = if interactive && !variable_names.is_empty() {
-            Self::get_interactive_variables(variable_names)?
+            Self::get_interactive_variables(variable_names, &secret)?
         } else {
             variable_names.into_iter()
-                .map(Variable::new)
+                .map(|name| match secret(&name) {
+                    Some(source) => Variable::new(name).with_secret(source),
+                    None => Variable::new(name),
+                })
                 .collect()
         };
         
@@ -47,6 +66,11 @@ impl AddCommand {
             .with_variables(variables)
             .with_tags(tags);
         
+        // Refuse or warn about secrets pasted into the content
+        for finding in scanner.check(&prompt)? {
+            utils::print_warning(&format!("Possible {}", finding));
+        }
+        
         // Save to database
         database.create_prompt(&prompt)?;
         
@@ -61,6 +85,12 @@ impl AddCommand {
                     .join(", ")
             ));
         }
+        if database.ensure_in_library(&name).is_err() {
+            utils::print_warning(&format!(
+                "A project prompt named '{}' shadows this one while you are in this project",
+                name
+            ));
+        }
         
         Ok(())
     }
@@ -99,7 +129,12 @@ impl AddCommand {
         Ok(content.trim().to_string())
     }
     
-    fn get_interactive_variables(variable_names: Vec<String>) -> Result<Vec<Variable>> {
+    /// Ask for descriptions and defaults; secret variables, for which
+    /// `secret` gives a source, get no default
+    fn get_interactive_variables(
+        variable_names: Vec<String>,
+        secret: &dyn Fn(&str) -> Option<Option<String>>,
+    ) -> Result<Vec<Variable>> {
         println!("\nFound {} variable(s) in template. Please provide descriptions:", variable_names.len());
         
         let mut variables = Vec::new();
@@ -111,17 +146,22 @@ impl AddCommand {
             io::stdin().read_line(&mut description)?;
             let description = description.trim();
             
-            print!("Default value for '{}' (optional): ", name);
+            let mut variable = Variable::new(name);
+            if !description.is_empty() {
+                variable = variable.with_description(description.to_string());
+            }
+            if let Some(source) = secret(&variable.name) {
+                variables.push(variable.with_secret(source));
+                continue;
+            }
+            
+            print!("Default value for '{}' (optional): ", variable.name);
             io::stdout().flush()?;
             
             let mut default_value = String::new();
             io::stdin().read_line(&mut default_value)?;
             let default_value = default_value.trim();
             
-            let mut variable = Variable::new(name);
-            if !description.is_empty() {
-                variable = variable.with_description(description.to_string());
-            }
             if !default_value.is_empty() {
                 variable = variable.with_default(default_value.to_string());
             }
@@ -0,0 +1,47 @@
+use crate::{
+    database::{backup, Database},
+    error::Result,
+    utils,
+};
+use std::path::PathBuf;
+
+pub struct BackupCommand;
+
+impl BackupCommand {
+    pub fn execute(database: &Database, path: PathBuf, compress: bool) -> Result<()> {
+        let compress = compress || path.extension().is_some_and(|ext| ext == "gz");
+        database.backup_to(&path, compress)?;
+
+        let size = std::fs::metadata(&path)?.len();
+        utils::print_success(&format!("Backed up to {} ({})", path.display(), utils::format_bytes(size)));
+        Ok(())
+    }
+}
+
+pub struct RestoreCommand;
+
+impl RestoreCommand {
+    pub fn execute(database: &mut Database, library: &str, file: PathBuf, yes: bool) -> Result<()> {
+        let snapshot = backup::verify(&file)?;
+        utils::print_info(&format!(
+            "{}: {} prompt(s), schema version {}",
+            file.display(),
+            snapshot.prompts,
+            snapshot.schema_version
+        ));
+
+        if !yes && !utils::confirm(&format!("Replace library '{}' with this backup?", library))? {
+            utils::print_info("Restore cancelled");
+            return Ok(());
+        }
+
+        match database.auto_backup("pre-restore")? {
+            Some(path) => utils::print_info(&format!("Saved the current database to {}", path.display())),
+            None => utils::print_warning("Automatic backups are disabled (backup.keep = 0); the current database is not kept"),
+        }
+        database.restore_from(&snapshot)?;
+
+        utils::print_success(&format!("Restored library '{}' from {}", library, file.display()));
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{
+    database::Database,
+    database::models::SavedSearch,
+    error::Result,
+    cli::{CollectionAction, OutputFormat},
+    utils,
+};
+use chrono::Utc;
+use clap::ValueEnum;
+
+pub struct CollectionCommand;
+
+impl CollectionCommand {
+    pub fn execute(database: &Database, action: CollectionAction, default_format: OutputFormat) -> Result<()> {
+        match action {
+            CollectionAction::Save { name, query, tags, sort, limit } => {
+                let search = SavedSearch {
+                    name: name.clone(),
+                    query,
+                    tags,
+                    sort: sort.and_then(|s| s.to_possible_value()).map(|v| v.get_name().to_string()),
+                    limit,
+                    created_at: Utc::now(),
+                };
+                database.save_search(&search)?;
+                utils::print_success(&format!(
+                    "Saved collection '{}'. Use 'list --collection {}' or 'search --saved {}'",
+                    name, name, name
+                ));
+            }
+            CollectionAction::List { format } => {
+                let format = format.unwrap_or(default_format);
+                let searches = database.list_saved_searches()?;
+                match format {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&searches)?),
+                    _ if searches.is_empty() => println!("No saved searches."),
+                    _ => {
+                        for search in searches {
+                            println!("{}", Self::describe(&search));
+                        }
+                    }
+                }
+            }
+            CollectionAction::Delete { name } => {
+                database.delete_saved_search(&name)?;
+                utils::print_success(&format!("Deleted collection '{}'", name));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn describe(search: &SavedSearch) -> String {
+        let mut parts = Vec::new();
+        if let Some(ref query) = search.query {
+            parts.push(format!("query \"{}\"", query));
+        }
+        if !search.tags.is_empty() {
+            parts.push(format!("tags {}", search.tags.join(", ")));
+        }
+        if let Some(ref sort) = search.sort {
+            parts.push(format!("sort {}", sort));
+        }
+        if let Some(limit) = search.limit {
+            parts.push(format!("limit {}", limit));
+        }
+        if parts.is_empty() {
+            parts.push("all prompts".to_string());
+        }
+        format!("{:<30} {}", search.name, parts.join("; "))
+    }
+}
@@ -4,29 +4,89 @@ use crate::{
 };
 use clap::CommandFactory;
 use clap_complete::{generate, shells};
-use std::io;
+use std::io::{self, Write};
+
+/// Subcommands whose first argument is a prompt name
+const NAME_COMMANDS: &[&str] = &["get", "edit", "delete", "similar", "pin", "unpin", "favorite"];
+
+/// Completes prompt names from the database, pinned first, and defers to
+/// the generated function for everything else
+const BASH_NAMES: &str = r#"
+_edisonprompt_prompt_names() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ ${COMP_CWORD} -eq 2 && "${cur}" != -* ]]; then
+        case "${COMP_WORDS[1]}" in
+            @COMMANDS@)
+                local IFS=$'\n'
+                COMPREPLY=( $(compgen -W "$(edisonprompt list --names-only --pinned 2>/dev/null)" -- "${cur}") )
+                return 0
+                ;;
+        esac
+    fi
+    _edisonprompt "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _edisonprompt_prompt_names -o nosort -o bashdefault -o default edisonprompt
+else
+    complete -F _edisonprompt_prompt_names -o bashdefault -o default edisonprompt
+fi
+"#;
+
+const ZSH_NAMES: &str = r#"
+_edisonprompt_prompt_names() {
+    if (( CURRENT == 3 )) && [[ ${words[2]} == (@COMMANDS@) && ${words[CURRENT]} != -* ]]; then
+        local -a names
+        names=(${(f)"$(edisonprompt list --names-only --pinned 2>/dev/null)"})
+        compadd -V prompts -- $names
+        return
+    fi
+    _edisonprompt "$@"
+}
+
+if [ "$funcstack[1]" = "_edisonprompt" ]; then
+    _edisonprompt_prompt_names "$@"
+else
+    compdef _edisonprompt_prompt_names edisonprompt
+fi
+"#;
+
+const FISH_NAMES: &str = r#"
+complete -c edisonprompt -n "__fish_seen_subcommand_from @COMMANDS@" -f -k -a "(edisonprompt list --names-only --pinned 2>/dev/null)"
+"#;
 
 pub struct CompletionsCommand;
 
 impl CompletionsCommand {
     pub fn execute(shell: Shell) -> Result<()> {
         let mut cmd = Cli::command();
-        
+        let mut script = Vec::new();
+
         match shell {
             Shell::Bash => {
-                generate(shells::Bash, &mut cmd, "edisonprompt", &mut io::stdout());
+                generate(shells::Bash, &mut cmd, "edisonprompt", &mut script);
+                script.extend_from_slice(BASH_NAMES.replace("@COMMANDS@", &NAME_COMMANDS.join("|")).as_bytes());
             }
             Shell::Zsh => {
-                generate(shells::Zsh, &mut cmd, "edisonprompt", &mut io::stdout());
+                generate(shells::Zsh, &mut cmd, "edisonprompt", &mut script);
+                // Replace the generated registration so the wrapper is used
+                let generated = String::from_utf8_lossy(&script).into_owned();
+                let body = match generated.rfind("if [ \"$funcstack[1]\"") {
+                    Some(index) => &generated[..index],
+                    None => generated.as_str(),
+                };
+                script = format!("{}{}", body, ZSH_NAMES.replace("@COMMANDS@", &NAME_COMMANDS.join("|"))).into_bytes();
             }
             Shell::Fish => {
-                generate(shells::Fish, &mut cmd, "edisonprompt", &mut io::stdout());
+                generate(shells::Fish, &mut cmd, "edisonprompt", &mut script);
+                script.extend_from_slice(FISH_NAMES.replace("@COMMANDS@", &NAME_COMMANDS.join(" ")).as_bytes());
             }
             Shell::PowerShell => {
-                generate(shells::PowerShell, &mut cmd, "edisonprompt", &mut io::stdout());
+                generate(shells::PowerShell, &mut cmd, "edisonprompt", &mut script);
             }
         }
-        
+
+        io::stdout().write_all(&script)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,155 @@
+use crate::{
+    cli::ConfigAction,
+    config::{layers, validate, ConfigManager, Issue, Origin},
+    error::{PromptedsError, Result},
+    utils,
+};
+use std::process::Command;
+
+const USER_CONFIG_HEADER: &str = "# EdisonPrompt user settings. Only settings you change need to be listed;\n\
+# see `edisonprompt config list --show-origin` for every effective value.\n";
+
+pub struct ConfigCommand;
+
+impl ConfigCommand {
+    pub fn execute(config_manager: &mut ConfigManager, action: ConfigAction) -> Result<()> {
+        match action {
+            ConfigAction::Get { key, show_origin } => {
+                Self::check_key(&key)?;
+                let layers = config_manager.layers();
+                match layers.get(&key) {
+                    Some(value) if show_origin => {
+                        let origin = layers.origin(&key).map(|o| o.to_string()).unwrap_or_default();
+                        println!("{}\t{}", origin, layers::display_value(value));
+                    }
+                    Some(value) => println!("{}", layers::display_value(value)),
+                    None => utils::print_info(&format!("{} is not set", key)),
+                }
+            }
+            ConfigAction::Set { key, value } => {
+                let template = Self::check_key(&key)?;
+                let value = layers::parse_value(&key, &value, Some(&template))?;
+                config_manager.set_user_value(&key, value)?;
+
+                utils::print_success(&format!(
+                    "Set {} in {}",
+                    key,
+                    config_manager.user_config_file().display()
+                ));
+                Self::warn_if_overridden(config_manager, &key);
+            }
+            ConfigAction::Unset { key } => {
+                if config_manager.unset_user_value(&key)? {
+                    utils::print_success(&format!(
+                        "Removed {} from {}",
+                        key,
+                        config_manager.user_config_file().display()
+                    ));
+                    Self::warn_if_overridden(config_manager, &key);
+                } else {
+                    utils::print_info(&format!("{} is not set in the user config file", key));
+                }
+            }
+            ConfigAction::List { show_origin } => {
+                let entries = config_manager.layers().entries();
+                let origins: Vec<String> = entries.iter().map(|(_, _, origin)| origin.to_string()).collect();
+                let width = origins.iter().map(|o| o.len()).max().unwrap_or(0);
+
+                for ((key, value, _), origin) in entries.iter().zip(&origins) {
+                    if show_origin {
+                        println!("{:<width$}  {} = {}", origin, key, value, width = width);
+                    } else {
+                        println!("{} = {}", key, value);
+                    }
+                }
+            }
+            ConfigAction::Edit => Self::edit(config_manager)?,
+            ConfigAction::Validate => Self::validate(config_manager)?,
+            ConfigAction::Path => {
+                for (layer, path) in config_manager.config_files() {
+                    let missing = if path.exists() { "" } else { " (not found)" };
+                    println!("{:<8} {}{}", layer, path.display(), missing);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The template of a known setting, for parsing values typed for it
+    fn check_key(key: &str) -> Result<toml::Value> {
+        let schema = validate::schema()?;
+        validate::template(&schema, key).ok_or_else(|| PromptedsError::ConfigValidation {
+            issues: vec![Issue::error(None, validate::unknown_key_message(&schema, key))],
+        })
+    }
+
+    fn validate(config_manager: &ConfigManager) -> Result<()> {
+        let (errors, warnings): (Vec<Issue>, Vec<Issue>) =
+            config_manager.validate(true).into_iter().partition(Issue::is_error);
+        for warning in &warnings {
+            utils::print_warning(&warning.to_string());
+        }
+        if !errors.is_empty() {
+            return Err(PromptedsError::ConfigValidation { issues: errors });
+        }
+
+        let files: Vec<String> = config_manager
+            .config_files()
+            .into_iter()
+            .filter(|(_, path)| path.exists())
+            .map(|(_, path)| path.display().to_string())
+            .collect();
+        if files.is_empty() {
+            utils::print_success("Configuration is valid (built-in defaults only)");
+        } else {
+            utils::print_success(&format!("Configuration is valid: {}", files.join(", ")));
+        }
+        Ok(())
+    }
+
+    /// A user setting has no effect while a higher layer sets the same key
+    fn warn_if_overridden(config_manager: &ConfigManager, key: &str) {
+        if let Some(origin @ (Origin::Project(_) | Origin::Env(_) | Origin::CommandLine(_))) =
+            config_manager.layers().origin(key)
+        {
+            utils::print_warning(&format!("{} is overridden by {}", key, origin));
+        }
+    }
+
+    fn edit(config_manager: &mut ConfigManager) -> Result<()> {
+        let path = config_manager.user_config_file().to_path_buf();
+        if !path.exists() {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, USER_CONFIG_HEADER)?;
+        }
+
+        let editor_config = &config_manager.config().editor;
+        let env_editor = std::env::var("EDITOR").ok();
+        let editor = editor_config
+            .command
+            .clone()
+            .or(env_editor)
+            .unwrap_or_else(|| editor_config.fallback.clone());
+
+        let status = Command::new(&editor)
+            .args(&editor_config.args)
+            .arg(&path)
+            .status()
+            .map_err(|e| PromptedsError::EditorError {
+                details: format!("Failed to launch editor '{}': {}", editor, e),
+            })?;
+        if !status.success() {
+            return Err(PromptedsError::EditorError {
+                details: format!("Editor exited with non-zero status: {}", status),
+            });
+        }
+
+        // Report mistakes now rather than on the next command
+        config_manager.reload_user_config()?;
+        utils::print_success(&format!("Saved {}", path.display()));
+        Ok(())
+    }
+}
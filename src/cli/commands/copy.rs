@@ -0,0 +1,131 @@
+use crate::{
+    database::Database,
+    error::{PromptedsError, Result},
+    utils,
+};
+
+pub struct CopyCommand;
+
+impl CopyCommand {
+    /// Copy prompts from `database` into `target`, keeping their ids unless
+    /// the target already uses one for a different prompt
+    pub fn execute(
+        database: &mut Database,
+        target: &mut Database,
+        target_name: &str,
+        names: Vec<String>,
+        move_prompts: bool,
+        force: bool,
+    ) -> Result<()> {
+        // The target seals prompts with its own key, so an unencrypted one
+        // would store prompts that were only ever kept encrypted in plaintext
+        if database.is_encrypted()? && !target.is_encrypted()? {
+            return Err(PromptedsError::EncryptionError {
+                details: format!(
+                    "library '{}' is not encrypted and would store these prompts in plaintext; \
+                     run 'edisonprompt --library {} encryption enable' first",
+                    target_name, target_name
+                ),
+            });
+        }
+        
+        // Resolve every name first so a typo does not leave a partial copy
+        let mut prompts = Vec::new();
+        for name in names {
+            let name = database.resolve_prompt_name(&name)?;
+            if move_prompts {
+                database.ensure_in_library(&name)?;
+            }
+            prompts.push(database.get_prompt(&name)?);
+        }
+        
+        for mut prompt in prompts {
+            let replace = target.prompt_exists(&prompt.name)?;
+            if replace && !force {
+                return Err(PromptedsError::PromptAlreadyExists { name: prompt.name });
+            }
+            if let Ok(owner) = target.get_prompt_by_id(&prompt.id) {
+                if !(replace && owner.name == prompt.name) {
+                    prompt.id = uuid::Uuid::new_v4();
+                }
+            }
+            
+            if replace {
+                target.replace_prompt(&prompt.name, &prompt)?;
+            } else {
+                target.create_prompt(&prompt)?;
+            }
+            
+            if move_prompts {
+                database.delete_prompt(&prompt.name)?;
+                utils::print_success(&format!("Moved '{}' to library '{}'", prompt.name, target_name));
+            } else {
+                utils::print_success(&format!("Copied '{}' to library '{}'", prompt.name, target_name));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Prompt;
+
+    fn library(prompts: &[(&str, &str)]) -> Database {
+        let mut database = Database::open_in_memory().unwrap();
+        for (name, content) in prompts {
+            database.create_prompt(&Prompt::new(name.to_string(), content.to_string())).unwrap();
+        }
+        database
+    }
+
+    #[test]
+    fn test_copy_keeps_ids_and_move_removes_the_source() {
+        let mut source = library(&[("review", "Review {{code}}"), ("summary", "Summarize")]);
+        let mut target = library(&[]);
+        let id = source.get_prompt("review").unwrap().id;
+
+        CopyCommand::execute(&mut source, &mut target, "work", vec!["rev".to_string()], false, false).unwrap();
+        assert_eq!(target.get_prompt("review").unwrap().id, id);
+        assert!(source.prompt_exists("review").unwrap());
+
+        CopyCommand::execute(&mut source, &mut target, "work", vec!["summary".to_string()], true, false).unwrap();
+        assert!(target.prompt_exists("summary").unwrap());
+        assert!(!source.prompt_exists("summary").unwrap());
+    }
+
+    #[test]
+    fn test_copy_replaces_only_with_force() {
+        let mut source = library(&[("review", "New review")]);
+        let mut target = library(&[("review", "Old review"), ("other", "Other")]);
+        // The target already uses the source prompt's id for a different prompt
+        let mut other = target.get_prompt("other").unwrap();
+        other.id = source.get_prompt("review").unwrap().id;
+        target.replace_prompt("other", &other).unwrap();
+
+        let names = vec!["review".to_string()];
+        let error = CopyCommand::execute(&mut source, &mut target, "work", names.clone(), false, false).unwrap_err();
+        assert!(matches!(error, PromptedsError::PromptAlreadyExists { .. }));
+        assert_eq!(target.get_prompt("review").unwrap().content, "Old review");
+
+        CopyCommand::execute(&mut source, &mut target, "work", names, false, true).unwrap();
+        let copied = target.get_prompt("review").unwrap();
+        assert_eq!(copied.content, "New review");
+        assert_ne!(copied.id, other.id);
+        assert_eq!(target.get_prompt("other").unwrap().id, other.id);
+    }
+
+    #[test]
+    fn test_encrypted_prompts_are_not_copied_into_plaintext() {
+        let mut source = library(&[("client-brief", "Write to Acme")]);
+        source.enable_encryption("correct horse").unwrap();
+        let mut target = library(&[]);
+
+        let error = CopyCommand::execute(&mut source, &mut target, "work", vec!["client-brief".to_string()], false, false)
+            .unwrap_err();
+        assert!(matches!(error, PromptedsError::EncryptionError { .. }));
+        assert!(!target.prompt_exists("client-brief").unwrap());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{
+    database::Database,
+    database::dedupe::{self, DuplicateGroup, DuplicateKind},
+    error::Result,
+    cli::OutputFormat,
+    utils,
+};
+use colored::*;
+use std::io::{self, Write};
+
+pub struct DedupeCommand;
+
+impl DedupeCommand {
+    pub fn execute(
+        database: &mut Database,
+        threshold: f64,
+        merge: bool,
+        yes: bool,
+        format: OutputFormat,
+        color: bool,
+    ) -> Result<()> {
+        let prompts = database.get_all_prompts()?;
+        let groups = dedupe::find_duplicates(&prompts, threshold);
+        
+        if groups.is_empty() {
+            utils::print_success("No duplicate prompts found");
+            return Ok(());
+        }
+        
+        if let OutputFormat::Json = format {
+            println!("{}", serde_json::to_string_pretty(&groups)?);
+            if !merge {
+                return Ok(());
+            }
+        }
+        
+        let mut merged_groups = 0;
+        let mut removed = 0;
+        
+        for (index, group) in groups.iter().enumerate() {
+            if !matches!(format, OutputFormat::Json) {
+                Self::print_group(index + 1, group, color);
+            }
+            
+            if !merge {
+                continue;
+            }
+            
+            let keep_index = if yes {
+                0
+            } else {
+                match Self::choose_keeper(group)? {
+                    Some(keep_index) => keep_index,
+                    None => {
+                        utils::print_info("Group skipped");
+                        continue;
+                    }
+                }
+            };
+            
+            let keep = &group.prompts[keep_index];
+            let others: Vec<_> = group.prompts.iter()
+                .enumerate()
+                .filter(|(i, _)| *i != keep_index)
+                .map(|(_, p)| p.clone())
+                .collect();
+            
+            let merged = dedupe::merge_group(keep, &others);
+            database.merge_prompts(&merged, &others)?;
+            
+            utils::print_success(&format!(
+                "Kept '{}', removed {}",
+                merged.name,
+                others.iter().map(|p| format!("'{}'", p.name)).collect::<Vec<_>>().join(", ")
+            ));
+            merged_groups += 1;
+            removed += others.len();
+        }
+        
+        if merge {
+            utils::print_success(&format!(
+                "Merged {} group(s), removed {} prompt(s)",
+                merged_groups, removed
+            ));
+        } else {
+            println!(
+                "\nFound {} group(s) of duplicates. Run with --merge to combine them.",
+                groups.len()
+            );
+        }
+        
+        Ok(())
+    }
+    
+    fn print_group(number: usize, group: &DuplicateGroup, color: bool) {
+        let kind = match group.kind {
+            DuplicateKind::Exact => "exact duplicates".to_string(),
+            DuplicateKind::Near => format!("near duplicates, {:.0}% similar", group.similarity * 100.0),
+        };
+        let heading = format!("Group {} ({})", number, kind);
+        if color {
+            println!("\n{}", heading.bold());
+        } else {
+            println!("\n{}", heading);
+        }
+        
+        for (i, prompt) in group.prompts.iter().enumerate() {
+            println!(
+                "  {}. {} (created {}, tags: {})",
+                i + 1,
+                prompt.name,
+                prompt.created_at.format("%Y-%m-%d"),
+                if prompt.tags.is_empty() { "-".to_string() } else { prompt.tags.join(", ") }
+            );
+        }
+        
+        // Exact duplicates can still differ in whitespace or case
+        let first = &group.prompts[0];
+        for other in &group.prompts[1..] {
+            if other.content != first.content {
+                println!("{}", utils::format_diff(&first.content, &other.content, &first.name, &other.name, color));
+            }
+        }
+    }
+    
+    /// Ask which prompt of the group to keep; `None` skips the group
+    fn choose_keeper(group: &DuplicateGroup) -> Result<Option<usize>> {
+        loop {
+            print!("Keep which prompt? [1-{}, s to skip] (1): ", group.prompts.len());
+            io::stdout().flush()?;
+            
+            let mut input = String::new();
+            io::stdin().read_line(&mut input)?;
+            let input = input.trim();
+            
+            if input.is_empty() {
+                return Ok(Some(0));
+            }
+            if input.eq_ignore_ascii_case("s") {
+                return Ok(None);
+            }
+            match input.parse::<usize>() {
+                Ok(n) if (1..=group.prompts.len()).contains(&n) => return Ok(Some(n - 1)),
+                _ => utils::print_warning("Please enter a number from the list"),
+            }
+        }
+    }
+}
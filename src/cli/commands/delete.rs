@@ -14,14 +14,13 @@ impl DeleteCommand {
         force: bool,
     ) -> Result<()> {
         // Check if prompt exists first
+        database.ensure_in_library(&name)?;
         let _prompt = database.get_prompt(&name)?;
         
         // Confirm deletion unless --yes or --force is used
-        if !yes && !force {
-            if !utils::confirm(&format!("Delete prompt '{}'? This cannot be undone", name))? {
-                utils::print_info("Delete cancelled");
-                return Ok(());
-            }
+        if !yes && !force && !utils::confirm(&format!("Delete prompt '{}'? This cannot be undone", name))? {
+            utils::print_info("Delete cancelled");
+            return Ok(());
         }
         
         // Delete the prompt
@@ -0,0 +1,385 @@
+use crate::{
+    cli::OutputFormat,
+    clipboard::ClipboardManager,
+    config::{validate, ConfigManager},
+    database::{schema, Database},
+    error::{PromptedsError, Result},
+    utils,
+};
+use colored::*;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    pub section: &'static str,
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+struct Report {
+    checks: Vec<Check>,
+    fixes: Vec<String>,
+}
+
+pub struct DoctorCommand;
+
+impl DoctorCommand {
+    /// `open` opens the active library; it is only called when the
+    /// database file exists, so the doctor never creates one
+    pub fn execute(
+        config_manager: &ConfigManager,
+        open: impl FnOnce() -> Result<Database>,
+        fix: bool,
+        format: OutputFormat,
+        color: bool,
+    ) -> Result<()> {
+        let mut report = Report { checks: Vec::new(), fixes: Vec::new() };
+        Self::check_config(config_manager, &mut report.checks);
+        Self::check_database(config_manager, open, fix, &mut report)?;
+        Self::check_clipboard(config_manager, &mut report.checks);
+        Self::check_editor(config_manager, &mut report.checks);
+
+        if let OutputFormat::Json = format {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            Self::print_report(&report, color);
+        }
+
+        let failed = report.checks.iter().filter(|c| c.status == Status::Error).count();
+        if failed > 0 {
+            return Err(PromptedsError::HealthCheckFailed { count: failed });
+        }
+        Ok(())
+    }
+
+    fn check_config(config_manager: &ConfigManager, checks: &mut Vec<Check>) {
+        let mut check = |name: &str, status, detail: String| {
+            checks.push(Check { section: "Configuration", name: name.to_string(), status, detail });
+        };
+
+        for (layer, path) in config_manager.config_files() {
+            let missing = if path.exists() { "" } else { " (not found)" };
+            check(layer, Status::Ok, format!("{}{}", path.display(), missing));
+        }
+
+        // The editor is checked in its own section
+        let issues = config_manager.validate(false);
+        if issues.is_empty() {
+            check("settings", Status::Ok, "valid".to_string());
+        }
+        for issue in issues {
+            let status = if issue.is_error() { Status::Error } else { Status::Warning };
+            check("settings", status, issue.to_string());
+        }
+    }
+
+    fn check_database(
+        config_manager: &ConfigManager,
+        open: impl FnOnce() -> Result<Database>,
+        fix: bool,
+        report: &mut Report,
+    ) -> Result<()> {
+        let checks = &mut report.checks;
+        let mut check = |name: &str, status, detail: String| {
+            checks.push(Check { section: "Database", name: name.to_string(), status, detail });
+        };
+
+        let library = config_manager.active_library();
+        let path = match config_manager.library_path(&library) {
+            Ok(path) => path,
+            Err(e) => {
+                check("library", Status::Error, e.to_string());
+                return Ok(());
+            }
+        };
+        check("library", Status::Ok, library);
+        check("path", Status::Ok, path.display().to_string());
+        Self::check_database_file(&path, open, fix, report)
+    }
+
+    /// Check the database file at `path`, rebuilding its search index and
+    /// compacting it first if `fix` is set
+    fn check_database_file(
+        path: &Path,
+        open: impl FnOnce() -> Result<Database>,
+        fix: bool,
+        report: &mut Report,
+    ) -> Result<()> {
+        let checks = &mut report.checks;
+        let mut check = |name: &str, status, detail: String| {
+            checks.push(Check { section: "Database", name: name.to_string(), status, detail });
+        };
+
+        if !path.exists() {
+            check("file", Status::Warning, "not created yet; it is created on first use".to_string());
+            return Ok(());
+        }
+        if let Some(problem) = permission_problem(path) {
+            check("permissions", Status::Error, problem);
+        } else {
+            check("permissions", Status::Ok, "readable and writable".to_string());
+        }
+
+        // Inspect through a plain connection, as opening the library
+        // normally would migrate it. It is not read-only only because
+        // checking the full-text index needs to write temporary data.
+        let conn = match Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE) {
+            Ok(conn) => conn,
+            Err(e) => {
+                check("open", Status::Error, e.to_string());
+                return Ok(());
+            }
+        };
+
+        if fix {
+            let version = schema::get_schema_version(&conn)?;
+            let database = match open() {
+                Ok(database) => database,
+                Err(e) => {
+                    check("open", Status::Error, e.to_string());
+                    return Ok(());
+                }
+            };
+            if version < schema::SCHEMA_VERSION {
+                report.fixes.push(format!("migrated the schema from version {} to {}", version, schema::SCHEMA_VERSION));
+            }
+            database.rebuild_search_index()?;
+            report.fixes.push("rebuilt the search index".to_string());
+            let before = std::fs::metadata(path)?.len();
+            database.vacuum()?;
+            let after = std::fs::metadata(path)?.len();
+            report.fixes.push(format!(
+                "vacuumed the database ({} -> {})",
+                utils::format_bytes(before),
+                utils::format_bytes(after)
+            ));
+        }
+
+        let size = std::fs::metadata(path)?.len();
+        check("size", Status::Ok, utils::format_bytes(size));
+
+        let problems = match schema::integrity_problems(&conn) {
+            Ok(problems) => problems,
+            Err(e) => {
+                check("integrity", Status::Error, format!("not a readable SQLite database ({})", e));
+                return Ok(());
+            }
+        };
+        if problems.is_empty() {
+            check("integrity", Status::Ok, "ok".to_string());
+        }
+        for problem in problems {
+            check("integrity", Status::Error, problem);
+        }
+
+        let version = schema::get_schema_version(&conn)?;
+        if version == schema::SCHEMA_VERSION {
+            check("schema version", Status::Ok, format!("{} (current)", version));
+        } else if version < schema::SCHEMA_VERSION {
+            check(
+                "schema version",
+                Status::Warning,
+                format!(
+                    "{}, expected {}; it is migrated on next use, or run `doctor --fix`",
+                    version,
+                    schema::SCHEMA_VERSION
+                ),
+            );
+        } else {
+            check(
+                "schema version",
+                Status::Error,
+                format!("{} is newer than this edisonprompt supports ({})", version, schema::SCHEMA_VERSION),
+            );
+        }
+
+        let (prompts, indexed) = schema::search_index_counts(&conn)?;
+        if prompts == indexed {
+            check("search index", Status::Ok, format!("{} prompt(s) indexed", prompts));
+        } else {
+            check(
+                "search index",
+                Status::Error,
+                format!("{} prompt(s) but {} indexed; run `doctor --fix` to rebuild", prompts, indexed),
+            );
+        }
+        Ok(())
+    }
+
+    fn check_clipboard(config_manager: &ConfigManager, checks: &mut Vec<Check>) {
+        let config = &config_manager.config().clipboard;
+        let clipboard = ClipboardManager::new(config.timeout_ms, config.enable_fallback);
+        let (status, detail) = if clipboard.has_system_clipboard() {
+            (Status::Ok, "system clipboard".to_string())
+        } else if clipboard.is_available() {
+            (Status::Warning, format!(
+                "system clipboard unavailable; using the file {}",
+                std::env::temp_dir().join("edisonprompt_clipboard.txt").display()
+            ))
+        } else {
+            (Status::Error, "system clipboard unavailable and clipboard.enable_fallback is off".to_string())
+        };
+        checks.push(Check { section: "Clipboard", name: "backend".to_string(), status, detail });
+    }
+
+    fn check_editor(config_manager: &ConfigManager, checks: &mut Vec<Check>) {
+        let config = &config_manager.config().editor;
+        let env_editor = std::env::var("EDITOR").ok();
+        let (editor, source) = match (&config.command, &env_editor) {
+            (Some(command), _) => (command.clone(), "editor.command"),
+            (None, Some(editor)) => (editor.clone(), "$EDITOR"),
+            (None, None) => (config.fallback.clone(), "editor.fallback"),
+        };
+
+        let program = editor.split_whitespace().next().unwrap_or(&editor);
+        let (status, detail) = match validate::find_program(program) {
+            Some(found) => (Status::Ok, format!("{} (from {}, {})", editor, source, found.display())),
+            None => (Status::Warning, format!("{} (from {}) was not found on PATH", editor, source)),
+        };
+        checks.push(Check { section: "Editor", name: "editor".to_string(), status, detail });
+        checks.push(Check {
+            section: "Editor",
+            name: "$EDITOR".to_string(),
+            status: Status::Ok,
+            detail: env_editor.unwrap_or_else(|| "not set".to_string()),
+        });
+    }
+
+    fn print_report(report: &Report, color: bool) {
+        for fix in &report.fixes {
+            utils::print_success(&format!("Fixed: {}", fix));
+        }
+
+        let width = report.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        let mut section = "";
+        for check in &report.checks {
+            if check.section != section {
+                section = check.section;
+                if color {
+                    println!("\n{}", section.bold());
+                } else {
+                    println!("\n{}", section);
+                }
+            }
+
+            let symbol = match (check.status, color) {
+                (Status::Ok, true) => "✓".green().to_string(),
+                (Status::Warning, true) => "!".yellow().to_string(),
+                (Status::Error, true) => "✗".red().to_string(),
+                (Status::Ok, false) => "✓".to_string(),
+                (Status::Warning, false) => "!".to_string(),
+                (Status::Error, false) => "✗".to_string(),
+            };
+            println!("  {} {:<width$}  {}", symbol, check.name, check.detail, width = width);
+        }
+
+        let warnings = report.checks.iter().filter(|c| c.status == Status::Warning).count();
+        if warnings > 0 {
+            println!();
+            utils::print_warning(&format!("{} warning(s)", warnings));
+        }
+    }
+}
+
+/// Why SQLite could not write `path`: it needs the file and, for its
+/// journal, the directory
+fn permission_problem(path: &Path) -> Option<String> {
+    if let Err(e) = std::fs::OpenOptions::new().read(true).write(true).open(path) {
+        return Some(format!("cannot open {} for writing: {}", path.display(), e));
+    }
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    if let Err(e) = tempfile::tempfile_in(dir) {
+        return Some(format!("cannot create files in {}: {}", dir.display(), e));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(path: &Path, fix: bool) -> Report {
+        let mut report = Report { checks: Vec::new(), fixes: Vec::new() };
+        DoctorCommand::check_database_file(path, || Database::new(path), fix, &mut report).unwrap();
+        report
+    }
+
+    fn status(report: &Report, name: &str) -> Status {
+        report.checks.iter().find(|c| c.name == name).unwrap().status
+    }
+
+    #[test]
+    fn test_permission_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.db");
+        assert!(permission_problem(&path).unwrap().starts_with("cannot open"));
+
+        std::fs::write(&path, "").unwrap();
+        assert_eq!(permission_problem(&path), None);
+        // Not even root can open a directory for writing
+        assert!(permission_problem(dir.path()).unwrap().starts_with("cannot open"));
+    }
+
+    #[test]
+    fn test_fix_rebuilds_a_stale_search_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.db");
+        let mut report = Report { checks: Vec::new(), fixes: Vec::new() };
+        DoctorCommand::check_database_file(&path, || unreachable!("the file does not exist"), false, &mut report).unwrap();
+        assert_eq!(status(&report, "file"), Status::Warning);
+
+        let mut database = Database::new(&path).unwrap();
+        database.create_prompt(&crate::models::Prompt::new("review".to_string(), "Review".to_string())).unwrap();
+        drop(database);
+        let healthy = run(&path, false);
+        assert!(healthy.checks.iter().all(|c| c.status == Status::Ok));
+
+        rusqlite::Connection::open(&path).unwrap().execute("DELETE FROM prompts_fts_docsize", []).unwrap();
+        let stale = run(&path, false);
+        assert_eq!(status(&stale, "search index"), Status::Error);
+        assert!(stale.fixes.is_empty());
+
+        let fixed = run(&path, true);
+        assert_eq!(fixed.fixes.len(), 2);
+        assert_eq!(status(&fixed, "search index"), Status::Ok);
+        assert_eq!(status(&run(&path, false), "search index"), Status::Ok);
+    }
+
+    #[test]
+    fn test_outdated_schema_is_reported_and_migrated_only_with_fix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(schema::INIT_SQL).unwrap();
+            for (_, sql) in schema::MIGRATIONS.iter().filter(|(version, _)| *version <= 3) {
+                conn.execute_batch(sql).unwrap();
+            }
+            conn.execute("INSERT INTO prompts (id, name, content) VALUES ('1', 'a', 'hello')", []).unwrap();
+        }
+        let contents = std::fs::read(&path).unwrap();
+
+        let report = run(&path, false);
+        let version = report.checks.iter().find(|c| c.name == "schema version").unwrap();
+        assert_eq!(version.status, Status::Warning);
+        assert!(version.detail.starts_with(&format!("3, expected {}", schema::SCHEMA_VERSION)), "{}", version.detail);
+        assert_eq!(status(&report, "search index"), Status::Ok);
+        assert_eq!(std::fs::read(&path).unwrap(), contents);
+
+        let fixed = run(&path, true);
+        assert!(fixed.fixes[0].starts_with("migrated the schema from version 3"), "{}", fixed.fixes[0]);
+        assert_eq!(status(&fixed, "schema version"), Status::Ok);
+    }
+}
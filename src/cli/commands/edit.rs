@@ -1,7 +1,7 @@
 use crate::{
-    database::Database,
+    database::{models::Prompt, Database},
     error::{PromptedsError, Result},
-    template::TemplateEngine,
+    template::{scan::Scanner, TemplateEngine},
     utils,
 };
 use std::process::Command;
@@ -16,46 +16,24 @@ impl EditCommand {
         database: &mut Database,
         editor_command: Option<&str>,
         fallback_editor: &str,
+        scanner: &Scanner,
         name: String,
         yes: bool,
+        color: bool,
     ) -> Result<()> {
-        // Get the prompt
+        // Get the prompt; project prompts are edited in their own file
+        database.ensure_in_library(&name)?;
         let mut prompt = database.get_prompt(&name)?;
-        
-        // Determine editor command
-        let env_editor = std::env::var("EDITOR").ok();
-        let editor = editor_command
-            .or_else(|| env_editor.as_deref())
-            .unwrap_or(fallback_editor);
-        
-        // Create temporary file with content
-        let mut temp_file = NamedTempFile::new()?;
-        temp_file.write_all(prompt.content.as_bytes())?;
-        let temp_path = temp_file.path().to_path_buf();
-        
-        // Open editor
-        let status = Command::new(editor)
-            .arg(&temp_path)
-            .status()
-            .map_err(|e| PromptedsError::EditorError {
-                details: format!("Failed to launch editor '{}': {}", editor, e),
-            })?;
-        
-        if !status.success() {
-            return Err(PromptedsError::EditorError {
-                details: format!("Editor exited with non-zero status: {}", status),
-            });
-        }
-        
-        // Read the modified content
-        let new_content = fs::read_to_string(&temp_path)?;
-        
+
+        let editor = Self::editor(editor_command, fallback_editor);
+        let new_content = Self::run_editor(&editor, &prompt.content)?;
+
         // Check if content changed
         if new_content == prompt.content {
             utils::print_info("No changes made to prompt");
             return Ok(());
         }
-        
+
         // Confirm changes if not using --yes
         if !yes {
             println!("Content changed. Preview:");
@@ -63,49 +41,161 @@ impl EditCommand {
             println!("{}", prompt.content);
             println!("--- Modified ---");
             println!("{}", new_content);
-            
+
             if !utils::confirm("Save changes?")? {
                 utils::print_info("Changes discarded");
                 return Ok(());
             }
         }
-        
-        // Update prompt content
-        prompt.content = new_content.trim().to_string();
-        prompt.updated_at = chrono::Utc::now();
-        
-        // Re-extract variables
-        let template_engine = TemplateEngine::new();
-        let variable_names = template_engine.extract_variables(&prompt.content)?;
-        
-        // Preserve existing variable descriptions and defaults
-        let existing_vars: std::collections::HashMap<String, _> = prompt.variables
-            .into_iter()
-            .map(|v| (v.name.clone(), v))
-            .collect();
-        
-        prompt.variables = variable_names.into_iter()
-            .map(|name| {
-                existing_vars.get(&name)
-                    .cloned()
-                    .unwrap_or_else(|| crate::database::models::Variable::new(name))
-            })
-            .collect();
-        
-        // Save changes
-        database.update_prompt(&prompt)?;
-        
+
+        // Refuse or warn about secrets pasted into the content
+        let mut edited = new_content.trim().to_string();
+        if let Err(e) = scanner.check(&Self::with_content(&prompt, &edited)?).map(|findings| {
+            for finding in findings {
+                utils::print_warning(&format!("Possible {}", finding));
+            }
+        }) {
+            Self::keep_draft(&name, &edited)?;
+            return Err(e);
+        }
+
+        // Save only over the version that was opened; if it was saved
+        // elsewhere meanwhile, merge the two edits and try again
+        let updated = loop {
+            let updated = Self::with_content(&prompt, &edited)?;
+            match database.update_prompt_if_unchanged(&updated, &prompt.updated_at) {
+                Ok(()) => break updated,
+                Err(PromptedsError::PromptConflict { .. }) => {}
+                Err(e) => return Err(e),
+            }
+
+            let current = match database.get_prompt(&name) {
+                Ok(current) => current,
+                Err(e) => {
+                    Self::keep_draft(&name, &edited)?;
+                    return Err(e);
+                }
+            };
+            utils::print_warning(&format!(
+                "'{}' was saved elsewhere at {} while you were editing it",
+                name,
+                current.updated_at.format("%Y-%m-%d %H:%M:%S")
+            ));
+
+            let (merged, conflicts) =
+                utils::merge3(&prompt.content, &edited, &current.content, "your edit", "saved version");
+            let merged = if conflicts == 0 {
+                println!("Your changes merge cleanly with the saved version:");
+                println!("{}", utils::format_diff(&current.content, &merged, "saved", "merged", color));
+                if !yes && !utils::confirm("Save the merged version?")? {
+                    Self::keep_draft(&name, &edited)?;
+                    return Err(PromptedsError::PromptConflict { name });
+                }
+                merged
+            } else if yes {
+                // Conflicts need a person to resolve them
+                utils::print_info(&format!(
+                    "{} conflicting change(s); run edit without --yes to resolve them",
+                    conflicts
+                ));
+                Self::keep_draft(&name, &edited)?;
+                return Err(PromptedsError::PromptConflict { name });
+            } else {
+                utils::print_info(&format!(
+                    "{} conflicting change(s) are marked in the editor; resolve them and save",
+                    conflicts
+                ));
+                let resolved = Self::run_editor(&editor, &merged)?;
+                if resolved.lines().any(|line| line.starts_with(utils::CONFLICT_START)) {
+                    utils::print_error("Conflict markers remain, not saving");
+                    Self::keep_draft(&name, &edited)?;
+                    return Err(PromptedsError::PromptConflict { name });
+                }
+                resolved
+            };
+
+            edited = merged.trim().to_string();
+            prompt = current;
+        };
+
         utils::print_success(&format!("Updated prompt '{}'", name));
-        if !prompt.variables.is_empty() {
+        if !updated.variables.is_empty() {
             utils::print_info(&format!(
-                "Variables: {}", 
-                prompt.variables.iter()
+                "Variables: {}",
+                updated.variables.iter()
                     .map(|v| v.name.as_str())
                     .collect::<Vec<_>>()
                     .join(", ")
             ));
         }
-        
+
+        Ok(())
+    }
+
+    /// The editor to run: `editor.command`, else `$EDITOR`, else
+    /// `editor.fallback`
+    pub fn editor(editor_command: Option<&str>, fallback_editor: &str) -> String {
+        let env_editor = std::env::var("EDITOR").ok();
+        editor_command
+            .or(env_editor.as_deref())
+            .unwrap_or(fallback_editor)
+            .to_string()
+    }
+
+    /// Open `content` in the editor and return the text it was saved with
+    pub fn run_editor(editor: &str, content: &str) -> Result<String> {
+        // Create temporary file with content
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(content.as_bytes())?;
+        let temp_path = temp_file.path().to_path_buf();
+
+        // Open editor
+        let status = Command::new(editor)
+            .arg(&temp_path)
+            .status()
+            .map_err(|e| PromptedsError::EditorError {
+                details: format!("Failed to launch editor '{}': {}", editor, e),
+            })?;
+
+        if !status.success() {
+            return Err(PromptedsError::EditorError {
+                details: format!("Editor exited with non-zero status: {}", status),
+            });
+        }
+
+        Ok(fs::read_to_string(&temp_path)?)
+    }
+
+    /// `prompt` with new content, keeping the descriptions and defaults of
+    /// variables that are still used
+    pub fn with_content(prompt: &Prompt, content: &str) -> Result<Prompt> {
+        let mut updated = prompt.clone();
+        updated.content = content.to_string();
+        updated.updated_at = chrono::Utc::now();
+
+        let template_engine = TemplateEngine::new();
+        let variable_names = template_engine.extract_variables(&updated.content)?;
+        updated.variables = variable_names.into_iter()
+            .map(|name| {
+                prompt.variables.iter()
+                    .find(|v| v.name == name)
+                    .cloned()
+                    .unwrap_or_else(|| crate::database::models::Variable::new(name))
+            })
+            .collect();
+
+        Ok(updated)
+    }
+
+    /// Save an edit that could not be stored, so it is not lost
+    fn keep_draft(name: &str, content: &str) -> Result<()> {
+        let mut draft = tempfile::Builder::new()
+            .prefix(&format!("edisonprompt-{}-", name))
+            .suffix(".txt")
+            .tempfile()?;
+        draft.write_all(content.as_bytes())?;
+        let (_, path) = draft.keep().map_err(|e| e.error)?;
+        utils::print_info(&format!("Your version was kept in {}", path.display()));
         Ok(())
     }
-}
\ No newline at end of file
+}
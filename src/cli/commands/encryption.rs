@@ -0,0 +1,55 @@
+use crate::{
+    cli::EncryptionAction,
+    database::crypto::{self, KEY_ENV},
+    database::Database,
+    error::Result,
+    utils,
+};
+use std::io::{self, IsTerminal};
+
+pub struct EncryptionCommand;
+
+impl EncryptionCommand {
+    pub fn execute(database: &mut Database, action: EncryptionAction) -> Result<()> {
+        match action {
+            EncryptionAction::Enable => {
+                let passphrase = crypto::passphrase("New passphrase", true)?;
+                let count = database.enable_encryption(&passphrase)?;
+                utils::print_success(&format!("Encrypted {} prompt(s)", count));
+                utils::print_info("Prompt names and tags stay searchable; content is searched only once decrypted");
+                utils::print_info("Backups taken before now are not encrypted");
+            }
+            EncryptionAction::Disable => {
+                let count = database.disable_encryption()?;
+                utils::print_success(&format!("Decrypted {} prompt(s)", count));
+            }
+            EncryptionAction::Status => match database.encryption_params()? {
+                None => println!("Encryption: off"),
+                Some(params) => {
+                    println!(
+                        "Encryption: on (XChaCha20-Poly1305, key from Argon2id with {} MiB, {} iteration(s))",
+                        params.memory_kib / 1024,
+                        params.iterations
+                    );
+                    if database.unlocked_by_env()? {
+                        println!("Unlocked in this shell through {}", KEY_ENV);
+                    } else {
+                        println!("Locked: commands that read prompt content ask for the passphrase");
+                    }
+                }
+            },
+            EncryptionAction::Unlock => {
+                let key = database.unlock()?.to_hex();
+                println!("export {}={}", KEY_ENV, key);
+                if io::stdout().is_terminal() {
+                    utils::eprint_warning(&format!(
+                        "This is the key itself; run eval \"$(edisonprompt encryption unlock)\" to set {} without showing it",
+                        KEY_ENV
+                    ));
+                }
+            }
+            EncryptionAction::Lock => println!("unset {}", KEY_ENV),
+        }
+        Ok(())
+    }
+}
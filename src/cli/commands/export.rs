@@ -1,12 +1,14 @@
 use crate::{
     database::Database,
     error::Result,
+    database::crypto::{self, Bundle},
+    database::export_schema,
+    database::interchange::{self, DataFormat},
     database::models::ExportData,
     utils,
 };
 use std::path::PathBuf;
 use std::fs;
-use chrono::Utc;
 
 pub struct ExportCommand;
 
@@ -16,47 +18,58 @@ impl ExportCommand {
         output: Option<PathBuf>,
         tag: Option<String>,
         pretty: bool,
+        format: Option<DataFormat>,
+        encrypt: bool,
     ) -> Result<()> {
-        // Get all prompts or filtered by tag
-        let prompts = if let Some(tag_filter) = tag.as_deref() {
-            // Get prompts with specific tag - we'll need to implement this
-            database.list_prompts(Some(tag_filter), None)?
-                .into_iter()
-                .map(|summary| database.get_prompt(&summary.name))
-                .collect::<Result<Vec<_>>>()?
-        } else {
-            database.get_all_prompts()?
-        };
+        // Get all library prompts or those with a tag; project prompts
+        // already live in their own file
+        let mut prompts = database.get_all_prompts()?;
+        if let Some(tag_filter) = tag.as_deref() {
+            prompts.retain(|p| p.tags.iter().any(|t| t == tag_filter));
+        }
         
         // Create export data
-        let export_data = ExportData {
-            version: env!("CARGO_PKG_VERSION").to_string(),
-            exported_at: Utc::now(),
-            prompts,
-        };
+        let export_data = ExportData::new(prompts, database.list_saved_searches()?);
         
-        // Serialize to JSON
-        let json = if pretty {
-            serde_json::to_string_pretty(&export_data)?
-        } else {
-            serde_json::to_string(&export_data)?
-        };
+        let format = format
+            .or_else(|| output.as_deref().and_then(DataFormat::from_path))
+            .unwrap_or(DataFormat::Json);
+        let mut text = interchange::to_string(&export_data, format, pretty)?;
+        if encrypt {
+            let passphrase = crypto::passphrase("Export passphrase", true)?;
+            let bundle = Bundle::seal(&text, &format.name().to_ascii_lowercase(), &passphrase)?;
+            text = serde_json::to_string_pretty(&bundle)?;
+        } else if database.is_encrypted()? {
+            utils::eprint_warning("The library is encrypted but this export is not; use --encrypt to protect it");
+        }
         
         // Output to file or stdout
         match output {
             Some(path) => {
-                fs::write(&path, json)?;
+                fs::write(&path, text)?;
                 utils::print_success(&format!(
                     "Exported {} prompt(s) to {}", 
                     export_data.prompts.len(),
                     path.display()
                 ));
             }
-            None => {
-                println!("{}", json);
-            }
+            None if text.ends_with('\n') => print!("{}", text),
+            None => println!("{}", text),
         }
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Write the JSON Schema that exports follow and imports are checked
+    /// against
+    pub fn write_schema(output: Option<PathBuf>) -> Result<()> {
+        match output {
+            Some(path) => {
+                fs::write(&path, export_schema::SCHEMA)?;
+                utils::print_success(&format!("Wrote the export schema to {}", path.display()));
+            }
+            None => print!("{}", export_schema::SCHEMA),
+        }
+        Ok(())
+    }
+}
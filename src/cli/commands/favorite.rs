@@ -0,0 +1,22 @@
+use crate::{
+    database::Database,
+    error::Result,
+    utils,
+};
+
+pub struct FavoriteCommand;
+
+impl FavoriteCommand {
+    pub fn execute(database: &Database, name: String, remove: bool) -> Result<()> {
+        let name = database.resolve_prompt_name(&name)?;
+        database.set_favorite(&name, !remove)?;
+        
+        if remove {
+            utils::print_success(&format!("Removed '{}' from favorites", name));
+        } else {
+            utils::print_success(&format!("Added '{}' to favorites", name));
+        }
+        
+        Ok(())
+    }
+}
@@ -1,12 +1,26 @@
 use crate::{
     clipboard::ClipboardManager,
-    database::Database,
-    error::Result,
-    template::TemplateEngine,
+    database::{Database, models::UsageEvent},
+    error::{PromptedsError, Result},
+    template::{secrets::REDACTED, SecretSource, TemplateEngine},
     utils,
 };
 use std::collections::HashMap;
 use std::io::{self, Write};
+use std::time::Duration;
+
+/// Flags that change how a prompt is rendered and where it goes
+pub struct GetOptions {
+    pub variables: Vec<(String, String)>,
+    pub copy: bool,
+    pub raw: bool,
+    /// Restore the clipboard this long after copying
+    pub clear_after: Option<Duration>,
+    /// Print the render this many back from the history instead
+    pub paste_last: Option<u32>,
+    /// Show where each variable's value came from
+    pub verbose: bool,
+}
 
 pub struct GetCommand;
 
@@ -14,33 +28,71 @@ impl GetCommand {
     pub fn execute(
         database: &Database,
         clipboard: &mut ClipboardManager,
-        name: String,
-        variables: Vec<(String, String)>,
-        copy: bool,
-        raw: bool,
+        name: Option<String>,
+        options: GetOptions,
     ) -> Result<()> {
-        // Get the prompt
+        let GetOptions { variables, copy, raw, clear_after, paste_last, verbose } = options;
+        
+        if let Some(back) = paste_last {
+            let back = back as usize;
+            let rendered = database.recent_render(back)?.ok_or(PromptedsError::RenderNotKept { back })?;
+            println!("{}", rendered);
+            if copy && clipboard.is_available() {
+                Self::copy(clipboard, &rendered, clear_after)?;
+                utils::print_info(&Self::copied_message("Copied kept render to clipboard", clear_after));
+            }
+            return Ok(());
+        }
+        
+        // Get the prompt, accepting a unique prefix of its name
+        let name = match name {
+            Some(name) => database.resolve_prompt_name(&name)?,
+            None => match Self::pick_prompt(database)? {
+                Some(name) => name,
+                None => return Ok(()),
+            },
+        };
         let prompt = database.get_prompt(&name)?;
         
         if raw {
             // Output raw content without rendering
             println!("{}", prompt.content);
-            if copy && clipboard.is_available() {
-                clipboard.set_text(&prompt.content)?;
-                utils::print_info("Copied raw content to clipboard");
+            let copied = copy && clipboard.is_available();
+            if copied {
+                Self::copy(clipboard, &prompt.content, clear_after)?;
+                utils::print_info(&Self::copied_message("Copied raw content to clipboard", clear_after));
             }
+            database.record_usage(&UsageEvent {
+                prompt_id: prompt.id,
+                rendered: false,
+                copied,
+                variables: Vec::new(),
+            })?;
             return Ok(());
         }
         
-        // Convert variables to HashMap
+        // Convert variables to HashMap, noting where each value came from
         let mut variable_map: HashMap<String, String> = variables.into_iter().collect();
+        let mut origins: HashMap<String, &str> = variable_map.keys().map(|name| (name.clone(), "--var")).collect();
         
-        // Add default values for missing variables
+        // Add default values for missing variables, and read secrets from
+        // their sources
         for var in &prompt.variables {
-            if !variable_map.contains_key(&var.name) {
-                if let Some(ref default) = var.default_value {
-                    variable_map.insert(var.name.clone(), default.clone());
+            if variable_map.contains_key(&var.name) {
+                continue;
+            }
+            if var.secret {
+                if let Some(ref source) = var.source {
+                    let source = SecretSource::parse(source).map_err(|details| PromptedsError::SecretUnavailable {
+                        name: var.name.clone(),
+                        details,
+                    })?;
+                    variable_map.insert(var.name.clone(), source.resolve(&var.name)?);
+                    origins.insert(var.name.clone(), source.kind());
                 }
+            } else if let Some(ref default) = var.default_value {
+                variable_map.insert(var.name.clone(), default.clone());
+                origins.insert(var.name.clone(), "default");
             }
         }
         
@@ -51,21 +103,35 @@ impl GetCommand {
         if !missing.is_empty() {
             for var_name in missing {
                 // Check if we have a description for this variable
-                let description = prompt.variables
-                    .iter()
-                    .find(|v| v.name == var_name)
-                    .and_then(|v| v.description.as_ref());
+                let variable = prompt.variables.iter().find(|v| v.name == var_name);
+                let label = match variable.and_then(|v| v.description.as_ref()) {
+                    Some(desc) => format!("Enter value for '{}' ({})", var_name, desc),
+                    None => format!("Enter value for '{}'", var_name),
+                };
                 
-                if let Some(desc) = description {
-                    print!("Enter value for '{}' ({}): ", var_name, desc);
+                // Secrets are typed without echo, so they need a terminal
+                let value = if variable.is_some_and(|v| v.secret) {
+                    utils::read_secret(&label)
+                        .map_err(|_| PromptedsError::MissingVariable { name: var_name.clone() })?
                 } else {
-                    print!("Enter value for '{}': ", var_name);
-                }
-                io::stdout().flush()?;
-                
-                let mut value = String::new();
-                io::stdin().read_line(&mut value)?;
-                variable_map.insert(var_name, value.trim().to_string());
+                    print!("{}: ", label);
+                    io::stdout().flush()?;
+                    let mut value = String::new();
+                    io::stdin().read_line(&mut value)?;
+                    value.trim().to_string()
+                };
+                origins.insert(var_name.clone(), "input");
+                variable_map.insert(var_name, value);
+            }
+        }
+        
+        if verbose {
+            let mut names: Vec<&String> = variable_map.keys().collect();
+            names.sort();
+            for name in names {
+                let secret = prompt.variables.iter().any(|v| &v.name == name && v.secret);
+                let value = if secret { REDACTED } else { variable_map[name].as_str() };
+                eprintln!("{} = {} (from {})", name, value, origins[name]);
             }
         }
         
@@ -76,11 +142,89 @@ impl GetCommand {
         println!("{}", rendered);
         
         // Copy to clipboard if requested
-        if copy && clipboard.is_available() {
-            clipboard.set_text(&rendered)?;
-            utils::print_info("Copied rendered prompt to clipboard");
+        let copied = copy && clipboard.is_available();
+        if copied {
+            Self::copy(clipboard, &rendered, clear_after)?;
+            utils::print_info(&Self::copied_message("Copied rendered prompt to clipboard", clear_after));
+        }
+        
+        // Renders holding secret values are never kept
+        if !prompt.variables.iter().any(|v| v.secret) {
+            database.record_render(&prompt.id, &rendered)?;
         }
         
+        let mut variable_names: Vec<String> = variable_map.into_keys().collect();
+        variable_names.sort();
+        database.record_usage(&UsageEvent {
+            prompt_id: prompt.id,
+            rendered: true,
+            copied,
+            variables: variable_names,
+        })?;
+        
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Copy `text`, and have the clipboard restored after `clear_after`
+    fn copy(clipboard: &mut ClipboardManager, text: &str, clear_after: Option<Duration>) -> Result<()> {
+        let previous = clear_after.map(|_| clipboard.get_text().ok());
+        clipboard.set_text(text)?;
+        if let (Some(delay), Some(previous)) = (clear_after, previous) {
+            clipboard.restore_after(previous, text, delay)?;
+        }
+        Ok(())
+    }
+    
+    fn copied_message(message: &str, clear_after: Option<Duration>) -> String {
+        match clear_after {
+            Some(delay) => format!("{}; it will be restored in {:?}", message, delay),
+            None => message.to_string(),
+        }
+    }
+    
+    /// Let the user choose a prompt: pinned first, then favorites, then the
+    /// most recently used. `None` means the picker was cancelled.
+    #[cfg(feature = "interactive")]
+    fn pick_prompt(database: &Database) -> Result<Option<String>> {
+        use inquire::{InquireError, Select};
+        use std::io::IsTerminal;
+        
+        if !io::stdin().is_terminal() {
+            return Err(Self::name_required());
+        }
+        
+        let mut prompts = database.list_prompts(None, None)?;
+        if prompts.is_empty() {
+            utils::print_info("No prompts yet. Add one with 'edisonprompt add <NAME>'");
+            return Ok(None);
+        }
+        prompts.sort_by(|a, b| {
+            b.pinned.cmp(&a.pinned)
+                .then(b.favorite.cmp(&a.favorite))
+                .then(b.last_used_at.cmp(&a.last_used_at))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        
+        let options: Vec<String> = prompts
+            .iter()
+            .map(|p| format!("{:<2} {}", crate::cli::output::marker(p), p.name))
+            .collect();
+        
+        match Select::new("Prompt:", options).with_page_size(15).raw_prompt() {
+            Ok(choice) => Ok(Some(prompts[choice.index].name.clone())),
+            Err(InquireError::OperationCanceled | InquireError::OperationInterrupted) => Ok(None),
+            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string()).into()),
+        }
+    }
+    
+    #[cfg(not(feature = "interactive"))]
+    fn pick_prompt(_database: &Database) -> Result<Option<String>> {
+        Err(Self::name_required())
+    }
+    
+    fn name_required() -> PromptedsError {
+        PromptedsError::InvalidPromptName {
+            reason: "a prompt name is required when not running in a terminal".to_string(),
+        }
+    }
+}
@@ -1,101 +1,756 @@
 use crate::{
     database::Database,
     error::{PromptedsError, Result},
-    database::models::ExportData,
+    database::converters::{self, Input, Tool},
+    database::crypto::{self, Bundle},
+    database::interchange::{self, DataFormat},
+    database::models::{ExportData, Prompt, SavedSearch},
     cli::MergeStrategy,
+    cli::commands::EditCommand,
+    template::{scan::Scanner, secrets::drop_untrusted_commands},
     utils,
 };
+use serde::Serialize;
 use std::path::PathBuf;
 use std::fs;
 use std::io::{self, Read};
 
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Outcome {
+    Created,
+    Updated,
+    Renamed,
+    Skipped,
+    Failed,
+}
+
+/// What happened to one prompt or saved search
+#[derive(Serialize)]
+struct Entry {
+    /// `prompt` or `saved_search`
+    kind: &'static str,
+    name: String,
+    outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    renamed_to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    /// Unified diff of the content a dry run would replace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+    /// Possible secrets or personal data in what was written
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report {
+    dry_run: bool,
+    /// False when nothing was written: a dry run, or a failure rolled the
+    /// whole import back
+    committed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backup: Option<PathBuf>,
+    created: usize,
+    updated: usize,
+    renamed: usize,
+    skipped: usize,
+    failed: usize,
+    entries: Vec<Entry>,
+}
+
+/// How `import` treats prompts and saved searches that already exist
+pub struct ImportOptions {
+    pub merge: MergeStrategy,
+    pub dry_run: bool,
+    pub continue_on_error: bool,
+    pub json: bool,
+    pub color: bool,
+    /// Editor for resolving conflicts with `--merge interactive`
+    pub editor: String,
+    /// Checks prompts for secrets and personal data before they are written
+    pub scanner: Scanner,
+}
+
+/// What to do with an incoming prompt that matches a local one
+enum Choice {
+    Keep(&'static str),
+    Take,
+    Save(Prompt),
+}
+
+/// Answers to the `--merge interactive` questions, by position in the
+/// import, with the local copy each question was about. They are asked
+/// before the import takes the write lock, so other commands are not locked
+/// out while the user decides.
+struct Answers {
+    prompts: Vec<Option<(Prompt, Choice)>>,
+    searches: Vec<Option<(SavedSearch, bool)>>,
+}
+
 pub struct ImportCommand;
 
 impl ImportCommand {
+    /// Import everything in one transaction. Each prompt and saved search
+    /// is written in its own savepoint, so all failures are reported; unless
+    /// `continue_on_error` is set, any failure rolls the whole import back.
     pub fn execute(
         database: &mut Database,
         input: Option<PathBuf>,
-        merge: MergeStrategy,
-        dry_run: bool,
+        format: Option<DataFormat>,
+        from: Option<Tool>,
+        options: ImportOptions,
     ) -> Result<()> {
-        // Read JSON from file or stdin
-        let json = match input {
-            Some(path) => fs::read_to_string(path)?,
-            None => {
-                let mut buffer = String::new();
-                io::stdin().read_to_string(&mut buffer)?;
-                buffer
+        if matches!(options.merge, MergeStrategy::Interactive) {
+            if options.json {
+                return Err(PromptedsError::ImportError {
+                    details: "--merge interactive cannot be used with --json".to_string(),
+                });
             }
+            if input.is_none() {
+                return Err(PromptedsError::ImportError {
+                    details: "--merge interactive needs --input, as stdin is used for the questions".to_string(),
+                });
+            }
+        }
+
+        let import_data = match from {
+            Some(tool) => {
+                let prompts = converters::convert(tool, &Input::read(tool, input)?)?;
+                if !options.json {
+                    utils::print_info(&format!("Converted {} prompt(s) from {}", prompts.len(), tool.name()));
+                }
+                ExportData::new(prompts, Vec::new())
+            }
+            None => Self::read_export(input, format)?,
         };
-        
-        // Parse import data
-        let import_data: ExportData = serde_json::from_str(&json)
-            .map_err(|e| PromptedsError::ImportError {
-                details: format!("Invalid JSON format: {}", e),
-            })?;
-        
-        if dry_run {
-            println!("Dry run - would import {} prompt(s):", import_data.prompts.len());
+
+        let report = Self::run(database, import_data, &options)?;
+
+        if options.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            Self::print_report(&report, options.color);
+        }
+
+        if report.failed > 0 {
+            return Err(PromptedsError::ImportFailed {
+                count: report.failed,
+                rolled_back: !report.committed,
+            });
+        }
+        Ok(())
+    }
+
+    /// Import `import_data` and report what happened to each item
+    fn run(database: &mut Database, import_data: ExportData, options: &ImportOptions) -> Result<Report> {
+        let mut report = Report {
+            dry_run: options.dry_run,
+            committed: false,
+            backup: None,
+            created: 0,
+            updated: 0,
+            renamed: 0,
+            skipped: 0,
+            failed: 0,
+            entries: Vec::new(),
+        };
+
+        // Replacing local copies loses them, so keep a copy first
+        if !options.dry_run && !matches!(options.merge, MergeStrategy::Skip | MergeStrategy::Rename) {
+            let mut overwrites = false;
             for prompt in &import_data.prompts {
-                let exists = database.prompt_exists(&prompt.name).unwrap_or(false);
-                let action = if exists {
-                    match merge {
-                        MergeStrategy::Skip => "SKIP (exists)",
-                        MergeStrategy::Overwrite => "OVERWRITE",
-                        MergeStrategy::Rename => "RENAME",
+                if let Some(local) = database.matching_prompt(prompt)? {
+                    overwrites |= !same_prompt(&local, prompt);
+                }
+            }
+            for search in &import_data.saved_searches {
+                if let Ok(local) = database.get_saved_search(&search.name) {
+                    overwrites |= !same_search(&local, search);
+                }
+            }
+            if overwrites {
+                report.backup = database.auto_backup("pre-import")?;
+            }
+        }
+
+        let answers = Self::answers(database, &import_data, options)?;
+
+        // A dry run performs the import too, then rolls it back, so it
+        // reports the failures a real import would hit
+        report.committed = database.transaction(|database| {
+            for (prompt, answer) in import_data.prompts.into_iter().zip(answers.prompts) {
+                let entry = Self::import_prompt(database, prompt, answer, options)?;
+                report.push(entry);
+            }
+            for (search, answer) in import_data.saved_searches.into_iter().zip(answers.searches) {
+                let entry = Self::import_search(database, search, answer, options)?;
+                report.push(entry);
+            }
+            Ok(!options.dry_run && (options.continue_on_error || report.failed == 0))
+        })?;
+        Ok(report)
+    }
+
+    /// Ask about every prompt and saved search that differs from its local
+    /// copy, with `--merge interactive`; no answers otherwise
+    fn answers(database: &Database, import_data: &ExportData, options: &ImportOptions) -> Result<Answers> {
+        let interactive = matches!(options.merge, MergeStrategy::Interactive);
+        let mut answers = Answers { prompts: Vec::new(), searches: Vec::new() };
+        for prompt in &import_data.prompts {
+            let local = if interactive { database.matching_prompt(prompt)? } else { None };
+            let answer = match local {
+                Some(local) => {
+                    let mut prompt = prompt.clone();
+                    drop_untrusted_commands(&mut prompt, Some(&local));
+                    if same_prompt(&local, &prompt) {
+                        None
+                    } else {
+                        let choice = Self::ask(&local, &prompt, options)?;
+                        Some((local, choice))
+                    }
+                }
+                None => None,
+            };
+            answers.prompts.push(answer);
+        }
+        for search in &import_data.saved_searches {
+            let local = if interactive { database.get_saved_search(&search.name).ok() } else { None };
+            let answer = match local {
+                Some(local) if !same_search(&local, search) => {
+                    let take = Self::ask_search(&local, search)?;
+                    Some((local, take))
+                }
+                _ => None,
+            };
+            answers.searches.push(answer);
+        }
+        Ok(answers)
+    }
+
+    /// Write one prompt according to the merge strategy. An incoming prompt
+    /// matches the local one with its id, or else the one with its name.
+    /// Only errors that end the whole import are returned; a prompt that
+    /// cannot be written is recorded as failed.
+    fn import_prompt(
+        database: &mut Database,
+        mut prompt: Prompt,
+        answer: Option<(Prompt, Choice)>,
+        options: &ImportOptions,
+    ) -> Result<Entry> {
+        let mut entry = Entry::new("prompt", &prompt.name, Outcome::Created);
+        let local = database.matching_prompt(&prompt)?;
+        entry.warnings = drop_untrusted_commands(&mut prompt, local.as_ref())
+            .into_iter()
+            .map(|name| format!("ignored the command source of secret '{}'; its value will be asked for", name))
+            .collect();
+        let Some(local) = local else {
+            return Ok(Self::screened(entry, &prompt, options, || database.create_prompt(&prompt)));
+        };
+        if same_prompt(&local, &prompt) {
+            return Ok(entry.skipped("unchanged"));
+        }
+
+        let choice = match options.merge {
+            MergeStrategy::Skip => Choice::Keep("already exists"),
+            MergeStrategy::Overwrite => Choice::Take,
+            MergeStrategy::Newest if prompt.updated_at > local.updated_at => Choice::Take,
+            MergeStrategy::Newest => Choice::Keep("local copy is at least as new"),
+            MergeStrategy::UnionTags => {
+                let mut merged = local.clone();
+                for tag in &prompt.tags {
+                    if !merged.tags.contains(tag) {
+                        merged.tags.push(tag.clone());
                     }
+                }
+                if merged.tags.len() == local.tags.len() {
+                    Choice::Keep("no new tags")
                 } else {
-                    "CREATE"
-                };
-                println!("  {} - {}", prompt.name, action);
-            }
-            return Ok(());
-        }
-        
-        let mut created = 0;
-        let mut updated = 0;
-        let mut skipped = 0;
-        
-        for mut prompt in import_data.prompts {
-            let exists = database.prompt_exists(&prompt.name)?;
-            
-            if exists {
-                match merge {
-                    MergeStrategy::Skip => {
-                        skipped += 1;
+                    merged.updated_at = chrono::Utc::now();
+                    Choice::Save(merged)
+                }
+            }
+            // The answer only holds for the local copy it was given about
+            MergeStrategy::Interactive => match answer {
+                Some((asked, choice)) if asked.id == local.id && same_prompt(&asked, &local) => choice,
+                _ => Choice::Keep("changed after the question; kept the local copy"),
+            },
+            MergeStrategy::Rename => {
+                let original_name = prompt.name.clone();
+                let mut counter = 1;
+                while database.prompt_exists(&prompt.name)? {
+                    prompt.name = format!("{}-{}", original_name, counter);
+                    counter += 1;
+                }
+                // The same prompt under another name: import it as a copy
+                if prompt.id == local.id {
+                    prompt.id = uuid::Uuid::new_v4();
+                }
+                if prompt.name != original_name {
+                    entry.outcome = Outcome::Renamed;
+                    entry.renamed_to = Some(prompt.name.clone());
+                }
+                return Ok(Self::screened(entry, &prompt, options, || database.create_prompt(&prompt)));
+            }
+        };
+
+        let updated = match choice {
+            Choice::Keep(reason) => return Ok(entry.skipped(reason)),
+            Choice::Take => Prompt { id: local.id, created_at: local.created_at, ..prompt },
+            Choice::Save(merged) => merged,
+        };
+        if updated.name != local.name {
+            if database.prompt_exists(&updated.name)? {
+                let written = Err(PromptedsError::PromptAlreadyExists { name: updated.name });
+                return Ok(entry.result(written));
+            }
+            entry.reason = Some(format!("was '{}'", local.name));
+        }
+        let added: Vec<&str> = updated.tags.iter().filter(|t| !local.tags.contains(t)).map(String::as_str).collect();
+        if !added.is_empty() && entry.reason.is_none() {
+            entry.reason = Some(format!("tags +{}", added.join(", +")));
+        }
+        if options.dry_run && updated.content != local.content {
+            entry.diff = Some(utils::format_diff(&local.content, &updated.content, "local", "incoming", false));
+        }
+        entry.outcome = Outcome::Updated;
+        Ok(Self::screened(entry, &updated, options, || database.update_prompt(&updated)))
+    }
+
+    /// Write `prompt` with `write` unless the scanner blocks it; what it
+    /// warns about is listed with the entry
+    fn screened(mut entry: Entry, prompt: &Prompt, options: &ImportOptions, write: impl FnOnce() -> Result<()>) -> Entry {
+        let written = options.scanner.check(prompt).and_then(|findings| {
+            entry.warnings.extend(findings.iter().map(|finding| format!("possible {}", finding)));
+            write()
+        });
+        entry.result(written)
+    }
+
+    /// Show how `incoming` differs from `local` and ask what to keep
+    fn ask(local: &Prompt, incoming: &Prompt, options: &ImportOptions) -> Result<Choice> {
+        println!();
+        if local.name == incoming.name {
+            println!("Prompt '{}' differs from the local copy:", incoming.name);
+        } else {
+            println!("Prompt '{}' is '{}' here, and differs:", incoming.name, local.name);
+        }
+        if local.content != incoming.content {
+            print!("{}", utils::format_diff(&local.content, &incoming.content, "local", "incoming", options.color));
+        }
+        if local.tags != incoming.tags {
+            println!("tags: [{}] -> [{}]", local.tags.join(", "), incoming.tags.join(", "));
+        }
+
+        loop {
+            match utils::choose("Keep the local copy, take the incoming one, or edit a merge?", &["keep", "take", "edit"])? {
+                0 => return Ok(Choice::Keep("kept local copy")),
+                1 => return Ok(Choice::Take),
+                _ => {
+                    let (merged, _) = utils::mark_conflicts(&local.content, &incoming.content, "local", "incoming");
+                    let edited = EditCommand::run_editor(&options.editor, &merged)?;
+                    if edited.lines().any(|line| line.starts_with(utils::CONFLICT_START)) {
+                        utils::print_error("Conflict markers remain, not saving");
                         continue;
                     }
-                    MergeStrategy::Overwrite => {
-                        database.update_prompt(&prompt)?;
-                        updated += 1;
-                    }
-                    MergeStrategy::Rename => {
-                        // Find a unique name
-                        let mut counter = 1;
-                        let original_name = prompt.name.clone();
-                        loop {
-                            let new_name = format!("{}-{}", original_name, counter);
-                            if !database.prompt_exists(&new_name)? {
-                                prompt.name = new_name;
-                                break;
-                            }
-                            counter += 1;
-                        }
-                        database.create_prompt(&prompt)?;
-                        created += 1;
+                    let taken = Prompt { id: local.id, created_at: local.created_at, ..incoming.clone() };
+                    return Ok(Choice::Save(EditCommand::with_content(&taken, edited.trim())?));
+                }
+            }
+        }
+    }
+
+    /// Show how `incoming` differs from `local`; true to take it
+    fn ask_search(local: &SavedSearch, incoming: &SavedSearch) -> Result<bool> {
+        println!();
+        println!("Saved search '{}' differs from the local copy:", incoming.name);
+        println!("  local:    {}", describe_search(local));
+        println!("  incoming: {}", describe_search(incoming));
+        Ok(utils::choose("Keep the local copy or take the incoming one?", &["keep", "take"])? == 1)
+    }
+
+    fn import_search(
+        database: &mut Database,
+        mut search: SavedSearch,
+        answer: Option<(SavedSearch, bool)>,
+        options: &ImportOptions,
+    ) -> Result<Entry> {
+        let mut entry = Entry::new("saved_search", &search.name, Outcome::Created);
+        if let Ok(local) = database.get_saved_search(&search.name) {
+            if same_search(&local, &search) {
+                return Ok(entry.skipped("unchanged"));
+            }
+            match options.merge {
+                MergeStrategy::Skip => return Ok(entry.skipped("already exists")),
+                MergeStrategy::UnionTags => return Ok(entry.skipped("already exists")),
+                MergeStrategy::Newest if search.created_at <= local.created_at => {
+                    return Ok(entry.skipped("local copy is at least as new"));
+                }
+                MergeStrategy::Interactive => match answer {
+                    Some((asked, true)) if same_search(&asked, &local) => entry.outcome = Outcome::Updated,
+                    Some((asked, false)) if same_search(&asked, &local) => return Ok(entry.skipped("kept local copy")),
+                    _ => return Ok(entry.skipped("changed after the question; kept the local copy")),
+                },
+                MergeStrategy::Overwrite | MergeStrategy::Newest => entry.outcome = Outcome::Updated,
+                MergeStrategy::Rename => {
+                    let original_name = search.name.clone();
+                    let mut counter = 1;
+                    while database.get_saved_search(&search.name).is_ok() {
+                        search.name = format!("{}-{}", original_name, counter);
+                        counter += 1;
                     }
+                    entry.outcome = Outcome::Renamed;
+                    entry.renamed_to = Some(search.name.clone());
                 }
-            } else {
-                database.create_prompt(&prompt)?;
-                created += 1;
             }
         }
-        
-        utils::print_success(&format!(
-            "Import complete: {} created, {} updated, {} skipped",
-            created, updated, skipped
-        ));
-        
-        Ok(())
+        let written = database.save_search(&search);
+        Ok(entry.result(written))
+    }
+
+    fn print_report(report: &Report, color: bool) {
+        if report.dry_run {
+            println!("Dry run - nothing was imported:");
+        }
+        for entry in &report.entries {
+            let outcome = match entry.outcome {
+                Outcome::Created => "created",
+                Outcome::Updated => "updated",
+                Outcome::Renamed => "renamed",
+                Outcome::Skipped => "skipped",
+                Outcome::Failed => "failed",
+            };
+            let kind = if entry.kind == "prompt" { String::new() } else { format!(" ({})", entry.kind.replace('_', " ")) };
+            let detail = match (&entry.renamed_to, &entry.reason) {
+                (Some(new_name), _) => format!(" -> {}", new_name),
+                (None, Some(reason)) => format!(": {}", reason),
+                (None, None) => String::new(),
+            };
+            println!("  {:<8} {}{}{}", outcome, entry.name, kind, detail);
+            for warning in &entry.warnings {
+                println!("      warning: {}", warning);
+            }
+            if let Some(diff) = &entry.diff {
+                let diff = if color { utils::color_diff(diff) } else { diff.clone() };
+                for line in diff.lines() {
+                    println!("      {}", line);
+                }
+            }
+        }
+
+        if let Some(path) = &report.backup {
+            utils::print_info(&format!("Saved the current database to {}", path.display()));
+        }
+        let summary = format!(
+            "{} created, {} updated, {} renamed, {} skipped, {} failed",
+            report.created, report.updated, report.renamed, report.skipped, report.failed
+        );
+        if report.dry_run {
+            utils::print_info(&format!("Would import: {}", summary));
+        } else if report.committed && report.failed > 0 {
+            utils::print_warning(&format!("Imported the rest: {}", summary));
+        } else if report.committed {
+            utils::print_success(&format!("Import complete: {}", summary));
+        } else {
+            utils::print_info(&format!("Rolled back: {}", summary));
+        }
+    }
+
+    /// Read an export file in `format`, or the format its extension implies
+    fn read_export(input: Option<PathBuf>, format: Option<DataFormat>) -> Result<ExportData> {
+        let format = format
+            .or_else(|| input.as_deref().and_then(DataFormat::from_path))
+            .unwrap_or(DataFormat::Json);
+
+        // Read from file or stdin
+        let text = match input {
+            Some(path) => fs::read_to_string(path)?,
+            None => {
+                let mut buffer = String::new();
+                io::stdin().read_to_string(&mut buffer)?;
+                buffer
+            }
+        };
+
+        // An encrypted bundle says which format it holds
+        if let Some(bundle) = Bundle::detect(&text) {
+            let format = DataFormat::from_name(&bundle.format).ok_or_else(|| PromptedsError::ImportError {
+                details: format!("the bundle holds an unknown format '{}'", bundle.format),
+            })?;
+            let text = bundle.open(&crypto::passphrase("Export passphrase", false)?)?;
+            return interchange::parse(&text, format);
+        }
+
+        interchange::parse(&text, format)
+    }
+}
+
+impl Entry {
+    fn new(kind: &'static str, name: &str, outcome: Outcome) -> Self {
+        Self { kind, name: name.to_string(), outcome, renamed_to: None, reason: None, diff: None, warnings: Vec::new() }
+    }
+
+    fn skipped(mut self, reason: &str) -> Self {
+        self.outcome = Outcome::Skipped;
+        self.reason = Some(reason.to_string());
+        self.warnings.clear();
+        self
+    }
+
+    /// The entry, or a failure if `written` is an error
+    fn result(mut self, written: Result<()>) -> Self {
+        if let Err(e) = written {
+            self.outcome = Outcome::Failed;
+            self.renamed_to = None;
+            self.reason = Some(e.to_string());
+            self.diff = None;
+            self.warnings.clear();
+        }
+        self
+    }
+}
+
+impl Report {
+    fn push(&mut self, entry: Entry) {
+        match entry.outcome {
+            Outcome::Created => self.created += 1,
+            Outcome::Updated => self.updated += 1,
+            Outcome::Renamed => self.renamed += 1,
+            Outcome::Skipped => self.skipped += 1,
+            Outcome::Failed => self.failed += 1,
+        }
+        self.entries.push(entry);
+    }
+}
+
+/// Whether importing `incoming` over `local` would change nothing
+fn same_prompt(local: &Prompt, incoming: &Prompt) -> bool {
+    let variable = |v: &crate::database::models::Variable| (v.name.clone(), v.description.clone(), v.default_value.clone());
+    let mut local_tags = local.tags.clone();
+    let mut incoming_tags = incoming.tags.clone();
+    local_tags.sort();
+    incoming_tags.sort();
+    local.name == incoming.name
+        && local.content == incoming.content
+        && local_tags == incoming_tags
+        && local.variables.iter().map(variable).eq(incoming.variables.iter().map(variable))
+}
+
+fn same_search(local: &SavedSearch, incoming: &SavedSearch) -> bool {
+    local.query == incoming.query
+        && local.tags == incoming.tags
+        && local.sort == incoming.sort
+        && local.limit == incoming.limit
+}
+
+/// One-line summary of a saved search, for the interactive question
+fn describe_search(search: &SavedSearch) -> String {
+    let mut parts = vec![format!("query {}", search.query.as_deref().unwrap_or("(any)"))];
+    if !search.tags.is_empty() {
+        parts.push(format!("tags {}", search.tags.join(", ")));
+    }
+    if let Some(sort) = &search.sort {
+        parts.push(format!("sort {}", sort));
+    }
+    if let Some(limit) = search.limit {
+        parts.push(format!("limit {}", limit));
+    }
+    parts.join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ScanConfig;
+
+    fn search(name: &str, query: &str) -> SavedSearch {
+        SavedSearch {
+            name: name.to_string(),
+            query: Some(query.to_string()),
+            tags: vec!["code".to_string()],
+            sort: Some("updated".to_string()),
+            limit: Some(5),
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn options(merge: MergeStrategy) -> ImportOptions {
+        ImportOptions {
+            merge,
+            dry_run: false,
+            continue_on_error: false,
+            json: true,
+            color: false,
+            editor: "true".to_string(),
+            scanner: Scanner::new(&ScanConfig::default(), false).unwrap(),
+        }
+    }
+
+    fn import_with(database: &mut Database, data: &ExportData, options: ImportOptions) -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("export.json");
+        fs::write(&file, interchange::to_string(data, DataFormat::Json, true).unwrap()).unwrap();
+        ImportCommand::execute(database, Some(file), None, None, options)
+    }
+
+    fn import(database: &mut Database, data: &ExportData, merge: MergeStrategy) {
+        import_with(database, data, options(merge)).unwrap();
+    }
+
+    fn run(database: &mut Database, prompts: Vec<Prompt>, options: &ImportOptions) -> Report {
+        ImportCommand::run(database, ExportData::new(prompts, Vec::new()), options).unwrap()
+    }
+
+    fn prompt(name: &str, content: &str, tags: &[&str]) -> Prompt {
+        Prompt::new(name.to_string(), content.to_string()).with_tags(tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    fn outcomes(report: &Report) -> Vec<(String, Outcome)> {
+        report.entries.iter().map(|e| (e.name.clone(), e.outcome)).collect()
+    }
+
+    #[test]
+    fn test_saved_searches_round_trip() {
+        let source = Database::open_in_memory().unwrap();
+        source.save_search(&search("reviews", "review")).unwrap();
+        let data = ExportData::new(Vec::new(), source.list_saved_searches().unwrap());
+
+        let mut target = Database::open_in_memory().unwrap();
+        import(&mut target, &data, MergeStrategy::Skip);
+        let imported = target.get_saved_search("reviews").unwrap();
+        assert!(same_search(&imported, &data.saved_searches[0]));
+    }
+
+    #[test]
+    fn test_renamed_saved_searches_get_distinct_names() {
+        let mut database = Database::open_in_memory().unwrap();
+        database.save_search(&search("reviews", "review")).unwrap();
+
+        let data = ExportData::new(Vec::new(), vec![search("reviews", "audit"), search("reviews", "lint")]);
+        import(&mut database, &data, MergeStrategy::Rename);
+
+        let searches: Vec<_> = database
+            .list_saved_searches()
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.query.unwrap()))
+            .collect();
+        assert_eq!(
+            searches,
+            [
+                ("reviews".to_string(), "review".to_string()),
+                ("reviews-1".to_string(), "audit".to_string()),
+                ("reviews-2".to_string(), "lint".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_newest_takes_only_newer_prompts() {
+        let mut database = Database::open_in_memory().unwrap();
+        let old = prompt("old", "local", &[]);
+        let new = prompt("new", "local", &[]);
+        database.create_prompt(&old).unwrap();
+        database.create_prompt(&new).unwrap();
+
+        let earlier = chrono::Utc::now() - chrono::Duration::days(1);
+        let later = chrono::Utc::now() + chrono::Duration::days(1);
+        let incoming = vec![
+            Prompt { content: "incoming".to_string(), updated_at: later, ..old.clone() },
+            Prompt { content: "incoming".to_string(), updated_at: earlier, ..new.clone() },
+        ];
+        let report = run(&mut database, incoming, &options(MergeStrategy::Newest));
+
+        assert_eq!(outcomes(&report), [("old".to_string(), Outcome::Updated), ("new".to_string(), Outcome::Skipped)]);
+        assert_eq!(database.get_prompt("old").unwrap().content, "incoming");
+        assert_eq!(database.get_prompt("new").unwrap().content, "local");
+    }
+
+    #[test]
+    fn test_union_tags_adds_tags_and_keeps_content() {
+        let mut database = Database::open_in_memory().unwrap();
+        let local = prompt("review", "local", &["code"]);
+        database.create_prompt(&local).unwrap();
+
+        let incoming = Prompt { content: "incoming".to_string(), ..prompt("review", "", &["code", "style"]) };
+        let report = run(&mut database, vec![incoming], &options(MergeStrategy::UnionTags));
+
+        assert_eq!(report.entries[0].reason.as_deref(), Some("tags +style"));
+        let merged = database.get_prompt("review").unwrap();
+        assert_eq!((merged.id, merged.content.as_str()), (local.id, "local"));
+        assert_eq!(merged.tags, ["code", "style"]);
+    }
+
+    #[test]
+    fn test_rename_gives_id_collisions_a_new_id() {
+        let mut database = Database::open_in_memory().unwrap();
+        let local = prompt("review", "local", &[]);
+        database.create_prompt(&local).unwrap();
+
+        let same_name = Prompt { content: "incoming".to_string(), ..local.clone() };
+        let other_name = Prompt { name: "critique".to_string(), content: "other".to_string(), ..local.clone() };
+        let report = run(&mut database, vec![same_name, other_name], &options(MergeStrategy::Rename));
+
+        assert_eq!(report.entries[0].renamed_to.as_deref(), Some("review-1"));
+        assert_eq!(report.entries[1].outcome, Outcome::Created);
+        assert_eq!(database.get_prompt("review").unwrap().content, "local");
+        for name in ["review-1", "critique"] {
+            assert_ne!(database.get_prompt(name).unwrap().id, local.id);
+        }
+    }
+
+    #[test]
+    fn test_failures_roll_back_unless_continue_on_error() {
+        let data = ExportData::new(vec![prompt("good", "fine", &[]), prompt("big", &"x".repeat(100_001), &[])], Vec::new());
+
+        let mut database = Database::open_in_memory().unwrap();
+        let error = import_with(&mut database, &data, options(MergeStrategy::Skip)).unwrap_err();
+        assert!(matches!(error, PromptedsError::ImportFailed { count: 1, rolled_back: true }));
+        assert!(!database.prompt_exists("good").unwrap());
+
+        let options = ImportOptions { continue_on_error: true, ..options(MergeStrategy::Skip) };
+        let error = import_with(&mut database, &data, options).unwrap_err();
+        assert!(matches!(error, PromptedsError::ImportFailed { count: 1, rolled_back: false }));
+        assert!(database.prompt_exists("good").unwrap());
+        assert!(!database.prompt_exists("big").unwrap());
+    }
+
+    #[test]
+    fn test_json_report_shape() {
+        let mut database = Database::open_in_memory().unwrap();
+        database.create_prompt(&prompt("kept", "same", &[])).unwrap();
+        let incoming = vec![prompt("kept", "same", &[]), prompt("big", &"x".repeat(100_001), &[])];
+        let options = ImportOptions { continue_on_error: true, ..options(MergeStrategy::Skip) };
+        let report = run(&mut database, incoming, &options);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["dry_run"], false);
+        assert_eq!(json["committed"], true);
+        assert!(json.get("backup").is_none());
+        assert_eq!((json["skipped"].as_u64(), json["failed"].as_u64()), (Some(1), Some(1)));
+        assert_eq!(
+            json["entries"][0],
+            serde_json::json!({"kind": "prompt", "name": "kept", "outcome": "skipped", "reason": "unchanged"})
+        );
+        assert_eq!(json["entries"][1]["outcome"], "failed");
+        assert!(json["entries"][1]["reason"].is_string());
+    }
+
+    #[test]
+    fn test_dry_run_reports_and_rolls_back() {
+        let mut database = Database::open_in_memory().unwrap();
+        let local = prompt("review", "one\ntwo", &[]);
+        database.create_prompt(&local).unwrap();
+
+        let incoming = vec![Prompt { content: "one\nTWO".to_string(), ..local.clone() }, prompt("new", "text", &[])];
+        let options = ImportOptions { dry_run: true, ..options(MergeStrategy::Overwrite) };
+        let report = run(&mut database, incoming, &options);
+
+        assert!(report.dry_run && !report.committed && report.backup.is_none());
+        assert_eq!(outcomes(&report), [("review".to_string(), Outcome::Updated), ("new".to_string(), Outcome::Created)]);
+        assert!(report.entries[0].diff.as_deref().unwrap().contains("+TWO"));
+        assert_eq!(database.get_prompt("review").unwrap().content, "one\ntwo");
+        assert!(!database.prompt_exists("new").unwrap());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,61 @@
+use crate::{
+    config::{ConfigManager, DEFAULT_LIBRARY},
+    database::Database,
+    error::{PromptedsError, Result},
+    cli::LibraryAction,
+    utils,
+};
+
+pub struct LibraryCommand;
+
+impl LibraryCommand {
+    pub fn execute(config_manager: &mut ConfigManager, action: LibraryAction) -> Result<()> {
+        match action {
+            LibraryAction::List => {
+                let active = config_manager.active_library();
+                for name in config_manager.library_names() {
+                    let path = config_manager.library_path(&name)?;
+                    let marker = if name == active { "*" } else { " " };
+                    let missing = if path.exists() { "" } else { " (not created yet)" };
+                    println!("{} {:<20} {}{}", marker, name, path.display(), missing);
+                }
+            }
+            LibraryAction::Create { name, path } => {
+                if config_manager.library_names().contains(&name) {
+                    return Err(PromptedsError::LibraryAlreadyExists { name });
+                }
+                
+                let path = path.unwrap_or_else(|| {
+                    config_manager.paths().data_dir().join(format!("{}.db", name))
+                });
+                config_manager.set_user_value(
+                    &format!("library.paths.{}", name),
+                    toml::Value::String(path.to_string_lossy().into_owned()),
+                )?;
+                
+                // Create the database file now so mistakes in the path show up early
+                Database::new(config_manager.library_path(&name)?)?;
+                
+                utils::print_success(&format!(
+                    "Created library '{}'. Switch to it with 'edisonprompt library use {}'",
+                    name, name
+                ));
+            }
+            LibraryAction::Use { name } => {
+                config_manager.library_path(&name)?;
+                if name == DEFAULT_LIBRARY {
+                    config_manager.unset_user_value("library.active")?;
+                } else {
+                    config_manager.set_user_value("library.active", toml::Value::String(name.clone()))?;
+                }
+                
+                utils::print_success(&format!("Now using library '{}'", name));
+                if std::env::var_os("EDISONPROMPT_LIBRARY").is_some() {
+                    utils::print_warning("EDISONPROMPT_LIBRARY is set and takes precedence over the active library");
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
@@ -1,41 +1,125 @@
 use crate::{
-    database::Database,
+    database::{Database, models::PromptSummary},
     error::Result,
     cli::{OutputFormat, SortField},
     cli::output::OutputFormatter,
+    utils,
 };
+use clap::ValueEnum;
+
+/// Filters and ordering for the prompt list
+pub struct ListOptions {
+    pub tag: Option<String>,
+    pub collection: Option<String>,
+    pub limit: Option<usize>,
+    pub sort: Option<SortField>,
+    pub names_only: bool,
+    /// Put pinned prompts first, keeping the sort order within each group
+    pub pinned: bool,
+    pub favorites: bool,
+}
 
 pub struct ListCommand;
 
 impl ListCommand {
     pub fn execute(
         database: &Database,
-        tag: Option<String>,
+        options: ListOptions,
         format: OutputFormat,
-        limit: Option<usize>,
-        sort: SortField,
-        names_only: bool,
-        color: bool,
+        formatter: &OutputFormatter,
     ) -> Result<()> {
-        // Get prompts from database
-        let mut prompts = database.list_prompts(tag.as_deref(), limit)?;
+        let ListOptions { tag, collection, mut limit, mut sort, names_only, pinned, favorites } = options;
+        
+        // Get prompts from database, re-running the saved search if one is named
+        let mut prompts = if let Some(ref name) = collection {
+            let saved = database.get_saved_search(name)?;
+            limit = limit.or(saved.limit);
+            sort = sort.or_else(|| {
+                saved.sort.as_deref().and_then(|s| SortField::from_str(s, true).ok())
+            });
+            database.list_prompts_matching(saved.query.as_deref(), &saved.tags, None)?
+        } else {
+            database.list_prompts(tag.as_deref(), None)?
+        };
         
         // Sort the results
-        match sort {
-            SortField::Name => prompts.sort_by(|a, b| a.name.cmp(&b.name)),
-            SortField::Created => prompts.sort_by(|a, b| b.created_at.cmp(&a.created_at)),
-            SortField::Updated => prompts.sort_by(|a, b| b.updated_at.cmp(&a.updated_at)),
+        Self::sort(&mut prompts, sort.unwrap_or(SortField::Name), pinned);
+        
+        if favorites {
+            prompts.retain(|p| p.favorite);
+        }
+        
+        if let Some(limit) = limit {
+            prompts.truncate(limit);
         }
         
         // Format and display results
-        let formatter = OutputFormatter::new(color);
         let output = formatter.format_prompt_list(&prompts, &format, names_only);
         println!("{}", output);
         
-        if !names_only && prompts.len() > 0 {
+        if !names_only && !prompts.is_empty() {
             println!("\nTotal: {} prompt(s)", prompts.len());
         }
         
+        if !names_only && !matches!(format, OutputFormat::Json) {
+            let shadowed = database.shadowed_prompts()?;
+            if !shadowed.is_empty() {
+                utils::print_warning(&format!(
+                    "Project prompts shadow library prompts with the same name: {}",
+                    shadowed.join(", ")
+                ));
+            }
+        }
+        
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Order `prompts` by `field`, moving pinned prompts first if `pinned`
+    fn sort(prompts: &mut [PromptSummary], field: SortField, pinned: bool) {
+        match field {
+            SortField::Name => prompts.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortField::Created => prompts.sort_by_key(|p| std::cmp::Reverse(p.created_at)),
+            SortField::Updated => prompts.sort_by_key(|p| std::cmp::Reverse(p.updated_at)),
+        }
+        
+        if pinned {
+            prompts.sort_by_key(|p| !p.pinned);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::PromptSource;
+
+    fn summary(name: &str, pinned: bool, age_days: i64) -> PromptSummary {
+        let created_at = chrono::Utc::now() - chrono::Duration::days(age_days);
+        PromptSummary {
+            name: name.to_string(),
+            variable_count: 0,
+            tag_count: 0,
+            created_at,
+            updated_at: created_at,
+            pinned,
+            favorite: false,
+            last_used_at: None,
+            source: PromptSource::Library,
+        }
+    }
+
+    #[test]
+    fn test_pinned_prompts_come_first_in_sort_order() {
+        let mut prompts = vec![summary("d", false, 1), summary("c", true, 3), summary("b", false, 2), summary("a", true, 4)];
+        let names = |prompts: &[PromptSummary]| prompts.iter().map(|p| p.name.clone()).collect::<Vec<_>>();
+
+        ListCommand::sort(&mut prompts, SortField::Name, true);
+        assert_eq!(names(&prompts), ["a", "c", "b", "d"]);
+
+        ListCommand::sort(&mut prompts, SortField::Created, true);
+        assert_eq!(names(&prompts), ["c", "a", "d", "b"]);
+
+        ListCommand::sort(&mut prompts, SortField::Created, false);
+        assert_eq!(names(&prompts), ["d", "b", "c", "a"]);
+    }
+}
@@ -2,18 +2,48 @@ pub mod add;
 pub mod get;
 pub mod list;
 pub mod search;
+pub mod similar;
+pub mod reindex;
 pub mod edit;
 pub mod delete;
 pub mod export;
 pub mod import;
 pub mod completions;
+pub mod collection;
+pub mod dedupe;
+pub mod stats;
+pub mod pin;
+pub mod favorite;
+pub mod recent;
+pub mod library;
+pub mod copy;
+pub mod config;
+pub mod backup;
+pub mod doctor;
+pub mod sync;
+pub mod encryption;
 
-pub use add::AddCommand;
-pub use get::GetCommand;
-pub use list::ListCommand;
-pub use search::SearchCommand;
+pub use add::{AddCommand, AddOptions};
+pub use get::{GetCommand, GetOptions};
+pub use list::{ListCommand, ListOptions};
+pub use search::{SearchCommand, SearchOptions};
+pub use similar::SimilarCommand;
+pub use reindex::ReindexCommand;
 pub use edit::EditCommand;
 pub use delete::DeleteCommand;
 pub use export::ExportCommand;
-pub use import::ImportCommand;
-pub use completions::CompletionsCommand;
\ No newline at end of file
+pub use import::{ImportCommand, ImportOptions};
+pub use completions::CompletionsCommand;
+pub use collection::CollectionCommand;
+pub use dedupe::DedupeCommand;
+pub use stats::StatsCommand;
+pub use pin::PinCommand;
+pub use favorite::FavoriteCommand;
+pub use recent::RecentCommand;
+pub use library::LibraryCommand;
+pub use copy::CopyCommand;
+pub use config::ConfigCommand;
+pub use backup::{BackupCommand, RestoreCommand};
+pub use doctor::DoctorCommand;
+pub use sync::SyncCommand;
+pub use encryption::EncryptionCommand;
@@ -0,0 +1,22 @@
+use crate::{
+    database::Database,
+    error::Result,
+    utils,
+};
+
+pub struct PinCommand;
+
+impl PinCommand {
+    pub fn execute(database: &Database, name: String, pinned: bool) -> Result<()> {
+        let name = database.resolve_prompt_name(&name)?;
+        database.set_pinned(&name, pinned)?;
+        
+        if pinned {
+            utils::print_success(&format!("Pinned '{}'", name));
+        } else {
+            utils::print_success(&format!("Unpinned '{}'", name));
+        }
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    database::Database,
+    error::Result,
+    cli::OutputFormat,
+    cli::output::OutputFormatter,
+};
+
+pub struct RecentCommand;
+
+impl RecentCommand {
+    pub fn execute(
+        database: &Database,
+        limit: usize,
+        format: OutputFormat,
+        formatter: &OutputFormatter,
+    ) -> Result<()> {
+        let mut prompts = database.list_prompts(None, None)?;
+        prompts.retain(|p| p.last_used_at.is_some());
+        prompts.sort_by_key(|p| std::cmp::Reverse(p.last_used_at));
+        prompts.truncate(limit);
+        
+        if prompts.is_empty() && !matches!(format, OutputFormat::Json) {
+            println!("No prompts rendered yet. Use 'edisonprompt get <NAME>' to render one.");
+            return Ok(());
+        }
+        
+        println!("{}", formatter.format_recent_list(&prompts, &format));
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use crate::{
+    database::Database,
+    error::Result,
+    utils,
+};
+
+pub struct ReindexCommand;
+
+impl ReindexCommand {
+    pub fn execute(database: &mut Database) -> Result<()> {
+        let count = database.reindex_embeddings()?;
+        
+        utils::print_success(&format!("Reindexed {} prompt(s)", count));
+        
+        Ok(())
+    }
+}
@@ -3,29 +3,85 @@ use crate::{
     error::Result,
     cli::OutputFormat,
     cli::output::OutputFormatter,
+    utils,
 };
 
+/// Flags that change how a search is run and displayed
+pub struct SearchOptions {
+    pub saved: Option<String>,
+    pub highlight: bool,
+    pub fuzzy: bool,
+    pub semantic: bool,
+    pub explain: bool,
+    pub limit: Option<usize>,
+    /// Used when neither `limit` nor the saved search sets one
+    pub default_limit: usize,
+}
+
 pub struct SearchCommand;
 
 impl SearchCommand {
     pub fn execute(
         database: &Database,
-        query: String,
-        highlight: bool,
-        limit: usize,
+        query: Option<String>,
+        options: SearchOptions,
         format: OutputFormat,
-        color: bool,
+        formatter: &OutputFormatter,
     ) -> Result<()> {
+        let mut limit = options.limit;
+        let mut required_tags = Vec::new();
+        
+        // A saved search supplies the query and tags; an explicit query narrows it
+        let query = match options.saved {
+            Some(ref name) => {
+                let saved = database.get_saved_search(name)?;
+                limit = limit.or(saved.limit);
+                required_tags = saved.tags;
+                match (saved.query, query) {
+                    (Some(saved_query), Some(query)) => format!("({}) AND ({})", saved_query, query),
+                    (Some(saved_query), None) => saved_query,
+                    (None, Some(query)) => query,
+                    (None, None) => {
+                        utils::print_info(&format!(
+                            "Collection '{}' has no query; use 'list --collection {}' to see its prompts",
+                            name, name
+                        ));
+                        return Ok(());
+                    }
+                }
+            }
+            None => query.unwrap_or_default(),
+        };
+        let limit = limit.unwrap_or(options.default_limit);
+        
         // Perform search
-        let results = database.search_prompts(&query, limit, highlight)?;
+        let mut results = if options.fuzzy {
+            database.search_prompts_fuzzy(&query, limit)?
+        } else if options.semantic {
+            warn_unindexed(database)?;
+            database.semantic_search(&query, limit)?
+        } else if required_tags.is_empty() {
+            database.search_prompts(&query, limit, options.highlight)?
+        } else {
+            // Tag filters apply after ranking, so rank every match first
+            let mut results = database.search_prompts(&query, u32::MAX as usize, options.highlight)?;
+            results.retain(|r| required_tags.iter().all(|tag| r.prompt.tags.contains(tag)));
+            results.truncate(limit);
+            results
+        };
         
         if results.is_empty() {
             println!("No results found for query: '{}'", query);
             return Ok(());
         }
         
+        if !options.explain {
+            for result in &mut results {
+                result.explanation = None;
+            }
+        }
+        
         // Format and display results
-        let formatter = OutputFormatter::new(color);
         let output = formatter.format_search_results(&results, &format);
         println!("{}", output);
         
@@ -33,4 +89,18 @@ impl SearchCommand {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Semantic results silently miss prompts without a vector for the current
+/// embedder, so say so
+pub(crate) fn warn_unindexed(database: &Database) -> Result<()> {
+    let (indexed, total) = database.embedding_coverage()?;
+    if indexed < total {
+        utils::print_warning(&format!(
+            "{} of {} prompt(s) have no embedding for the current embedder; run 'edisonprompt reindex'",
+            total - indexed,
+            total
+        ));
+    }
+    Ok(())
+}
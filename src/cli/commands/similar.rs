@@ -0,0 +1,33 @@
+use crate::{
+    database::Database,
+    error::Result,
+    cli::OutputFormat,
+    cli::output::OutputFormatter,
+    cli::commands::search::warn_unindexed,
+};
+
+pub struct SimilarCommand;
+
+impl SimilarCommand {
+    pub fn execute(
+        database: &Database,
+        name: String,
+        limit: usize,
+        format: OutputFormat,
+        formatter: &OutputFormatter,
+    ) -> Result<()> {
+        warn_unindexed(database)?;
+        
+        let results = database.similar_prompts(&name, limit)?;
+        
+        if results.is_empty() {
+            println!("No prompts similar to '{}'", name);
+            return Ok(());
+        }
+        
+        let output = formatter.format_search_results(&results, &format);
+        println!("{}", output);
+        
+        Ok(())
+    }
+}
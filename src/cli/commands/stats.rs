@@ -0,0 +1,90 @@
+use crate::{
+    database::Database,
+    database::stats::UsageStats,
+    error::Result,
+    cli::OutputFormat,
+    utils,
+};
+use colored::*;
+
+/// Width of the longest bar in the usage-over-time chart
+const CHART_WIDTH: u64 = 40;
+
+pub struct StatsCommand;
+
+impl StatsCommand {
+    pub fn execute(
+        database: &Database,
+        days: u32,
+        limit: usize,
+        format: OutputFormat,
+        tracking: bool,
+        color: bool,
+    ) -> Result<()> {
+        let stats = database.usage_stats(days, limit)?;
+        
+        if let OutputFormat::Json = format {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+            return Ok(());
+        }
+        
+        if !tracking {
+            utils::print_warning("Usage tracking is disabled ([usage] track = false); no new uses are recorded");
+        }
+        
+        Self::print_stats(&stats, days, color);
+        Ok(())
+    }
+    
+    fn print_stats(stats: &UsageStats, days: u32, color: bool) {
+        let heading = |text: &str| {
+            if color {
+                println!("\n{}", text.bold());
+            } else {
+                println!("\n{}", text);
+            }
+        };
+        
+        heading("Library");
+        println!(
+            "  {} prompt(s), {} tag(s), {} variable(s), database {}",
+            stats.library.prompts,
+            stats.library.tags,
+            stats.library.variables,
+            utils::format_bytes(stats.library.database_bytes)
+        );
+        
+        heading(&format!("Top prompts (last {} day(s))", days));
+        if stats.top.is_empty() {
+            println!("  No prompts used in this period");
+        } else {
+            println!("  {:<30} {:>6} {:>7}  Last used", "Name", "Uses", "Copied");
+            for usage in &stats.top {
+                println!(
+                    "  {:<30} {:>6} {:>7}  {}",
+                    utils::truncate_string(&usage.name, 30),
+                    usage.uses,
+                    usage.copies,
+                    usage.last_used_at
+                        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+        
+        heading(&format!("Never used ({})", stats.unused_count));
+        for name in &stats.unused {
+            println!("  {}", name);
+        }
+        if stats.unused_count > stats.unused.len() {
+            println!("  ... and {} more", stats.unused_count - stats.unused.len());
+        }
+        
+        heading(&format!("Usage over time ({} use(s), {} copied)", stats.total_uses, stats.copies));
+        let max = stats.daily.iter().map(|d| d.uses).max().unwrap_or(0).max(1);
+        for day in &stats.daily {
+            let width = ((day.uses * CHART_WIDTH + max - 1) / max) as usize;
+            println!("  {}  {:>4} {}", day.date.format("%Y-%m-%d"), day.uses, "█".repeat(width));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    database::Database,
+    database::sync::{self, Prompts, Repo},
+    error::{PromptedsError, Result},
+    cli::SyncAction,
+    template::secrets::drop_untrusted_commands,
+    utils,
+};
+use std::path::Path;
+
+pub struct SyncCommand;
+
+impl SyncCommand {
+    /// `dir` is the library's git working copy
+    pub fn execute(database: &mut Database, dir: &Path, action: SyncAction) -> Result<()> {
+        // The working copy holds prompts in the clear
+        if database.is_encrypted()? {
+            return Err(PromptedsError::SyncError {
+                details: "an encrypted library cannot be synced; its prompts would be pushed in plain text".to_string(),
+            });
+        }
+        match action {
+            SyncAction::Init { remote } => {
+                let repo = Repo::init(dir, &remote)?;
+                let remote_prompts = match repo.fetch()? {
+                    Some(remote) => repo.read(&remote)?.len(),
+                    None => 0,
+                };
+                utils::print_success(&format!("Sync set up with {}", repo.remote_url()?));
+                if remote_prompts > 0 {
+                    utils::print_info(&format!(
+                        "The remote has {} prompt(s); run 'sync pull' to merge them into this library",
+                        remote_prompts
+                    ));
+                } else {
+                    utils::print_info("Run 'sync push' to share this library");
+                }
+            }
+            SyncAction::Push => Self::push(database, &Repo::open(dir)?)?,
+            SyncAction::Pull => Self::pull(database, &Repo::open(dir)?)?,
+        }
+        Ok(())
+    }
+
+    fn push(database: &Database, repo: &Repo) -> Result<()> {
+        let prompts = Self::commit_library(database, repo)?;
+        let remote = repo.fetch()?;
+        let Some(head) = repo.head()? else {
+            utils::print_info("Nothing to push: the library is empty");
+            return Ok(());
+        };
+        if let Some(remote) = remote {
+            if remote == head {
+                utils::print_info("Already up to date");
+                return Ok(());
+            }
+            if !repo.is_ancestor(&remote, &head)? {
+                return Err(PromptedsError::SyncError {
+                    details: "the remote has changes that are not here; run 'sync pull' first".to_string(),
+                });
+            }
+        }
+
+        repo.push()?;
+        utils::print_success(&format!("Pushed {} prompt(s) to {}", prompts.len(), repo.remote_url()?));
+        Ok(())
+    }
+
+    fn pull(database: &mut Database, repo: &Repo) -> Result<()> {
+        let ours = Self::commit_library(database, repo)?;
+        let Some(remote) = repo.fetch()? else {
+            utils::print_info("The remote has no prompts yet; run 'sync push' to share this library");
+            return Ok(());
+        };
+        let head = repo.head()?;
+        if let Some(head) = &head {
+            if repo.is_ancestor(&remote, head)? {
+                utils::print_info("Already up to date");
+                return Ok(());
+            }
+        }
+
+        let base_commit = match &head {
+            Some(head) => repo.merge_base(head, &remote)?,
+            None => None,
+        };
+        let base = match &base_commit {
+            Some(commit) => repo.read(commit)?,
+            None => Prompts::new(),
+        };
+        let theirs = repo.read(&remote)?;
+        let mut merge = sync::merge(&base, &ours, &theirs);
+        
+        // Anyone with push access could add a command that runs on 'get'
+        for prompt in merge.prompts.values_mut() {
+            for name in drop_untrusted_commands(prompt, ours.get(&prompt.id)) {
+                utils::print_warning(&format!(
+                    "Ignored the command source of secret '{}' in '{}' from the remote; its value will be asked for",
+                    name, prompt.name
+                ));
+            }
+        }
+
+        // Deleting first frees names for renamed and new prompts
+        let deleted: Vec<_> = ours.values().filter(|p| !merge.prompts.contains_key(&p.id)).collect();
+        let updated: Vec<_> = merge
+            .prompts
+            .values()
+            .filter(|p| ours.get(&p.id).is_some_and(|o| !sync::same(o, p)))
+            .collect();
+        let created: Vec<_> = merge.prompts.values().filter(|p| !ours.contains_key(&p.id)).collect();
+
+        if !deleted.is_empty() || !updated.is_empty() {
+            if let Some(path) = database.auto_backup("pre-sync")? {
+                utils::print_info(&format!("Saved the current database to {}", path.display()));
+            }
+        }
+        database.transaction(|database| {
+            for prompt in &deleted {
+                database.delete_prompt(&prompt.name)?;
+            }
+            for prompt in &updated {
+                database.update_prompt(prompt)?;
+            }
+            for prompt in &created {
+                database.create_prompt(prompt)?;
+            }
+            Ok(true)
+        })?;
+
+        if head.is_none() || base_commit == head {
+            repo.fast_forward(&remote)?;
+        } else {
+            repo.start_merge(&remote)?;
+        }
+        repo.write(&merge.prompts)?;
+        repo.commit("Merge prompts from the remote")?;
+
+        for conflict in &merge.conflicts {
+            utils::print_warning(&format!("Conflict in '{}': {}", conflict.name, conflict.resolution));
+        }
+        utils::print_success(&format!(
+            "Pulled: {} created, {} updated, {} deleted, {} conflict(s)",
+            created.len(),
+            updated.len(),
+            deleted.len(),
+            merge.conflicts.len()
+        ));
+        if repo.head()? != Some(remote) {
+            utils::print_info("Run 'sync push' to share the merged library");
+        }
+        Ok(())
+    }
+
+    /// Write the library to the working copy and commit any changes since
+    /// the last sync; returns the prompts as written
+    fn commit_library(database: &Database, repo: &Repo) -> Result<Prompts> {
+        let prompts = sync::by_id(database.get_all_prompts()?);
+        repo.write(&prompts)?;
+        repo.commit("Update prompts")?;
+        Ok(prompts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{backup::Rotation, models::Prompt, ConnectionOptions};
+    use std::process::Command;
+
+    fn library(dir: &Path, name: &str) -> Database {
+        let options = ConnectionOptions {
+            backups: Some(Rotation { dir: dir.join(format!("{}-backups", name)), keep: 5, compress: false }),
+            ..ConnectionOptions::default()
+        };
+        Database::open(dir.join(format!("{}.db", name)), &options).unwrap()
+    }
+
+    fn sync(database: &mut Database, dir: &Path, action: SyncAction) {
+        SyncCommand::execute(database, dir, action).unwrap();
+    }
+
+    fn edit(database: &mut Database, name: &str, content: &str) {
+        let prompt = database.get_prompt(name).unwrap();
+        database.update_prompt(&Prompt { content: content.to_string(), ..prompt }).unwrap();
+    }
+
+    #[test]
+    fn test_libraries_sync_through_a_bare_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let status = Command::new("git").args(["init", "--quiet", "--bare"]).arg(&remote).status().unwrap();
+        assert!(status.success());
+        let remote = remote.to_string_lossy().into_owned();
+        let (here, there) = (dir.path().join("sync-here"), dir.path().join("sync-there"));
+
+        let mut ours = library(dir.path(), "here");
+        ours.create_prompt(&Prompt::new("review".to_string(), "one\ntwo\nthree".to_string())).unwrap();
+        ours.create_prompt(&Prompt::new("old".to_string(), "unused".to_string())).unwrap();
+        sync(&mut ours, &here, SyncAction::Init { remote: remote.clone() });
+        sync(&mut ours, &here, SyncAction::Push);
+
+        // One file per prompt, named by id
+        let files = Command::new("git").args(["--git-dir", &remote, "ls-tree", "-r", "--name-only", sync::BRANCH]).output().unwrap();
+        let mut files: Vec<String> = String::from_utf8(files.stdout).unwrap().lines().map(str::to_string).collect();
+        files.sort();
+        let mut expected: Vec<String> = ["review", "old"]
+            .iter()
+            .map(|name| format!("prompts/{}.json", ours.get_prompt(name).unwrap().id))
+            .collect();
+        expected.sort();
+        assert_eq!(files, expected);
+
+        let mut theirs = library(dir.path(), "there");
+        sync(&mut theirs, &there, SyncAction::Init { remote: remote.clone() });
+        sync(&mut theirs, &there, SyncAction::Pull);
+        assert_eq!(theirs.get_prompt("review").unwrap().id, ours.get_prompt("review").unwrap().id);
+        assert!(theirs.prompt_exists("old").unwrap());
+
+        // Edits to different lines on each side, and a deletion on the other
+        edit(&mut ours, "review", "ONE\ntwo\nthree");
+        edit(&mut theirs, "review", "one\ntwo\nTHREE");
+        theirs.delete_prompt("old").unwrap();
+        sync(&mut theirs, &there, SyncAction::Push);
+        assert!(SyncCommand::execute(&mut ours, &here, SyncAction::Push).is_err());
+
+        sync(&mut ours, &here, SyncAction::Pull);
+        assert_eq!(ours.get_prompt("review").unwrap().content, "ONE\ntwo\nTHREE");
+        assert!(!ours.prompt_exists("old").unwrap());
+        let backups: Vec<String> = std::fs::read_dir(dir.path().join("here-backups"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(backups.len() == 1 && backups[0].ends_with("-pre-sync.db"), "{:?}", backups);
+
+        sync(&mut ours, &here, SyncAction::Push);
+        sync(&mut theirs, &there, SyncAction::Pull);
+        assert_eq!(theirs.get_prompt("review").unwrap().content, "ONE\ntwo\nTHREE");
+    }
+}
@@ -2,4 +2,4 @@ pub mod args;
 pub mod commands;
 pub mod output;
 
-pub use args::{Cli, Commands, OutputFormat, SortField, MergeStrategy, Shell};
\ No newline at end of file
+pub use args::{Cli, Commands, CollectionAction, ConfigAction, EncryptionAction, FileFormat, ImportTool, LibraryAction, OutputFormat, SortField, MergeStrategy, Shell, SyncAction};
\ No newline at end of file
@@ -1,15 +1,26 @@
-use crate::database::models::{PromptSummary, SearchResult};
+use crate::config::TableWidths;
+use crate::database::models::{PromptSource, PromptSummary, SearchResult};
 use crate::cli::OutputFormat;
 use colored::*;
 use serde_json;
 
+/// Width of the date columns ("%Y-%m-%d %H:%M" plus padding)
+const DATE_WIDTH: usize = 20;
+
 pub struct OutputFormatter {
     color: bool,
+    widths: TableWidths,
 }
 
 impl OutputFormatter {
     pub fn new(color: bool) -> Self {
-        Self { color }
+        Self { color, widths: TableWidths::default() }
+    }
+
+    /// Use the configured table column widths
+    pub fn with_widths(mut self, widths: &TableWidths) -> Self {
+        self.widths = widths.clone();
+        self
     }
 
     pub fn format_prompt_list(&self, prompts: &[PromptSummary], format: &OutputFormat, names_only: bool) -> String {
@@ -35,18 +46,57 @@ impl OutputFormatter {
         }
     }
 
+    pub fn format_recent_list(&self, prompts: &[PromptSummary], format: &OutputFormat) -> String {
+        match format {
+            OutputFormat::Json => serde_json::to_string_pretty(prompts).unwrap_or_default(),
+            OutputFormat::Plain => prompts.iter()
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            OutputFormat::Table => {
+                let name_width = self.widths.name;
+                let header = format!("{:<2} {:<name_width$} {:<DATE_WIDTH$}", "", "Name", "Last used");
+                let mut output = if self.color { header.bold().to_string() } else { header };
+                output.push('\n');
+                output.push_str(&"-".repeat(3 + name_width + 1 + DATE_WIDTH));
+                output.push('\n');
+                for prompt in prompts {
+                    let last_used = prompt.last_used_at
+                        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    output.push_str(&format!(
+                        "{:<2} {:<name_width$} {:<DATE_WIDTH$}\n",
+                        marker(prompt),
+                        crate::utils::truncate_string(&prompt.name, name_width - 2),
+                        last_used
+                    ));
+                }
+                output
+            }
+        }
+    }
+
     fn format_table(&self, prompts: &[PromptSummary]) -> String {
         if prompts.is_empty() {
             return "No prompts found.".to_string();
         }
 
         let mut output = String::new();
+        let markers = prompts.iter().any(|p| p.pinned || p.favorite);
+        let sources = prompts.iter().any(|p| p.source == PromptSource::Project);
+        let TableWidths { name: name_width, variables: variables_width, tags: tags_width, .. } = self.widths;
         
         // Header
-        let header = format!(
-            "{:<30} {:<10} {:<10} {:<20}",
+        let mut header = format!(
+            "{:<name_width$} {:<variables_width$} {:<tags_width$} {:<DATE_WIDTH$}",
             "Name", "Variables", "Tags", "Updated"
         );
+        if markers {
+            header.insert_str(0, "   ");
+        }
+        if sources {
+            header.push_str(&format!(" {:<8}", "Source"));
+        }
         
         if self.color {
             output.push_str(&header.bold().to_string());
@@ -56,20 +106,27 @@ impl OutputFormatter {
         output.push('\n');
         
         // Separator
-        output.push_str(&"-".repeat(72));
+        let width = name_width + variables_width + tags_width + DATE_WIDTH + 3;
+        output.push_str(&"-".repeat(width + if markers { 3 } else { 0 } + if sources { 9 } else { 0 }));
         output.push('\n');
 
         // Rows
         for prompt in prompts {
             let updated = prompt.updated_at.format("%Y-%m-%d %H:%M").to_string();
+            if markers {
+                output.push_str(&format!("{:<2} ", marker(prompt)));
+            }
             let row = format!(
-                "{:<30} {:<10} {:<10} {:<20}",
-                crate::utils::truncate_string(&prompt.name, 28),
+                "{:<name_width$} {:<variables_width$} {:<tags_width$} {:<DATE_WIDTH$}",
+                crate::utils::truncate_string(&prompt.name, name_width - 2),
                 prompt.variable_count,
                 prompt.tag_count,
                 updated
             );
             output.push_str(&row);
+            if sources {
+                output.push_str(&format!(" {:<8}", source_label(prompt.source)));
+            }
             output.push('\n');
         }
 
@@ -81,13 +138,18 @@ impl OutputFormatter {
             return "No results found.".to_string();
         }
 
+        let explain = results.iter().any(|r| r.explanation.is_some());
         let mut output = String::new();
+        let TableWidths { name: name_width, content: content_width, .. } = self.widths;
         
         // Header
-        let header = format!(
-            "{:<30} {:<10} {:<50}",
+        let mut header = format!(
+            "{:<name_width$} {:<10} {:<content_width$}",
             "Name", "Score", "Content Preview"
         );
+        if explain {
+            header.push_str(&format!(" {:<8} {:<8} {:<8}", "Text", "Recency", "Usage"));
+        }
         
         if self.color {
             output.push_str(&header.bold().to_string());
@@ -97,23 +159,29 @@ impl OutputFormatter {
         output.push('\n');
         
         // Separator
-        output.push_str(&"-".repeat(92));
+        output.push_str(&"-".repeat(name_width + content_width + 12 + if explain { 27 } else { 0 }));
         output.push('\n');
 
         // Rows
         for result in results {
             let content_preview = if let Some(ref highlighted) = result.highlighted_content {
-                crate::utils::truncate_string(highlighted, 48)
+                self.render_highlights(&highlighted.replace('\n', " "), content_width)
             } else {
-                crate::utils::truncate_string(&result.prompt.content.replace('\n', " "), 48)
+                crate::utils::truncate_string(&result.prompt.content.replace('\n', " "), content_width - 2)
             };
             
-            let row = format!(
-                "{:<30} {:<10.2} {:<50}",
-                crate::utils::truncate_string(&result.prompt.name, 28),
+            let mut row = format!(
+                "{:<name_width$} {:<10.2} {:<content_width$}",
+                crate::utils::truncate_string(&result.prompt.name, name_width - 2),
                 result.score,
                 content_preview
             );
+            if let Some(ref breakdown) = result.explanation {
+                row.push_str(&format!(
+                    " {:<8.2} {:<8.2} {:<8.2}",
+                    breakdown.text, breakdown.recency, breakdown.usage
+                ));
+            }
             output.push_str(&row);
             output.push('\n');
         }
@@ -121,17 +189,141 @@ impl OutputFormatter {
         output
     }
 
+    /// Show `<mark>` spans from full-text search in color, truncated and
+    /// padded to `width` visible characters. Without color the marks are
+    /// dropped. The text is cut before any markup is added, so a cut never
+    /// lands inside it.
+    fn render_highlights(&self, text: &str, width: usize) -> String {
+        // Alternating plain and marked segments
+        let mut segments = Vec::new();
+        for (i, part) in text.split("<mark>").enumerate() {
+            match part.split_once("</mark>") {
+                Some((marked, plain)) if i > 0 => {
+                    segments.push((marked, true));
+                    segments.push((plain, false));
+                }
+                _ => segments.push((part, false)),
+            }
+        }
+        
+        let total: usize = segments.iter().map(|(text, _)| text.chars().count()).sum();
+        let mut budget = if total > width - 2 { (width - 2).saturating_sub(3) } else { total };
+        let visible = budget;
+        let mut rendered = String::new();
+        for (text, marked) in segments {
+            let shown: String = text.chars().take(budget).collect();
+            budget -= shown.chars().count();
+            if marked && self.color {
+                rendered.push_str(&shown.yellow().bold().to_string());
+            } else {
+                rendered.push_str(&shown);
+            }
+        }
+        let visible = if total > width - 2 {
+            rendered.push_str("...");
+            visible + 3
+        } else {
+            visible
+        };
+        
+        rendered.push_str(&" ".repeat(width.saturating_sub(visible)));
+        rendered
+    }
+
     fn format_plain(&self, prompts: &[PromptSummary]) -> String {
         prompts.iter()
-            .map(|p| format!("{} ({} vars, {} tags)", p.name, p.variable_count, p.tag_count))
+            .map(|p| {
+                let mut line = format!("{} ({} vars, {} tags)", p.name, p.variable_count, p.tag_count);
+                if p.pinned {
+                    line.push_str(" [pinned]");
+                }
+                if p.favorite {
+                    line.push_str(" [favorite]");
+                }
+                if p.source == PromptSource::Project {
+                    line.push_str(" [project]");
+                }
+                line
+            })
             .collect::<Vec<_>>()
             .join("\n")
     }
 
     fn format_search_plain(&self, results: &[SearchResult]) -> String {
         results.iter()
-            .map(|r| format!("{} (score: {:.2})", r.prompt.name, r.score))
+            .map(|r| match r.explanation {
+                Some(ref b) => format!(
+                    "{} (score: {:.2} = text {:.2} x recency {:.2} x usage {:.2}, {} uses)",
+                    r.prompt.name, r.score, b.text, b.recency, b.usage, b.use_count
+                ),
+                None => format!("{} (score: {:.2})", r.prompt.name, r.score),
+            })
             .collect::<Vec<_>>()
             .join("\n")
     }
-}
\ No newline at end of file
+}
+
+/// `*` for pinned and `♥` for favorite prompts
+pub(crate) fn marker(prompt: &PromptSummary) -> String {
+    let mut marker = String::new();
+    if prompt.pinned {
+        marker.push('*');
+    }
+    if prompt.favorite {
+        marker.push('♥');
+    }
+    marker
+}
+
+fn source_label(source: PromptSource) -> &'static str {
+    match source {
+        PromptSource::Library => "library",
+        PromptSource::Project => "project",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlights_without_color_drop_marks() {
+        let formatter = OutputFormatter::new(false);
+        let rendered = formatter.render_highlights("Review the <mark>code</mark> carefully", 40);
+        assert_eq!(rendered.trim_end(), "Review the code carefully");
+        assert_eq!(rendered.chars().count(), 40);
+
+        // Cut inside a marked span without leaving half a tag behind
+        let rendered = formatter.render_highlights("A long <mark>highlighted</mark> word", 14);
+        assert_eq!(rendered, "A long hi...  ");
+    }
+
+    #[test]
+    fn test_highlights_with_color_keep_padding() {
+        let rendered = OutputFormatter::new(true).render_highlights("See <mark>docs</mark> now", 20);
+        assert!(!rendered.contains("<mark>"));
+        assert_eq!(crate::utils::strip_ansi(&rendered), format!("{:<20}", "See docs now"));
+    }
+
+    #[test]
+    fn test_table_widths_apply() {
+        let widths = TableWidths { name: 12, ..TableWidths::default() };
+        let summary = PromptSummary {
+            name: "a-rather-long-prompt-name".to_string(),
+            variable_count: 1,
+            tag_count: 0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            pinned: false,
+            favorite: false,
+            last_used_at: None,
+            source: PromptSource::Library,
+        };
+        let table = OutputFormatter::new(false)
+            .with_widths(&widths)
+            .format_prompt_list(&[summary], &OutputFormat::Table, false);
+        let row = table.lines().nth(2).unwrap();
+        assert!(row.starts_with("a-rathe...   1"), "{}", row);
+        assert_eq!(table.lines().nth(1).unwrap().len(), 12 + 20 + 20 + DATE_WIDTH + 3);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::error::Result;
 use arboard::Clipboard;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{Command, Stdio};
 use std::time::Duration;
 
 pub struct ClipboardManager {
@@ -8,12 +11,22 @@ pub struct ClipboardManager {
     enable_fallback: bool,
 }
 
+/// What the helper started by `restore_after` is told, on its stdin so
+/// clipboard contents never show up in the process list
+#[derive(Serialize, Deserialize)]
+struct Restore {
+    /// Put back when the delay is over; the clipboard is cleared if `None`
+    previous: Option<String>,
+    /// Left alone if the clipboard no longer holds it
+    copied: String,
+    delay_ms: u64,
+    timeout_ms: u64,
+    enable_fallback: bool,
+}
+
 impl ClipboardManager {
     pub fn new(timeout_ms: u64, enable_fallback: bool) -> Self {
-        let clipboard = match Clipboard::new() {
-            Ok(clipboard) => Some(clipboard),
-            Err(_) => None,
-        };
+        let clipboard = Clipboard::new().ok();
 
         Self {
             clipboard,
@@ -64,6 +77,89 @@ impl ClipboardManager {
         }
     }
 
+    /// Empty the clipboard
+    pub fn clear(&mut self) -> Result<()> {
+        if let Some(ref mut clipboard) = self.clipboard {
+            match clipboard.clear() {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    if self.enable_fallback {
+                        self.fallback_clear()
+                    } else {
+                        Err(e.into())
+                    }
+                }
+            }
+        } else if self.enable_fallback {
+            self.fallback_clear()
+        } else {
+            Err(crate::error::PromptedsError::Clipboard(
+                arboard::Error::ContentNotAvailable
+            ))
+        }
+    }
+
+    /// After `delay`, put `previous` back on the clipboard (or clear it) if
+    /// it still holds `copied`. A detached helper process waits, so this
+    /// returns straight away.
+    pub fn restore_after(&self, previous: Option<String>, copied: &str, delay: Duration) -> Result<()> {
+        let restore = Restore {
+            previous,
+            copied: copied.to_string(),
+            delay_ms: delay.as_millis() as u64,
+            timeout_ms: self.timeout.as_millis() as u64,
+            enable_fallback: self.enable_fallback,
+        };
+
+        let mut command = Command::new(std::env::current_exe()?);
+        command
+            .arg("clipboard-restore")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Outlive the terminal's process group, so Ctrl+C does not stop it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const DETACHED_PROCESS: u32 = 0x0000_0008;
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+            command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+        }
+
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().expect("helper stdin is piped");
+        serde_json::to_writer(stdin, &restore)?;
+        Ok(())
+    }
+
+    /// The helper started by `restore_after`: read what to restore from
+    /// `input`, wait, then restore it
+    pub fn run_restore(input: impl Read) -> Result<()> {
+        let restore: Restore = serde_json::from_reader(input)?;
+        std::thread::sleep(Duration::from_millis(restore.delay_ms));
+
+        let mut clipboard = Self::new(restore.timeout_ms, restore.enable_fallback);
+        // Something else was copied meanwhile
+        if clipboard.get_text().ok().as_deref() != Some(restore.copied.as_str()) {
+            return Ok(());
+        }
+        match restore.previous {
+            Some(previous) => clipboard.set_text(&previous),
+            None => clipboard.clear(),
+        }
+    }
+
+    /// Whether the system clipboard could be opened, rather than only the
+    /// file fallback
+    pub fn has_system_clipboard(&self) -> bool {
+        self.clipboard.is_some()
+    }
+
     pub fn is_available(&self) -> bool {
         self.clipboard.is_some() || self.enable_fallback
     }
@@ -82,6 +178,14 @@ impl ClipboardManager {
         }
     }
 
+    fn fallback_clear(&self) -> Result<()> {
+        let temp_file = std::env::temp_dir().join("edisonprompt_clipboard.txt");
+        if temp_file.exists() {
+            std::fs::remove_file(temp_file)?;
+        }
+        Ok(())
+    }
+
     fn fallback_set_text(&self, text: &str) -> Result<()> {
         // In headless environments, write to a temporary file
         let temp_file = std::env::temp_dir().join("edisonprompt_clipboard.txt");
@@ -0,0 +1,344 @@
+use super::validate::{self, Issue};
+use crate::error::{PromptedsError, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Prefix of environment variables that override settings, e.g.
+/// `EDISONPROMPT_SEARCH__LIMIT=20` sets `search.limit`
+pub const ENV_PREFIX: &str = "EDISONPROMPT_";
+
+/// Where an effective setting came from, lowest precedence first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Env(String),
+    CommandLine(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::System(path) => write!(f, "system: {}", path.display()),
+            Origin::User(path) => write!(f, "user: {}", path.display()),
+            Origin::Project(path) => write!(f, "project: {}", path.display()),
+            Origin::Env(var) => write!(f, "env: {}", var),
+            Origin::CommandLine(flag) => write!(f, "command line: {}", flag),
+        }
+    }
+}
+
+/// Settings merged layer by layer, remembering which layer set each key
+pub struct LayeredConfig {
+    value: toml::Table,
+    origins: BTreeMap<String, Origin>,
+}
+
+impl LayeredConfig {
+    pub fn new(defaults: toml::Table) -> Self {
+        let mut layered = Self {
+            value: toml::Table::new(),
+            origins: BTreeMap::new(),
+        };
+        layered.apply(defaults, Origin::Default);
+        layered
+    }
+
+    /// Overlay `layer`, table by table; its leaves take `origin`
+    pub fn apply(&mut self, layer: toml::Table, origin: Origin) {
+        for (key, value) in layer {
+            merge(&mut self.value, &key, value, &origin, &mut self.origins);
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: toml::Value, origin: Origin) {
+        let mut layer = toml::Table::new();
+        set_path(&mut layer, key, value);
+        self.apply(layer, origin);
+    }
+
+    pub fn table(&self) -> &toml::Table {
+        &self.value
+    }
+
+    pub fn get(&self, key: &str) -> Option<&toml::Value> {
+        get_path(&self.value, key)
+    }
+
+    /// Origin of `key`; for a table, the highest-precedence origin among its leaves
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.origins.get(key).or_else(|| {
+            let prefix = format!("{}.", key);
+            self.origins
+                .iter()
+                .filter(|(k, _)| k.starts_with(&prefix))
+                .map(|(_, origin)| origin)
+                .max_by_key(|origin| precedence(origin))
+        })
+    }
+
+    /// Every leaf setting with its value and origin, sorted by key
+    pub fn entries(&self) -> Vec<(String, &toml::Value, &Origin)> {
+        self.origins
+            .iter()
+            .filter_map(|(key, origin)| self.get(key).map(|value| (key.clone(), value, origin)))
+            .collect()
+    }
+}
+
+fn merge(
+    table: &mut toml::Table,
+    key: &str,
+    value: toml::Value,
+    origin: &Origin,
+    origins: &mut BTreeMap<String, Origin>,
+) {
+    let path = |parent: &str, child: &str| {
+        if parent.is_empty() { child.to_string() } else { format!("{}.{}", parent, child) }
+    };
+    merge_at(table, "", key, value, origin, origins, &path);
+}
+
+fn merge_at(
+    table: &mut toml::Table,
+    parent: &str,
+    key: &str,
+    value: toml::Value,
+    origin: &Origin,
+    origins: &mut BTreeMap<String, Origin>,
+    path: &dyn Fn(&str, &str) -> String,
+) {
+    let full_key = path(parent, key);
+    match (table.get_mut(key), value) {
+        (Some(toml::Value::Table(existing)), toml::Value::Table(overrides)) => {
+            for (child, value) in overrides {
+                merge_at(existing, &full_key, &child, value, origin, origins, path);
+            }
+        }
+        (_, toml::Value::Table(overrides)) => {
+            // A table replacing a scalar, or a new table: record each leaf
+            let prefix = format!("{}.", full_key);
+            origins.retain(|k, _| k != &full_key && !k.starts_with(&prefix));
+            let mut fresh = toml::Table::new();
+            for (child, value) in overrides {
+                merge_at(&mut fresh, &full_key, &child, value, origin, origins, path);
+            }
+            table.insert(key.to_string(), toml::Value::Table(fresh));
+        }
+        (_, value) => {
+            let prefix = format!("{}.", full_key);
+            origins.retain(|k, _| !k.starts_with(&prefix));
+            origins.insert(full_key, origin.clone());
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn precedence(origin: &Origin) -> u8 {
+    match origin {
+        Origin::Default => 0,
+        Origin::System(_) => 1,
+        Origin::User(_) => 2,
+        Origin::Project(_) => 3,
+        Origin::Env(_) => 4,
+        Origin::CommandLine(_) => 5,
+    }
+}
+
+/// Value at a dotted key such as `search.limit`
+pub fn get_path<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (parents, leaf) = match key.rsplit_once('.') {
+        Some((parents, leaf)) => (Some(parents), leaf),
+        None => (None, key),
+    };
+    let mut current = table;
+    if let Some(parents) = parents {
+        for part in parents.split('.') {
+            current = current.get(part)?.as_table()?;
+        }
+    }
+    current.get(leaf)
+}
+
+/// Set a dotted key, creating intermediate tables
+pub fn set_path(table: &mut toml::Table, key: &str, value: toml::Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let child = table
+                .entry(head.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+            if !child.is_table() {
+                *child = toml::Value::Table(toml::Table::new());
+            }
+            if let toml::Value::Table(child) = child {
+                set_path(child, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Remove a dotted key, dropping tables it leaves empty. Returns whether
+/// anything was removed.
+pub fn remove_path(table: &mut toml::Table, key: &str) -> bool {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let Some(toml::Value::Table(child)) = table.get_mut(head) else {
+                return false;
+            };
+            let removed = remove_path(child, rest);
+            if removed && child.is_empty() {
+                table.remove(head);
+            }
+            removed
+        }
+        None => table.remove(key).is_some(),
+    }
+}
+
+/// Parse a value typed on the command line or in an environment variable,
+/// using the type of `template` (usually the default) when there is one
+pub fn parse_value(key: &str, raw: &str, template: Option<&toml::Value>) -> Result<toml::Value> {
+    let invalid = |expected: &str| PromptedsError::ConfigValue {
+        key: key.to_string(),
+        details: format!("expected {}, got '{}'", expected, raw),
+    };
+
+    match template {
+        Some(toml::Value::String(_)) => Ok(toml::Value::String(raw.to_string())),
+        Some(toml::Value::Integer(_)) => match raw.trim().parse::<i64>() {
+            Ok(n) if n >= 0 => Ok(toml::Value::Integer(n)),
+            _ => Err(invalid("a non-negative integer")),
+        },
+        Some(toml::Value::Float(_)) => raw.trim().parse().map(toml::Value::Float).map_err(|_| invalid("a number")),
+        Some(toml::Value::Boolean(_)) => match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(toml::Value::Boolean(true)),
+            "false" | "no" | "off" | "0" => Ok(toml::Value::Boolean(false)),
+            _ => Err(invalid("true or false")),
+        },
+        Some(toml::Value::Array(_)) => match parse_toml(raw) {
+            Some(value @ toml::Value::Array(_)) => Ok(value),
+            _ => Ok(toml::Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| toml::Value::String(s.to_string()))
+                    .collect(),
+            )),
+        },
+        Some(toml::Value::Table(_)) => Err(PromptedsError::ConfigValue {
+            key: key.to_string(),
+            details: "is a section; set one of its keys instead".to_string(),
+        }),
+        _ => Ok(parse_toml(raw).unwrap_or_else(|| toml::Value::String(raw.to_string()))),
+    }
+}
+
+fn parse_toml(raw: &str) -> Option<toml::Value> {
+    toml::from_str::<toml::Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+}
+
+/// Settings given as `EDISONPROMPT_SECTION__KEY` variables, checked against
+/// `schema`. Unknown keys and values that do not parse are reported instead.
+pub fn env_overrides<I>(vars: I, schema: &toml::Table) -> (Vec<(String, toml::Value, Origin)>, Vec<Issue>)
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut overrides = Vec::new();
+    let mut issues = Vec::new();
+    for (var, raw) in vars {
+        let Some(rest) = var.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if !rest.contains("__") {
+            continue;
+        }
+
+        let key = rest.to_lowercase().replace("__", ".");
+        let Some(template) = validate::template(schema, &key) else {
+            issues.push(Issue::warning(Some(var), validate::unknown_key_message(schema, &key)));
+            continue;
+        };
+
+        match parse_value(&key, &raw, Some(&template)) {
+            Ok(value) => overrides.push((key, value, Origin::Env(var))),
+            Err(e) => issues.push(Issue::error(Some(var), e.to_string())),
+        }
+    }
+    overrides.sort_by(|a, b| a.0.cmp(&b.0));
+    (overrides, issues)
+}
+
+/// Render a value for display: strings bare, everything else as TOML
+pub fn display_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_layers_track_origins() {
+        let mut layered = LayeredConfig::new(table("[search]\nlimit = 50\nhighlight = true\n"));
+        layered.apply(table("[search]\nlimit = 20\n"), Origin::User(PathBuf::from("user.toml")));
+
+        assert_eq!(layered.get("search.limit"), Some(&toml::Value::Integer(20)));
+        assert_eq!(layered.origin("search.limit"), Some(&Origin::User(PathBuf::from("user.toml"))));
+        assert_eq!(layered.origin("search.highlight"), Some(&Origin::Default));
+        assert_eq!(layered.entries().len(), 2);
+    }
+
+    #[test]
+    fn test_env_overrides_use_default_types() {
+        let schema = validate::schema().unwrap();
+        let vars = vec![
+            ("EDISONPROMPT_SEARCH__LIMIT".to_string(), "20".to_string()),
+            ("EDISONPROMPT_SEARCH__EMBEDDER".to_string(), "123".to_string()),
+            ("EDISONPROMPT_LIBRARY__PATHS__TEAM".to_string(), "/tmp/team.db".to_string()),
+            ("EDISONPROMPT_LIBRARY".to_string(), "team".to_string()),
+            ("EDISONPROMPT_SEARCH__LIMT".to_string(), "1".to_string()),
+            ("EDISONPROMPT_OUTPUT__COLOR".to_string(), "maybe".to_string()),
+        ];
+
+        let (overrides, issues) = env_overrides(vars, &schema);
+        let keys: Vec<&str> = overrides.iter().map(|(k, _, _)| k.as_str()).collect();
+        assert_eq!(keys, vec!["library.paths.team", "search.embedder", "search.limit"]);
+        assert_eq!(overrides[1].1, toml::Value::String("123".to_string()));
+        assert_eq!(overrides[2].1, toml::Value::Integer(20));
+
+        let issues: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "EDISONPROMPT_SEARCH__LIMT: unknown setting 'search.limt'; did you mean 'search.limit'?",
+                "EDISONPROMPT_OUTPUT__COLOR: Invalid value for 'output.color': expected true or false, got 'maybe'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_and_remove_path() {
+        let mut t = toml::Table::new();
+        set_path(&mut t, "output.table_widths.name", toml::Value::Integer(40));
+        assert_eq!(get_path(&t, "output.table_widths.name"), Some(&toml::Value::Integer(40)));
+        assert!(remove_path(&mut t, "output.table_widths.name"));
+        assert!(t.is_empty());
+        assert!(!remove_path(&mut t, "output.color"));
+    }
+}
@@ -1,36 +1,143 @@
 pub mod settings;
 pub mod paths;
+pub mod layers;
+pub mod validate;
 
-use crate::error::Result;
-use settings::Config;
-use paths::ConfigPaths;
-use std::path::Path;
+use crate::error::{PromptedsError, Result};
+use std::path::{Path, PathBuf};
 
 pub use settings::*;
 pub use paths::*;
+pub use layers::{LayeredConfig, Origin};
+pub use validate::{Issue, Severity};
 
+/// Name of the library backed by `database.path`
+pub const DEFAULT_LIBRARY: &str = "default";
+
+/// Settings a project config may not change: a cloned repository must not be
+/// able to run programs, redirect where prompts and backups are stored,
+/// turn off secret scanning or start keeping renders
+const UNTRUSTED_PROJECT_KEYS: &[(&str, Option<&str>)] = &[
+    ("database", None),
+    ("library", None),
+    ("editor", None),
+    ("search", Some("embedder")),
+    ("backup", None),
+    ("scan", None),
+    ("clipboard", Some("history")),
+];
+
+/// Settings are layered, each layer overriding the ones before it:
+/// built-in defaults, the system config file, the user config file, the
+/// config of the nearest project, `EDISONPROMPT_SECTION__KEY` environment
+/// variables, then command-line flags. Only the user layer is ever written.
 pub struct ConfigManager {
     paths: ConfigPaths,
     config: Config,
+    layers: LayeredConfig,
+    system_file: Option<PathBuf>,
+    system_layer: toml::Table,
+    config_file: PathBuf,
+    user_layer: toml::Table,
+    project_dir: Option<PathBuf>,
+    project_layer: toml::Table,
+    env_layer: Vec<(String, toml::Value, Origin)>,
+    cli_layer: Vec<(String, toml::Value, Origin)>,
+    /// Contents of the config files read, for locating settings in messages
+    sources: Vec<(PathBuf, String)>,
+    /// Problems found while reading the layers
+    load_issues: Vec<Issue>,
+    /// False when the user config file exists but is not valid TOML
+    user_file_parsed: bool,
+    /// Library chosen on the command line or through the environment
+    library: Option<String>,
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self> {
         let paths = ConfigPaths::new()?;
+        let config_file = paths.config_file();
+        Self::load(paths, config_file)
+    }
+
+    /// Use `config_path` as the user config file; the other layers still apply
+    pub fn with_custom_config<P: AsRef<Path>>(config_path: P) -> Result<Self> {
+        let config_file = config_path.as_ref().to_path_buf();
+        if !config_file.exists() {
+            return Err(PromptedsError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("config file {} not found", config_file.display()),
+            )));
+        }
+        Self::load(ConfigPaths::new()?, config_file)
+    }
+
+    fn load(paths: ConfigPaths, config_file: PathBuf) -> Result<Self> {
         paths.ensure_dirs()?;
         
-        let config = Self::load_config(&paths)?;
+        let mut manager = Self::from_parts(paths, config_file.clone());
+        manager.system_file = ConfigPaths::system_config_file();
+        if let Some(path) = manager.system_file.clone() {
+            manager.system_layer = manager.read_layer(&path)?.unwrap_or_default();
+        }
+        match manager.read_layer(&config_file)? {
+            Some(layer) => manager.user_layer = layer,
+            None => manager.user_file_parsed = !config_file.exists(),
+        }
         
-        Ok(Self { paths, config })
+        let (env_layer, env_issues) = layers::env_overrides(std::env::vars(), &validate::schema()?);
+        manager.env_layer = env_layer;
+        manager.load_issues.extend(env_issues);
+        manager.rebuild()?;
+        Ok(manager)
     }
 
-    pub fn with_custom_config<P: AsRef<Path>>(config_path: P) -> Result<Self> {
-        let paths = ConfigPaths::new()?;
-        paths.ensure_dirs()?;
+    fn from_parts(paths: ConfigPaths, config_file: PathBuf) -> Self {
+        Self {
+            paths,
+            config: Config::default(),
+            layers: LayeredConfig::new(toml::Table::new()),
+            system_file: None,
+            system_layer: toml::Table::new(),
+            config_file,
+            user_layer: toml::Table::new(),
+            project_dir: None,
+            project_layer: toml::Table::new(),
+            env_layer: Vec::new(),
+            cli_layer: Vec::new(),
+            sources: Vec::new(),
+            load_issues: Vec::new(),
+            user_file_parsed: true,
+            library: None,
+        }
+    }
+
+    /// Look for a `.edisonprompt/` directory in `start` and its ancestors and
+    /// layer its `config.toml` over the user settings
+    pub fn with_project(mut self, start: &Path) -> Result<Self> {
+        self.project_dir = ConfigPaths::find_project_dir(start);
         
-        let config = Self::load_config_from_file(config_path)?;
+        if let Some(config_file) = self.project_config_file() {
+            if config_file.exists() {
+                self.project_layer = self.read_project_layer(&config_file)?;
+                self.rebuild()?;
+            }
+        }
         
-        Ok(Self { paths, config })
+        Ok(self)
+    }
+
+    /// Override `key` from a command-line flag, above every other layer
+    pub fn with_cli_override(mut self, key: &str, value: toml::Value, flag: &str) -> Result<Self> {
+        self.cli_layer.push((key.to_string(), value, Origin::CommandLine(flag.to_string())));
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Use `library` instead of the configured active library
+    pub fn with_library(mut self, library: Option<String>) -> Self {
+        self.library = library;
+        self
     }
 
     pub fn config(&self) -> &Config {
@@ -41,44 +148,363 @@ impl ConfigManager {
         &self.paths
     }
 
-    fn load_config(paths: &ConfigPaths) -> Result<Config> {
-        let config_file = paths.config_file();
+    /// Effective settings with the layer each one came from
+    pub fn layers(&self) -> &LayeredConfig {
+        &self.layers
+    }
+
+    /// The `.edisonprompt/` directory of the current project, if any
+    pub fn project_dir(&self) -> Option<&Path> {
+        self.project_dir.as_deref()
+    }
+
+    /// The project's prompts file, if the project has one
+    pub fn project_prompts_file(&self) -> Option<PathBuf> {
+        let dir = self.project_dir.as_ref()?;
+        PROJECT_PROMPTS_FILES.iter().map(|name| dir.join(name)).find(|path| path.exists())
+    }
+
+    /// Config files of every layer that has one, lowest precedence first
+    pub fn config_files(&self) -> Vec<(&'static str, PathBuf)> {
+        let mut files = Vec::new();
+        if let Some(path) = &self.system_file {
+            files.push(("system", path.clone()));
+        }
+        files.push(("user", self.config_file.clone()));
+        if let Some(path) = self.project_config_file() {
+            files.push(("project", path));
+        }
+        files
+    }
+
+    /// The user config file, which `config set` and `config unset` write
+    pub fn user_config_file(&self) -> &Path {
+        &self.config_file
+    }
+
+    /// Set a user setting and write the user config file
+    pub fn set_user_value(&mut self, key: &str, value: toml::Value) -> Result<()> {
+        self.ensure_user_file_parsed()?;
+        let mut user_layer = self.user_layer.clone();
+        layers::set_path(&mut user_layer, key, value);
+        self.replace_user_layer(user_layer)
+    }
+
+    /// Remove a user setting, returning whether the user config file had it
+    pub fn unset_user_value(&mut self, key: &str) -> Result<bool> {
+        self.ensure_user_file_parsed()?;
+        let mut user_layer = self.user_layer.clone();
+        if !layers::remove_path(&mut user_layer, key) {
+            return Ok(false);
+        }
+        self.replace_user_layer(user_layer)?;
+        Ok(true)
+    }
+
+    /// Re-read the user config file, e.g. after it was edited by hand
+    pub fn reload_user_config(&mut self) -> Result<()> {
+        let path = self.config_file.clone();
+        self.load_issues.retain(|issue| !issue_in_file(issue, &path));
+        self.sources.retain(|(source, _)| source != &path);
         
-        if config_file.exists() {
-            Self::load_config_from_file(config_file)
-        } else {
-            // Create default config file
-            let default_config = Config::default();
-            Self::save_config(&default_config, &config_file)?;
-            Ok(default_config)
+        let layer = self.read_layer(&path)?;
+        self.user_file_parsed = layer.is_some() || !path.exists();
+        self.user_layer = layer.unwrap_or_default();
+        self.rebuild()
+    }
+
+    /// Problems in the configuration: those found while reading the layers,
+    /// then those in the effective values. `check_programs` also looks up
+    /// the editor and embedder on PATH.
+    pub fn validate(&self, check_programs: bool) -> Vec<Issue> {
+        let locate = |key: &str| self.locate(key);
+        let resolve = |path: &Path| self.resolve_path(path);
+        let cx = validate::Context { locate: &locate, resolve: &resolve, check_programs };
+        
+        let mut issues = self.load_issues.clone();
+        issues.extend(validate::check_config(&self.config, &cx));
+        issues
+    }
+
+    /// Where the effective value of `key` was set, as `file:line`, an
+    /// environment variable or a flag
+    fn locate(&self, key: &str) -> Option<String> {
+        match self.layers.origin(key)? {
+            Origin::Default => None,
+            Origin::System(path) | Origin::User(path) | Origin::Project(path) => {
+                let content = self.sources.iter().find(|(source, _)| source == path).map(|(_, c)| c.as_str());
+                Some(validate::Source { path, content: content.unwrap_or_default() }.location(key))
+            }
+            Origin::Env(var) => Some(var.clone()),
+            Origin::CommandLine(flag) => Some(flag.clone()),
         }
     }
 
-    fn load_config_from_file<P: AsRef<Path>>(path: P) -> Result<Config> {
-        let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
-        Ok(config)
+    fn ensure_user_file_parsed(&self) -> Result<()> {
+        if self.user_file_parsed {
+            return Ok(());
+        }
+        Err(PromptedsError::ConfigValidation {
+            issues: self
+                .load_issues
+                .iter()
+                .filter(|issue| issue.is_error() && issue_in_file(issue, &self.config_file))
+                .cloned()
+                .collect(),
+        })
     }
 
-    fn save_config(config: &Config, path: &Path) -> Result<()> {
-        let content = toml::to_string_pretty(config)?;
-        std::fs::write(path, content)?;
+    /// Read one config file and check its keys and types. A missing file is
+    /// an empty layer; a file that is not valid TOML is reported and skipped.
+    fn read_layer(&mut self, path: &Path) -> Result<Option<toml::Table>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Some(toml::Table::new())),
+            Err(e) => return Err(e.into()),
+        };
+        
+        let source = validate::Source { path, content: &content };
+        let layer = match toml::from_str::<toml::Table>(&content) {
+            Ok(mut layer) => {
+                let issues = validate::check_layer(&mut layer, &validate::schema()?, &source);
+                self.load_issues.extend(issues);
+                Some(layer)
+            }
+            Err(e) => {
+                self.load_issues.push(validate::parse_error(&source, &e));
+                None
+            }
+        };
+        self.sources.push((path.to_path_buf(), content));
+        Ok(layer)
+    }
+
+    fn replace_user_layer(&mut self, user_layer: toml::Table) -> Result<()> {
+        // Check the result before writing so a bad value never reaches the
+        // file; problems that were already there do not block the change
+        let before: Vec<String> = self.validate(false).iter().map(|issue| issue.to_string()).collect();
+        let previous = std::mem::replace(&mut self.user_layer, user_layer);
+        let introduced = self.rebuild().map(|()| {
+            self.validate(false)
+                .into_iter()
+                .filter(|issue| issue.is_error() && !before.contains(&issue.to_string()))
+                .collect::<Vec<_>>()
+        });
+        match introduced {
+            Ok(issues) if issues.is_empty() => {}
+            Ok(issues) => {
+                self.user_layer = previous;
+                self.rebuild()?;
+                return Err(PromptedsError::ConfigValidation { issues });
+            }
+            Err(e) => {
+                self.user_layer = previous;
+                self.rebuild()?;
+                return Err(e);
+            }
+        }
+        
+        if let Some(dir) = self.config_file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.config_file, toml::to_string_pretty(&self.user_layer)?)?;
+        Ok(())
+    }
+
+    fn rebuild(&mut self) -> Result<()> {
+        let mut layers = LayeredConfig::new(defaults_table()?);
+        if let Some(path) = &self.system_file {
+            layers.apply(self.system_layer.clone(), Origin::System(path.clone()));
+        }
+        layers.apply(self.user_layer.clone(), Origin::User(self.config_file.clone()));
+        if let Some(path) = self.project_config_file() {
+            layers.apply(self.project_layer.clone(), Origin::Project(path));
+        }
+        for (key, value, origin) in self.env_layer.iter().chain(&self.cli_layer) {
+            layers.set(key, value.clone(), origin.clone());
+        }
+        
+        self.config = toml::Value::Table(layers.table().clone()).try_into()?;
+        self.layers = layers;
         Ok(())
     }
 
-    pub fn get_database_path(&self) -> std::path::PathBuf {
-        if self.config.database.path.to_string_lossy().starts_with('~') {
-            ConfigPaths::expand_home(&self.config.database.path)
-        } else if self.config.database.path.is_relative() {
-            self.paths.data_dir().join(&self.config.database.path)
+    fn project_config_file(&self) -> Option<PathBuf> {
+        self.project_dir.as_ref().map(|dir| dir.join("config.toml"))
+    }
+
+    fn read_project_layer(&mut self, path: &Path) -> Result<toml::Table> {
+        let Some(mut overrides) = self.read_layer(path)? else {
+            return Ok(toml::Table::new());
+        };
+        
+        let mut ignored = Vec::new();
+        for (section, key) in UNTRUSTED_PROJECT_KEYS {
+            match key {
+                None => {
+                    if overrides.remove(*section).is_some() {
+                        ignored.push(section.to_string());
+                    }
+                }
+                Some(key) => {
+                    if let Some(toml::Value::Table(table)) = overrides.get_mut(*section) {
+                        if table.remove(*key).is_some() {
+                            ignored.push(format!("{}.{}", section, key));
+                        }
+                    }
+                }
+            }
+        }
+        if !ignored.is_empty() {
+            self.load_issues.push(Issue::warning(
+                Some(path.display().to_string()),
+                format!("ignoring settings a project may not change: {}", ignored.join(", ")),
+            ));
+        }
+        
+        Ok(overrides)
+    }
+
+    /// Database file of the selected library
+    pub fn get_database_path(&self) -> Result<PathBuf> {
+        self.library_path(&self.active_library())
+    }
+
+    /// The library to use: --library or EDISONPROMPT_LIBRARY, then
+    /// `library.active`, then the default library
+    pub fn active_library(&self) -> String {
+        self.library
+            .clone()
+            .or_else(|| self.config.library.active.clone())
+            .unwrap_or_else(|| DEFAULT_LIBRARY.to_string())
+    }
+
+    /// All library names, the default library first
+    pub fn library_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_LIBRARY.to_string())
+            .chain(self.config.library.paths.keys().filter(|n| *n != DEFAULT_LIBRARY).cloned())
+            .collect()
+    }
+
+    pub fn library_path(&self, name: &str) -> Result<PathBuf> {
+        let path = match self.config.library.paths.get(name) {
+            Some(path) => path,
+            None if name == DEFAULT_LIBRARY => &self.config.database.path,
+            None => {
+                return Err(PromptedsError::LibraryNotFound {
+                    name: name.to_string(),
+                    available: self.library_names(),
+                })
+            }
+        };
+        Ok(self.resolve_path(path))
+    }
+
+    /// Directory of the automatic and default backups of a library
+    pub fn backup_dir(&self, library: &str) -> PathBuf {
+        self.resolve_path(&self.config.backup.directory).join(library)
+    }
+
+    /// Git working copy that `sync` keeps for `library`
+    pub fn sync_dir(&self, library: &str) -> PathBuf {
+        self.paths.data_dir().join("sync").join(library)
+    }
+
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.to_string_lossy().starts_with('~') {
+            ConfigPaths::expand_home(path)
+        } else if path.is_relative() {
+            self.paths.data_dir().join(path)
         } else {
-            self.config.database.path.clone()
+            path.to_path_buf()
         }
     }
 }
 
+/// Built-in defaults as a TOML table, the bottom layer
+fn defaults_table() -> Result<toml::Table> {
+    match toml::Value::try_from(Config::default())? {
+        toml::Value::Table(table) => Ok(table),
+        _ => Ok(toml::Table::new()),
+    }
+}
+
+fn issue_in_file(issue: &Issue, path: &Path) -> bool {
+    let path = path.display().to_string();
+    issue.location.as_deref().is_some_and(|location| {
+        location == path || location.strip_prefix(path.as_str()).is_some_and(|rest| rest.starts_with(':'))
+    })
+}
+
 impl Default for ConfigManager {
     fn default() -> Self {
         Self::new().expect("Failed to initialize config manager")
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_config_layers_over_user_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join(PROJECT_DIR_NAME);
+        std::fs::create_dir_all(project.join("nested")).unwrap();
+        std::fs::write(
+            project.join("config.toml"),
+            "[output]\nformat = \"json\"\n[editor]\ncommand = \"evil\"\n[backup]\ndirectory = \"backups\"\n[scan]\nsecrets = \"off\"\n[clipboard]\nhistory = 10\n",
+        )
+        .unwrap();
+
+        let mut manager = ConfigManager::from_parts(ConfigPaths::new().unwrap(), dir.path().join("user.toml"));
+        manager.user_layer = toml::from_str("[output]\nformat = \"plain\"\ncolor = false\n").unwrap();
+        let manager = manager
+            .with_project(&project.join("nested"))
+            .unwrap()
+            .with_cli_override("output.color", toml::Value::Boolean(true), "--color")
+            .unwrap();
+
+        assert_eq!(manager.project_dir(), Some(project.as_path()));
+        assert_eq!(manager.config().output.format, "json");
+        assert!(manager.config().output.color);
+        assert!(manager.config().editor.command.is_none());
+        assert_eq!(manager.config().backup.directory, Config::default().backup.directory);
+        assert_eq!(manager.config().scan.secrets, "warn");
+        assert_eq!(manager.config().clipboard.history, 0);
+        assert_eq!(
+            manager.layers().origin("output.format"),
+            Some(&Origin::Project(project.join("config.toml")))
+        );
+        assert_eq!(
+            manager.layers().origin("output.color"),
+            Some(&Origin::CommandLine("--color".to_string()))
+        );
+        assert_eq!(manager.layers().origin("search.limit"), Some(&Origin::Default));
+    }
+
+    #[test]
+    fn test_libraries_resolve_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = ConfigManager::from_parts(ConfigPaths::new().unwrap(), dir.path().join("user.toml"));
+        let work = dir.path().join("work.db");
+        manager
+            .set_user_value("library.paths.work", toml::Value::String(work.to_string_lossy().into_owned()))
+            .unwrap();
+        manager.set_user_value("library.active", toml::Value::String("work".to_string())).unwrap();
+
+        assert_eq!(manager.library_names(), vec![DEFAULT_LIBRARY.to_string(), "work".to_string()]);
+        assert_eq!(manager.active_library(), "work");
+        assert_eq!(manager.get_database_path().unwrap(), work);
+        assert!(matches!(
+            manager.library_path("missing"),
+            Err(PromptedsError::LibraryNotFound { .. })
+        ));
+
+        let manager = manager.with_library(Some(DEFAULT_LIBRARY.to_string()));
+        assert_eq!(manager.active_library(), DEFAULT_LIBRARY);
+        assert_eq!(
+            manager.get_database_path().unwrap(),
+            manager.resolve_path(&manager.config().database.path)
+        );
+    }
+}
@@ -2,6 +2,12 @@ use crate::error::{PromptedsError, Result};
 use directories::ProjectDirs;
 use std::path::{Path, PathBuf};
 
+/// Directory holding a project's prompts and settings, committed with its code
+pub const PROJECT_DIR_NAME: &str = ".edisonprompt";
+
+/// Names of a project's prompts file, in the order they are looked for
+pub const PROJECT_PROMPTS_FILES: &[&str] = &["prompts.json", "prompts.yaml", "prompts.yml", "prompts.toml"];
+
 pub struct ConfigPaths {
     project_dirs: ProjectDirs,
 }
@@ -34,6 +40,20 @@ impl ConfigPaths {
         self.config_dir().join("config.toml")
     }
 
+    /// Machine-wide config file shared by all users: `EDISONPROMPT_SYSTEM_CONFIG`,
+    /// else `/etc/edisonprompt/config.toml` (`%PROGRAMDATA%` on Windows)
+    pub fn system_config_file() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("EDISONPROMPT_SYSTEM_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        if cfg!(windows) {
+            std::env::var_os("PROGRAMDATA")
+                .map(|dir| PathBuf::from(dir).join("edisonprompt").join("config.toml"))
+        } else {
+            Some(PathBuf::from("/etc/edisonprompt/config.toml"))
+        }
+    }
+
     /// Get the default database file path
     pub fn database_file(&self) -> PathBuf {
         self.data_dir().join("prompts.db")
@@ -47,6 +67,14 @@ impl ConfigPaths {
         Ok(())
     }
 
+    /// Nearest `.edisonprompt/` directory in `start` or one of its ancestors
+    pub fn find_project_dir(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_DIR_NAME))
+            .find(|dir| dir.is_dir())
+    }
+
     /// Expand home directory in path
     pub fn expand_home<P: AsRef<Path>>(path: P) -> PathBuf {
         let path = path.as_ref();
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
     pub editor: EditorConfig,
@@ -9,6 +10,14 @@ pub struct Config {
     pub search: SearchConfig,
     pub output: OutputConfig,
     pub template: TemplateConfig,
+    #[serde(default)]
+    pub usage: UsageConfig,
+    #[serde(default)]
+    pub library: LibraryConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +52,9 @@ pub struct ClipboardConfig {
     /// Enable clipboard fallback to file I/O in headless environments
     #[serde(default = "default_true")]
     pub enable_fallback: bool,
+    /// Recent renders kept for `get --paste-last`; 0 keeps none
+    #[serde(default)]
+    pub history: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +68,21 @@ pub struct SearchConfig {
     /// FTS5 ranking algorithm
     #[serde(default = "default_ranking")]
     pub ranking: String,
+    /// BM25 weight for matches in the prompt name
+    #[serde(default = "default_name_weight")]
+    pub name_weight: f64,
+    /// BM25 weight for matches in the prompt content
+    #[serde(default = "default_content_weight")]
+    pub content_weight: f64,
+    /// Half-life in days for boosting recently updated prompts (0 disables)
+    #[serde(default)]
+    pub recency_half_life_days: f64,
+    /// Strength of the boost for frequently used prompts (0 disables)
+    #[serde(default)]
+    pub usage_boost: f64,
+    /// Embedder for semantic search: "hashing" or "command:<program>"
+    #[serde(default = "default_embedder")]
+    pub embedder: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +106,9 @@ pub struct TableWidths {
     pub variables: usize,
     #[serde(default = "default_tags_width")]
     pub tags: usize,
+    /// Content preview column of search results
+    #[serde(default = "default_content_width")]
+    pub content: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,33 +121,74 @@ pub struct TemplateConfig {
     pub syntax: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageConfig {
+    /// Record when prompts are fetched (names of variables only, never values)
+    #[serde(default = "default_true")]
+    pub track: bool,
+}
+
+/// Named prompt libraries, each with its own database file. The library
+/// called "default" is always available and uses `database.path`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    /// Library used when neither --library nor EDISONPROMPT_LIBRARY is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    /// Database file of each named library (supports ~ expansion)
+    #[serde(default)]
+    pub paths: BTreeMap<String, PathBuf>,
+}
+
+/// Automatic backups taken before migrations, overwriting imports and restores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupConfig {
+    /// Backup directory, with one subdirectory per library (supports ~ expansion)
+    #[serde(default = "default_backup_directory")]
+    pub directory: PathBuf,
+    /// Automatic backups kept per library (0 disables them)
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+    /// Compress backups with gzip
+    #[serde(default = "default_true")]
+    pub compress: bool,
+}
+
+/// Checks for secrets and personal data in prompts before they are saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// Tokens, keys and other credentials: "off", "warn" or "block"
+    #[serde(default = "default_scan_action")]
+    pub secrets: String,
+    /// Email addresses and phone numbers: "off", "warn" or "block"
+    #[serde(default = "default_scan_action")]
+    pub pii: String,
+    /// Regular expressions for matches that are fine to save
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+}
+
 // Default value functions
 fn default_db_timeout() -> u64 { 5000 }
 fn default_clipboard_timeout() -> u64 { 5000 }
 fn default_search_limit() -> usize { 50 }
 fn default_fallback_editor() -> String { "nano".to_string() }
 fn default_ranking() -> String { "bm25".to_string() }
+fn default_name_weight() -> f64 { 10.0 }
+fn default_content_weight() -> f64 { 1.0 }
+fn default_embedder() -> String { "hashing".to_string() }
 fn default_format() -> String { "table".to_string() }
 fn default_template_syntax() -> String { "handlebars".to_string() }
 fn default_name_width() -> usize { 30 }
 fn default_variables_width() -> usize { 20 }
 fn default_tags_width() -> usize { 20 }
+fn default_content_width() -> usize { 50 }
+fn default_backup_directory() -> PathBuf { PathBuf::from("~/.local/share/edisonprompt/backups") }
+fn default_backup_keep() -> usize { 5 }
+fn default_scan_action() -> String { "warn".to_string() }
 fn default_true() -> bool { true }
 fn default_false() -> bool { false }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            database: DatabaseConfig::default(),
-            editor: EditorConfig::default(),
-            clipboard: ClipboardConfig::default(),
-            search: SearchConfig::default(),
-            output: OutputConfig::default(),
-            template: TemplateConfig::default(),
-        }
-    }
-}
-
 impl Default for DatabaseConfig {
     fn default() -> Self {
         Self {
@@ -143,6 +214,7 @@ impl Default for ClipboardConfig {
         Self {
             timeout_ms: default_clipboard_timeout(),
             enable_fallback: default_true(),
+            history: 0,
         }
     }
 }
@@ -153,6 +225,11 @@ impl Default for SearchConfig {
             limit: default_search_limit(),
             highlight: default_true(),
             ranking: default_ranking(),
+            name_weight: default_name_weight(),
+            content_weight: default_content_weight(),
+            recency_half_life_days: 0.0,
+            usage_boost: 0.0,
+            embedder: default_embedder(),
         }
     }
 }
@@ -173,6 +250,7 @@ impl Default for TableWidths {
             name: default_name_width(),
             variables: default_variables_width(),
             tags: default_tags_width(),
+            content: default_content_width(),
         }
     }
 }
@@ -184,4 +262,32 @@ impl Default for TemplateConfig {
             syntax: default_template_syntax(),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            track: default_true(),
+        }
+    }
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_backup_directory(),
+            keep: default_backup_keep(),
+            compress: default_true(),
+        }
+    }
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            secrets: default_scan_action(),
+            pii: default_scan_action(),
+            allowlist: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use super::settings::Config;
+use crate::database::search::{edit_similarity, trigram_similarity};
+use crate::error::Result;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+const OUTPUT_FORMATS: &[&str] = &["table", "json", "plain"];
+const RANKINGS: &[&str] = &["bm25", "rank"];
+const TEMPLATE_SYNTAXES: &[&str] = &["handlebars"];
+
+/// Keys without a default, so absent from the serialized defaults
+const OPTIONAL_KEYS: &[&str] = &["editor.command", "library.active"];
+
+/// Tables whose keys are chosen by the user; every value is a string
+const OPEN_TABLES: &[&str] = &["library.paths"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in the configuration, with where it came from when known
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    /// `file:line`, an environment variable or a flag
+    pub location: Option<String>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn error(location: Option<String>, message: String) -> Self {
+        Self { severity: Severity::Error, location, message }
+    }
+
+    pub fn warning(location: Option<String>, message: String) -> Self {
+        Self { severity: Severity::Warning, location, message }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A config file as read from disk, for locating keys in error messages
+pub struct Source<'a> {
+    pub path: &'a Path,
+    pub content: &'a str,
+}
+
+impl Source<'_> {
+    pub fn location(&self, key: &str) -> String {
+        match locate(self.content, key) {
+            Some(line) => format!("{}:{}", self.path.display(), line),
+            None => self.path.display().to_string(),
+        }
+    }
+}
+
+/// Every known setting with its default; optional settings map to an
+/// empty string
+pub fn schema() -> Result<toml::Table> {
+    let mut schema = match toml::Value::try_from(Config::default())? {
+        toml::Value::Table(table) => table,
+        _ => toml::Table::new(),
+    };
+    for key in OPTIONAL_KEYS {
+        super::layers::set_path(&mut schema, key, toml::Value::String(String::new()));
+    }
+    Ok(schema)
+}
+
+/// The value a setting is checked against, or `None` for unknown keys
+pub fn template(schema: &toml::Table, key: &str) -> Option<toml::Value> {
+    if let Some(value) = super::layers::get_path(schema, key) {
+        return Some(value.clone());
+    }
+    let (table, _) = key.rsplit_once('.')?;
+    OPEN_TABLES
+        .contains(&table)
+        .then(|| toml::Value::String(String::new()))
+}
+
+/// Closest known setting or section to a mistyped `key`
+pub fn suggest_key(schema: &toml::Table, key: &str) -> Option<String> {
+    let mut known = Vec::new();
+    collect_keys(schema, "", &mut known);
+
+    known
+        .into_iter()
+        .map(|candidate| {
+            let score = trigram_similarity(key, &candidate).max(edit_similarity(key, &candidate));
+            (candidate, score)
+        })
+        .filter(|(_, score)| *score >= 0.5)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(candidate, _)| candidate)
+}
+
+fn collect_keys(table: &toml::Table, prefix: &str, out: &mut Vec<String>) {
+    for (key, value) in table {
+        let full = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        if let toml::Value::Table(child) = value {
+            collect_keys(child, &full, out);
+        }
+        out.push(full);
+    }
+}
+
+pub fn unknown_key_message(schema: &toml::Table, key: &str) -> String {
+    match suggest_key(schema, key) {
+        Some(suggestion) => format!("unknown setting '{}'; did you mean '{}'?", key, suggestion),
+        None => format!("unknown setting '{}'", key),
+    }
+}
+
+/// Issue for a file that is not valid TOML, located at the parse error
+pub fn parse_error(source: &Source, error: &toml::de::Error) -> Issue {
+    let location = match error.span() {
+        Some(span) => {
+            let line = source.content[..span.start.min(source.content.len())].matches('\n').count() + 1;
+            format!("{}:{}", source.path.display(), line)
+        }
+        None => source.path.display().to_string(),
+    };
+    let message: Vec<&str> = error.message().lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+    Issue::error(Some(location), message.join("; "))
+}
+
+/// Check the keys and value types of one layer. Unknown keys are reported
+/// and kept; values of the wrong type are reported and removed, so the
+/// remaining layers still load.
+pub fn check_layer(layer: &mut toml::Table, schema: &toml::Table, source: &Source) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    check_table(layer, "", schema, source, &mut issues);
+    issues
+}
+
+fn check_table(table: &mut toml::Table, prefix: &str, schema: &toml::Table, source: &Source, issues: &mut Vec<Issue>) {
+    let keys: Vec<String> = table.keys().cloned().collect();
+    for key in keys {
+        let full = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        let Some(template) = template(schema, &full) else {
+            issues.push(Issue::warning(Some(source.location(&full)), unknown_key_message(schema, &full)));
+            continue;
+        };
+
+        let remove = match (table.get_mut(&key), &template) {
+            (Some(toml::Value::Table(child)), toml::Value::Table(_)) => {
+                check_table(child, &full, schema, source, issues);
+                false
+            }
+            (Some(value), template) => match type_mismatch(value, template) {
+                Some(message) => {
+                    issues.push(Issue::error(Some(source.location(&full)), format!("{}: {}", full, message)));
+                    true
+                }
+                None => false,
+            },
+            (None, _) => false,
+        };
+        if remove {
+            table.remove(&key);
+        }
+    }
+}
+
+/// Why `value` cannot stand in for `template`, if it cannot
+pub fn type_mismatch(value: &toml::Value, template: &toml::Value) -> Option<String> {
+    let found = describe(value);
+    match (template, value) {
+        (toml::Value::String(_), toml::Value::String(_)) => None,
+        (toml::Value::Integer(_), toml::Value::Integer(n)) if *n < 0 => Some(format!("must not be negative, found {}", n)),
+        (toml::Value::Integer(_), toml::Value::Integer(_)) => None,
+        (toml::Value::Float(_), toml::Value::Float(_) | toml::Value::Integer(_)) => None,
+        (toml::Value::Boolean(_), toml::Value::Boolean(_)) => None,
+        (toml::Value::Array(_), toml::Value::Array(items)) => items
+            .iter()
+            .find(|item| !item.is_str())
+            .map(|item| format!("expected a list of strings, found {}", describe(item))),
+        (toml::Value::Table(_), _) => Some(format!("expected a section, found {}", found)),
+        (template, _) => Some(format!("expected {}, found {}", describe_type(template), found)),
+    }
+}
+
+fn describe(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => format!("string \"{}\"", s),
+        toml::Value::Table(_) => "a section".to_string(),
+        toml::Value::Array(_) => "a list".to_string(),
+        other => format!("{} {}", other.type_str(), other),
+    }
+}
+
+fn describe_type(template: &toml::Value) -> &'static str {
+    match template {
+        toml::Value::String(_) => "a string",
+        toml::Value::Integer(_) => "a non-negative integer",
+        toml::Value::Float(_) => "a number",
+        toml::Value::Boolean(_) => "true or false",
+        toml::Value::Array(_) => "a list of strings",
+        toml::Value::Datetime(_) => "a date",
+        toml::Value::Table(_) => "a section",
+    }
+}
+
+/// What `check_config` needs from the config manager
+pub struct Context<'a> {
+    /// Where the effective value of a key was set
+    pub locate: &'a dyn Fn(&str) -> Option<String>,
+    /// Absolute path of a configured database path
+    pub resolve: &'a dyn Fn(&Path) -> PathBuf,
+    /// Also look up the editor and embedder programs on PATH
+    pub check_programs: bool,
+}
+
+/// Check the values of the effective configuration
+pub fn check_config(config: &Config, cx: &Context) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut error = |key: &str, message: String| {
+        issues.push(Issue::error((cx.locate)(key), format!("{}: {}", key, message)));
+    };
+
+    one_of(&mut error, "output.format", &config.output.format, OUTPUT_FORMATS);
+    one_of(&mut error, "search.ranking", &config.search.ranking, RANKINGS);
+    one_of(&mut error, "template.syntax", &config.template.syntax, TEMPLATE_SYNTAXES);
+    one_of(&mut error, "scan.secrets", &config.scan.secrets, crate::template::scan::ACTIONS);
+    one_of(&mut error, "scan.pii", &config.scan.pii, crate::template::scan::ACTIONS);
+    for pattern in &config.scan.allowlist {
+        if let Err(e) = regex::Regex::new(pattern) {
+            error("scan.allowlist", format!("\"{}\" is not a valid regular expression: {}", pattern, e));
+        }
+    }
+
+    let embedder = &config.search.embedder;
+    let embedder_ok = embedder == "hashing"
+        || embedder.strip_prefix("command:").is_some_and(|program| !program.trim().is_empty());
+    if !embedder_ok {
+        error("search.embedder", format!("expected \"hashing\" or \"command:<program>\", found \"{}\"", embedder));
+    }
+
+    if config.search.limit == 0 {
+        error("search.limit", "must be at least 1".to_string());
+    }
+    if config.clipboard.timeout_ms == 0 {
+        error("clipboard.timeout_ms", "must be at least 1".to_string());
+    }
+    let widths = &config.output.table_widths;
+    for (key, width) in [
+        ("name", widths.name),
+        ("variables", widths.variables),
+        ("tags", widths.tags),
+        ("content", widths.content),
+    ] {
+        if width < 4 {
+            error(&format!("output.table_widths.{}", key), format!("must be at least 4, found {}", width));
+        }
+    }
+    for (key, value) in [
+        ("search.name_weight", config.search.name_weight),
+        ("search.content_weight", config.search.content_weight),
+        ("search.recency_half_life_days", config.search.recency_half_life_days),
+        ("search.usage_boost", config.search.usage_boost),
+    ] {
+        if !value.is_finite() || value < 0.0 {
+            error(key, format!("must be zero or more, found {}", value));
+        }
+    }
+
+    if let Some(message) = database_path_problem(&(cx.resolve)(&config.database.path)) {
+        error("database.path", message);
+    }
+    for (name, path) in &config.library.paths {
+        if let Some(message) = database_path_problem(&(cx.resolve)(path)) {
+            error(&format!("library.paths.{}", name), message);
+        }
+    }
+    let backups = (cx.resolve)(&config.backup.directory);
+    if backups.exists() && !backups.is_dir() {
+        error("backup.directory", format!("{} is not a directory", backups.display()));
+    }
+    if let Some(active) = &config.library.active {
+        if active != super::DEFAULT_LIBRARY && !config.library.paths.contains_key(active) {
+            error("library.active", format!("no library named '{}' in [library.paths]", active));
+        }
+    }
+
+    if cx.check_programs {
+        let (key, editor) = match (&config.editor.command, std::env::var("EDITOR")) {
+            (Some(command), _) => ("editor.command", Some(command.clone())),
+            (None, Ok(_)) => ("editor.fallback", None),
+            (None, Err(_)) => ("editor.fallback", Some(config.editor.fallback.clone())),
+        };
+        if let Some(editor) = editor.filter(|editor| find_program(editor).is_none()) {
+            issues.push(Issue::warning((cx.locate)(key), format!("{}: '{}' was not found on PATH", key, editor)));
+        }
+        if let Some(program) = embedder.strip_prefix("command:").map(str::trim) {
+            let program = program.split_whitespace().next().unwrap_or(program);
+            if !program.is_empty() && find_program(program).is_none() {
+                issues.push(Issue::warning(
+                    (cx.locate)("search.embedder"),
+                    format!("search.embedder: '{}' was not found on PATH", program),
+                ));
+            }
+        }
+    }
+
+    issues
+}
+
+fn one_of(error: &mut impl FnMut(&str, String), key: &str, value: &str, allowed: &[&str]) {
+    if !allowed.contains(&value) {
+        error(key, format!("expected one of {}, found \"{}\"", allowed.join(", "), value));
+    }
+}
+
+fn database_path_problem(path: &Path) -> Option<String> {
+    if path.is_dir() {
+        return Some(format!("{} is a directory, expected a database file", path.display()));
+    }
+    // The database and its directories are created on first use, which only
+    // fails if part of the way is a file
+    let blocked = path.ancestors().skip(1).find(|dir| dir.exists()).filter(|dir| !dir.is_dir());
+    blocked.map(|file| format!("cannot create {}: {} is not a directory", path.display(), file.display()))
+}
+
+/// Full path of `program`, searching PATH unless it contains a separator
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return program.is_file().then(|| program.to_path_buf());
+    }
+
+    let extensions: &[&str] = if cfg!(windows) { &["", ".exe", ".cmd", ".bat"] } else { &[""] };
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| {
+            extensions
+                .iter()
+                .map(move |ext| dir.join(format!("{}{}", program.display(), ext)))
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+/// Line (1-based) where `key` is set in TOML `content`, following
+/// `[section]` headers and dotted keys
+pub fn locate(content: &str, key: &str) -> Option<usize> {
+    let mut section = String::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            let header = line.trim_start_matches('[');
+            let header = header.split(']').next().unwrap_or_default();
+            section = normalize_key(header);
+            if section == key {
+                return Some(index + 1);
+            }
+        } else if let Some((name, _)) = line.split_once('=') {
+            if line.starts_with('#') {
+                continue;
+            }
+            let name = normalize_key(name);
+            let full = if section.is_empty() { name } else { format!("{}.{}", section, name) };
+            if full == key {
+                return Some(index + 1);
+            }
+        }
+    }
+    None
+}
+
+fn normalize_key(key: &str) -> String {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').trim_matches('\''))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_layer_reports_unknown_keys_and_bad_types() {
+        let content = "[search]\nlimt = 20\nhighlight = \"yes\"\n\n[output]\nformat = \"json\"\n";
+        let mut layer: toml::Table = toml::from_str(content).unwrap();
+        let source = Source { path: Path::new("config.toml"), content };
+
+        let issues = check_layer(&mut layer, &schema().unwrap(), &source);
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(
+            issues[0].to_string(),
+            "config.toml:2: unknown setting 'search.limt'; did you mean 'search.limit'?"
+        );
+        assert!(issues[1].is_error());
+        assert!(issues[1].to_string().starts_with("config.toml:3: search.highlight: expected true or false"));
+        assert!(super::super::layers::get_path(&layer, "search.highlight").is_none());
+        assert!(super::super::layers::get_path(&layer, "output.format").is_some());
+    }
+
+    #[test]
+    fn test_check_config_rejects_unsupported_values() {
+        let mut config = Config::default();
+        config.output.format = "yaml".to_string();
+        config.search.limit = 0;
+        let cx = Context {
+            locate: &|key| Some(format!("at {}", key)),
+            resolve: &|path| std::env::temp_dir().join(path.file_name().unwrap()),
+            check_programs: false,
+        };
+
+        let messages: Vec<String> = check_config(&config, &cx).iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "at output.format: output.format: expected one of table, json, plain, found \"yaml\"",
+                "at search.limit: search.limit: must be at least 1",
+            ]
+        );
+    }
+}
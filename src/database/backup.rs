@@ -0,0 +1,239 @@
+use crate::error::{PromptedsError, Result};
+use super::schema;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rusqlite::{backup::Backup, Connection, DatabaseName, OpenFlags};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempPath;
+
+/// Timestamp at the start of backup file names; sorts oldest first. It goes
+/// down to the millisecond so backups taken one after another get their own
+/// file.
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Where the automatic backups of one library go and how many are kept
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    pub dir: PathBuf,
+    pub keep: usize,
+    pub compress: bool,
+}
+
+/// A backup file that passed the checks in `verify`
+pub struct Snapshot {
+    /// Private uncompressed copy of the backup, removed on drop
+    file: TempPath,
+    pub schema_version: i32,
+    pub prompts: usize,
+}
+
+/// File name of a backup taken now; automatic backups carry their reason
+pub fn file_name(reason: Option<&str>, compress: bool) -> String {
+    let timestamp = chrono::Local::now().format(TIMESTAMP_FORMAT);
+    let reason = reason.map(|r| format!("-{}", r)).unwrap_or_default();
+    let extension = if compress { "db.gz" } else { "db" };
+    format!("{}{}.{}", timestamp, reason, extension)
+}
+
+/// Copy the database to `dest` with SQLite's online backup API, which takes
+/// a consistent snapshot while other connections read and write. The copy
+/// is a self-contained file (no WAL) and appears at `dest` only once
+/// complete.
+pub fn snapshot(conn: &Connection, dest: &Path, compress: bool) -> Result<()> {
+    let dir = match dest.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    let copy = tempfile::Builder::new().prefix(".edisonprompt-backup").tempfile_in(dir)?;
+    {
+        let mut target = Connection::open(copy.path())?;
+        Backup::new(conn, &mut target)?.run_to_completion(100, Duration::from_millis(50), None)?;
+        target.query_row("PRAGMA journal_mode = DELETE", [], |_| Ok(()))?;
+    }
+
+    if !compress {
+        copy.persist_noclobber(dest).map_err(|e| exists_error(dest, e.error))?;
+        return Ok(());
+    }
+
+    let compressed = tempfile::Builder::new().prefix(".edisonprompt-backup").tempfile_in(dir)?;
+    let mut encoder = GzEncoder::new(compressed.as_file(), Compression::default());
+    io::copy(&mut File::open(copy.path())?, &mut encoder)?;
+    encoder.finish()?;
+    compressed.persist_noclobber(dest).map_err(|e| exists_error(dest, e.error))?;
+    Ok(())
+}
+
+/// A backup never replaces an existing file
+fn exists_error(dest: &Path, error: io::Error) -> PromptedsError {
+    if error.kind() == io::ErrorKind::AlreadyExists {
+        PromptedsError::BackupError { details: format!("{} already exists", dest.display()) }
+    } else {
+        error.into()
+    }
+}
+
+/// Take an automatic backup into `rotation.dir`, then delete the oldest
+/// automatic backups beyond `rotation.keep`. Backups taken by hand are
+/// never deleted.
+pub fn rotate(conn: &Connection, rotation: &Rotation, reason: &str) -> Result<PathBuf> {
+    let dest = rotation.dir.join(file_name(Some(reason), rotation.compress));
+    snapshot(conn, &dest, rotation.compress)?;
+
+    let mut automatic: Vec<PathBuf> = fs::read_dir(&rotation.dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(is_automatic))
+        .collect();
+    automatic.sort();
+    let excess = automatic.len().saturating_sub(rotation.keep.max(1));
+    for old in &automatic[..excess] {
+        fs::remove_file(old)?;
+    }
+
+    Ok(dest)
+}
+
+/// Names made by `file_name` with a reason
+fn is_automatic(name: &str) -> bool {
+    let stem = name
+        .strip_suffix(".db.gz")
+        .or_else(|| name.strip_suffix(".db"))
+        .unwrap_or("");
+    let timestamp_len = "YYYYmmdd-HHMMSS-fff".len();
+    stem.len() > timestamp_len + 1
+        && stem.is_char_boundary(timestamp_len)
+        && chrono::NaiveDateTime::parse_from_str(&stem[..timestamp_len], TIMESTAMP_FORMAT).is_ok()
+        && stem[timestamp_len..].starts_with('-')
+}
+
+/// Check that `path` (plain or gzip-compressed) is an intact edisonprompt
+/// database this version can open. The checks run on a private copy, which
+/// is also what gets restored, so the backup file itself is never opened
+/// as a database.
+pub fn verify(path: &Path) -> Result<Snapshot> {
+    let invalid = |details: String| PromptedsError::BackupError {
+        details: format!("{}: {}", path.display(), details),
+    };
+
+    let mut magic = [0u8; 2];
+    let compressed = File::open(path)?.read(&mut magic)? == 2 && magic == GZIP_MAGIC;
+    let mut copy = tempfile::Builder::new().prefix(".edisonprompt-restore").tempfile()?;
+    if compressed {
+        io::copy(&mut GzDecoder::new(File::open(path)?), &mut copy)
+            .map_err(|e| invalid(format!("cannot decompress: {}", e)))?;
+    } else {
+        io::copy(&mut File::open(path)?, &mut copy)?;
+    }
+    let file = copy.into_temp_path();
+
+    let (schema_version, prompts) = {
+        let conn = Connection::open_with_flags(&file, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        let check: String = conn
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|e| invalid(format!("not a readable SQLite database ({})", e)))?;
+        if check != "ok" {
+            return Err(invalid(format!("integrity check failed: {}", check)));
+        }
+
+        let has_prompts: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'prompts')",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_prompts {
+            return Err(invalid("not an edisonprompt database".to_string()));
+        }
+        let version = schema::get_schema_version(&conn)?;
+        if version > schema::SCHEMA_VERSION {
+            return Err(invalid(format!(
+                "schema version {} is newer than this edisonprompt supports ({}); upgrade edisonprompt first",
+                version,
+                schema::SCHEMA_VERSION
+            )));
+        }
+        let prompts: i64 = conn.query_row("SELECT COUNT(*) FROM prompts", [], |row| row.get(0))?;
+        (version, prompts as usize)
+    };
+
+    Ok(Snapshot { file, schema_version, prompts })
+}
+
+/// Replace the contents of the database behind `conn` with `snapshot`,
+/// through the backup API so other connections see either the old or the
+/// new database
+pub fn restore(conn: &mut Connection, snapshot: &Snapshot) -> Result<()> {
+    let source = Connection::open_with_flags(&snapshot.file, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    Backup::new_with_names(&source, DatabaseName::Main, conn, DatabaseName::Main)?
+        .run_to_completion(100, Duration::from_millis(50), None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_verify_restore_and_rotate() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("prompts.db")).unwrap();
+        schema::initialize_database(&conn).unwrap();
+        conn.execute("INSERT INTO prompts (id, name, content) VALUES ('1', 'a', 'hello')", []).unwrap();
+
+        let rotation = Rotation { dir: dir.path().join("backups"), keep: 2, compress: true };
+        let backup = rotate(&conn, &rotation, "pre-import").unwrap();
+        let snapshot = verify(&backup).unwrap();
+        assert_eq!((snapshot.schema_version, snapshot.prompts), (schema::SCHEMA_VERSION, 1));
+
+        let mut other = Connection::open(dir.path().join("other.db")).unwrap();
+        schema::initialize_database(&other).unwrap();
+        restore(&mut other, &snapshot).unwrap();
+        let name: String = other.query_row("SELECT name FROM prompts", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "a");
+
+        // Manual backups are left alone by rotation. The first backup goes,
+        // so the result does not depend on whether the clock ticked since.
+        fs::remove_file(&backup).unwrap();
+        fs::write(rotation.dir.join("20000101-000000-000.db"), "").unwrap();
+        for (n, reason) in ["pre-a", "pre-b", "pre-c"].iter().enumerate() {
+            fs::write(rotation.dir.join(format!("2000010{}-000000-000-{}.db", n + 2, reason)), "").unwrap();
+        }
+        rotate(&conn, &rotation, "pre-import").unwrap();
+        let mut names: Vec<String> = fs::read_dir(&rotation.dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], "20000101-000000-000.db");
+        assert!(names[1].starts_with("20000104-000000-000-pre-c"));
+
+        assert!(verify(&rotation.dir.join("20000101-000000-000.db")).is_err());
+    }
+
+    #[test]
+    fn test_backups_never_replace_each_other() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("prompts.db")).unwrap();
+        schema::initialize_database(&conn).unwrap();
+
+        let rotation = Rotation { dir: dir.path().join("backups"), keep: 5, compress: false };
+        let first = rotate(&conn, &rotation, "pre-import").unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+        let second = rotate(&conn, &rotation, "pre-import").unwrap();
+        assert_ne!(first, second);
+        assert!(is_automatic(first.file_name().unwrap().to_str().unwrap()));
+
+        let taken = dir.path().join("taken.db");
+        fs::write(&taken, "keep me").unwrap();
+        for compress in [false, true] {
+            let error = snapshot(&conn, &taken, compress).unwrap_err();
+            assert!(error.to_string().contains("already exists"), "{}", error);
+        }
+        assert_eq!(fs::read_to_string(&taken).unwrap(), "keep me");
+    }
+}
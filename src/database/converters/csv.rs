@@ -0,0 +1,43 @@
+use crate::error::Result;
+use crate::database::models::Prompt;
+use super::{build_prompt, invalid, to_handlebars, Converter, Input, Syntax, Tool};
+
+/// A spreadsheet with a header row naming `name`, `content` and optionally
+/// `tags` columns, in any order; tags are separated by commas or semicolons
+pub struct CsvConverter;
+
+impl Converter for CsvConverter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>> {
+        let text = input.text(Tool::Csv)?;
+        let mut reader = ::csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|e| invalid(Tool::Csv, e.to_string()))?
+            .clone();
+        let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let (Some(name_column), Some(content_column)) = (column("name"), column("content")) else {
+            return Err(invalid(Tool::Csv, "the header row needs 'name' and 'content' columns".to_string()));
+        };
+        let tags_column = column("tags");
+
+        let mut prompts = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| invalid(Tool::Csv, e.to_string()))?;
+            let field = |column: usize| record.get(column).unwrap_or("").trim();
+            if field(name_column).is_empty() && field(content_column).is_empty() {
+                continue;
+            }
+            let line = record.position().map_or(0, |p| p.line());
+            if field(name_column).is_empty() || field(content_column).is_empty() {
+                return Err(invalid(Tool::Csv, format!("line {}: missing name or content", line)));
+            }
+
+            let tags = tags_column
+                .map(|column| field(column).split([',', ';']).map(str::to_string).collect())
+                .unwrap_or_default();
+            let content = to_handlebars(field(content_column), Syntax::Mixed);
+            prompts.push(build_prompt(field(name_column).to_string(), content, tags, &[])?);
+        }
+        Ok(prompts)
+    }
+}
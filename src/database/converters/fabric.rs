@@ -0,0 +1,46 @@
+use crate::error::Result;
+use crate::database::models::Prompt;
+use super::{build_prompt, file_stem, invalid, to_handlebars, Converter, Input, Syntax, Tool};
+use std::fs;
+use std::path::Path;
+
+/// Fabric patterns: a `patterns/` directory (or a checkout containing one)
+/// with a `<name>/system.md` per pattern, or a single pattern directory. A
+/// pattern's `user.md`, if any, follows its system prompt.
+pub struct FabricConverter;
+
+impl Converter for FabricConverter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>> {
+        let Input::Directory(dir) = input else {
+            return Err(invalid(Tool::Fabric, "--input must be a patterns directory".to_string()));
+        };
+        if dir.join("system.md").is_file() {
+            return Ok(vec![pattern(dir)?]);
+        }
+
+        let patterns = if dir.join("patterns").is_dir() { dir.join("patterns") } else { dir.clone() };
+        let mut dirs: Vec<_> = fs::read_dir(&patterns)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.join("system.md").is_file())
+            .collect();
+        if dirs.is_empty() {
+            return Err(invalid(
+                Tool::Fabric,
+                format!("no <pattern>/system.md files in {}", patterns.display()),
+            ));
+        }
+        dirs.sort();
+        dirs.iter().map(|dir| pattern(dir)).collect()
+    }
+}
+
+fn pattern(dir: &Path) -> Result<Prompt> {
+    let name = file_stem(dir).unwrap_or_default();
+    let mut content = fs::read_to_string(dir.join("system.md"))?.trim().to_string();
+    if let Ok(user) = fs::read_to_string(dir.join("user.md")) {
+        if !user.trim().is_empty() {
+            content = format!("{}\n\n{}", content, user.trim());
+        }
+    }
+    build_prompt(name, to_handlebars(&content, Syntax::Handlebars), Vec::new(), &[])
+}
@@ -0,0 +1,163 @@
+use crate::error::Result;
+use crate::database::models::{Prompt, Variable};
+use super::{build_prompt, invalid, to_handlebars, Converter, Input, Syntax, Tool};
+use serde_json::Value;
+
+/// Prompts serialized by LangChain (`dumps`, `save`) or pulled from the
+/// LangSmith hub: `PromptTemplate` and `ChatPromptTemplate` objects, the
+/// legacy `{"_type": "prompt"}` files, hub responses wrapping a `manifest`,
+/// or a list of any of these
+pub struct LangchainConverter;
+
+impl Converter for LangchainConverter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>> {
+        let text = input.text(Tool::Langchain)?;
+        let value: Value = serde_json::from_str(&text).map_err(|e| invalid(Tool::Langchain, e.to_string()))?;
+        let stem = input.stem().unwrap_or_else(|| "langchain-prompt".to_string());
+        match value {
+            Value::Array(items) => items.iter().map(|item| prompt(item, &stem)).collect(),
+            value => Ok(vec![prompt(&value, &stem)?]),
+        }
+    }
+}
+
+fn prompt(value: &Value, stem: &str) -> Result<Prompt> {
+    let (template, repo) = match value.get("manifest") {
+        Some(manifest) => (manifest, value.get("repo").or_else(|| value.get("repo_handle"))),
+        None => (value, None),
+    };
+    let kwargs = template.get("kwargs").unwrap_or(template);
+    let metadata = kwargs.get("metadata");
+
+    let name = [
+        value.get("name"),
+        kwargs.get("name"),
+        metadata.and_then(|m| m.get("lc_hub_repo")),
+        repo,
+    ]
+    .into_iter()
+    .flatten()
+    .find_map(Value::as_str)
+    .map(|name| name.rsplit('/').next().unwrap_or(name).to_string())
+    .unwrap_or_else(|| stem.to_string());
+
+    let mut declared = Vec::new();
+    let content = render(template, &mut declared)?;
+    let tags = kwargs
+        .get("tags")
+        .and_then(Value::as_array)
+        .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+    build_prompt(name, content, tags, &declared)
+}
+
+/// Handlebars text of a prompt template; `partial_variables` become
+/// variable defaults in `declared`
+fn render(template: &Value, declared: &mut Vec<Variable>) -> Result<String> {
+    let kwargs = template.get("kwargs").unwrap_or(template);
+
+    if let Some(text) = kwargs.get("template").and_then(Value::as_str) {
+        let syntax = match kwargs.get("template_format").and_then(Value::as_str) {
+            Some("mustache") | Some("jinja2") => Syntax::Handlebars,
+            _ => Syntax::FString,
+        };
+        if let Some(partials) = kwargs.get("partial_variables").and_then(Value::as_object) {
+            for (name, default) in partials {
+                if let Some(default) = default.as_str() {
+                    declared.push(Variable::new(name.clone()).with_default(default.to_string()));
+                }
+            }
+        }
+        return Ok(to_handlebars(text, syntax));
+    }
+
+    if let Some(messages) = kwargs.get("messages").and_then(Value::as_array) {
+        let mut parts = Vec::new();
+        for message in messages {
+            parts.push(render_message(message, declared)?);
+        }
+        return Ok(parts.join("\n\n"));
+    }
+
+    Err(invalid(
+        Tool::Langchain,
+        format!("unsupported prompt type {}", class_name(template).unwrap_or("(unknown)")),
+    ))
+}
+
+/// One message of a chat prompt, headed by its role
+fn render_message(message: &Value, declared: &mut Vec<Variable>) -> Result<String> {
+    // Messages are either serialized objects or `[role, template]` pairs
+    if let Some([role, text]) = message.as_array().map(Vec::as_slice) {
+        let role = role.as_str().unwrap_or("user");
+        return Ok(format!("{}:\n{}", role, to_handlebars(text.as_str().unwrap_or(""), Syntax::FString)));
+    }
+
+    let class = class_name(message).unwrap_or("");
+    let kwargs = message.get("kwargs").unwrap_or(message);
+    if class == "MessagesPlaceholder" {
+        let variable = kwargs.get("variable_name").and_then(Value::as_str).unwrap_or("messages");
+        return Ok(format!("{{{{{}}}}}", variable));
+    }
+
+    let role = if class.starts_with("System") {
+        "system"
+    } else if class.starts_with("AI") {
+        "assistant"
+    } else {
+        "user"
+    };
+    let text = match kwargs.get("prompt") {
+        Some(prompt) => render(prompt, declared)?,
+        None => kwargs.get("content").and_then(Value::as_str).unwrap_or("").to_string(),
+    };
+    Ok(format!("{}:\n{}", role, text))
+}
+
+/// Class of a serialized LangChain object: the last element of its `id`
+fn class_name(value: &Value) -> Option<&str> {
+    value.get("id")?.as_array()?.last()?.as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_templates_become_one_prompt() {
+        let manifest = r#"{
+            "repo": "hwchase17/code-review",
+            "manifest": {
+                "lc": 1, "type": "constructor",
+                "id": ["langchain", "prompts", "chat", "ChatPromptTemplate"],
+                "kwargs": {
+                    "input_variables": ["language", "code"],
+                    "messages": [
+                        {"lc": 1, "type": "constructor",
+                         "id": ["langchain", "prompts", "chat", "SystemMessagePromptTemplate"],
+                         "kwargs": {"prompt": {"lc": 1, "type": "constructor",
+                            "id": ["langchain", "prompts", "prompt", "PromptTemplate"],
+                            "kwargs": {"template": "You review {language} code. Use {{braces}} literally.",
+                                       "template_format": "f-string",
+                                       "partial_variables": {"language": "rust"}}}}},
+                        {"lc": 1, "type": "constructor",
+                         "id": ["langchain", "prompts", "chat", "HumanMessagePromptTemplate"],
+                         "kwargs": {"prompt": {"kwargs": {"template": "{{code}}", "template_format": "mustache"}}}}
+                    ]
+                }
+            }
+        }"#;
+        let chat = prompt(&serde_json::from_str(manifest).unwrap(), "file").unwrap();
+        assert_eq!(chat.name, "code-review");
+        assert_eq!(
+            chat.content,
+            "system:\nYou review {{language}} code. Use {braces} literally.\n\nuser:\n{{code}}"
+        );
+        assert_eq!(chat.variables.len(), 2);
+        assert_eq!(chat.variables[0].default_value.as_deref(), Some("rust"));
+
+        let text = r#"{"_type": "prompt", "input_variables": ["topic"], "template": "Tell me about {topic}"}"#;
+        let legacy = prompt(&serde_json::from_str(text).unwrap(), "topic-prompt").unwrap();
+        assert_eq!((legacy.name.as_str(), legacy.content.as_str()), ("topic-prompt", "Tell me about {{topic}}"));
+    }
+}
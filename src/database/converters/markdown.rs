@@ -0,0 +1,138 @@
+use crate::error::Result;
+use crate::database::models::{Prompt, Variable};
+use super::{build_prompt, file_stem, invalid, to_handlebars, Converter, Input, Syntax, Tool};
+use serde_norway::Value;
+use std::fs;
+
+/// Markdown files whose optional YAML front matter gives the `name` (or
+/// `title`), `tags` and `variables`; the body is the prompt. A directory
+/// imports every `.md` file in it.
+pub struct MarkdownConverter;
+
+impl Converter for MarkdownConverter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>> {
+        let Input::Directory(dir) = input else {
+            return Ok(vec![parse(&input.text(Tool::Markdown)?, input.stem())?]);
+        };
+
+        let mut files: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().and_then(|e| e.to_str()).is_some_and(|e| e == "md" || e == "markdown")
+            })
+            .collect();
+        files.sort();
+        files
+            .iter()
+            .map(|path| parse(&fs::read_to_string(path)?, file_stem(path)))
+            .collect()
+    }
+}
+
+fn parse(text: &str, stem: Option<String>) -> Result<Prompt> {
+    let (front_matter, body) = split_front_matter(text);
+    let meta = match front_matter {
+        Some(yaml) => serde_norway::from_str::<Value>(yaml)
+            .map_err(|e| invalid(Tool::Markdown, format!("front matter: {}", e)))?,
+        None => Value::Null,
+    };
+
+    let name = ["name", "title"]
+        .iter()
+        .find_map(|key| meta.get(key).and_then(Value::as_str).map(str::to_string))
+        .or(stem)
+        .ok_or_else(|| invalid(Tool::Markdown, "no name in the front matter".to_string()))?;
+    let tags = meta.get("tags").map(tags).unwrap_or_default();
+    let declared = meta.get("variables").map(variables).unwrap_or_default();
+    let content = to_handlebars(body.trim(), Syntax::Mixed);
+    build_prompt(name, content, tags, &declared)
+}
+
+/// Front matter between `---` lines at the very start, and the rest
+fn split_front_matter(text: &str) -> (Option<&str>, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (None, text);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+/// A list of tags or one comma-separated string
+fn tags(value: &Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.iter().filter_map(scalar).collect(),
+        Value::String(text) => text.split(',').map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Variables as a list of names or of `{name, description, default}`, or as
+/// a map from name to description
+fn variables(value: &Value) -> Vec<Variable> {
+    let described = |name: String, description: Option<String>, default: Option<String>| {
+        let variable = Variable::new(name);
+        let variable = match description {
+            Some(description) => variable.with_description(description),
+            None => variable,
+        };
+        match default {
+            Some(default) => variable.with_default(default),
+            None => variable,
+        }
+    };
+    match value {
+        Value::Sequence(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::Mapping(_) => Some(described(
+                    item.get("name").and_then(scalar)?,
+                    item.get("description").and_then(scalar),
+                    item.get("default").and_then(scalar),
+                )),
+                _ => Some(Variable::new(scalar(item)?)),
+            })
+            .collect(),
+        Value::Mapping(map) => map
+            .iter()
+            .filter_map(|(name, description)| Some(described(scalar(name)?, scalar(description), None)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_gives_name_tags_and_variables() {
+        let text = "---\ntitle: Code review\ntags: [code, review]\nvariables:\n  - name: language\n    description: Source language\n    default: rust\n---\n\nReview this {language} code:\n\n$code\n";
+        let prompt = parse(text, Some("file".to_string())).unwrap();
+        assert_eq!(prompt.name, "Code review");
+        assert_eq!(prompt.content, "Review this {{language}} code:\n\n{{code}}");
+        assert_eq!(prompt.tags, ["code", "review"]);
+        assert_eq!(prompt.variables.len(), 2);
+        assert_eq!(prompt.variables[0].default_value.as_deref(), Some("rust"));
+        assert_eq!(prompt.variables[1].name, "code");
+
+        let plain = parse("Just {{text}}\n", Some("notes".to_string())).unwrap();
+        assert_eq!((plain.name.as_str(), plain.content.as_str()), ("notes", "Just {{text}}"));
+    }
+}
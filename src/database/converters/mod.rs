@@ -0,0 +1,287 @@
+//! Converters from other prompt tools' files into prompts. Each tool has a
+//! `Converter`; `import --from <tool>` feeds its output through the usual
+//! merge strategies.
+
+mod csv;
+mod fabric;
+mod langchain;
+mod markdown;
+mod promptfoo;
+
+use crate::error::{PromptedsError, Result};
+use crate::template::TemplateEngine;
+use super::models::{Prompt, Variable};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tools whose prompt files can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Promptfoo,
+    Langchain,
+    Fabric,
+    Markdown,
+    Csv,
+}
+
+/// Reads another tool's prompt files. Implementations return prompts with
+/// Handlebars variables; names are made valid and unique by `convert`.
+pub trait Converter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>>;
+}
+
+/// What was passed to `import --input`
+pub enum Input {
+    File(PathBuf),
+    Directory(PathBuf),
+    Stdin(String),
+}
+
+/// Variable syntax of the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// Python format strings: `{var}`, with `{{` and `}}` as literal braces
+    FString,
+    /// Already `{{var}}` (Handlebars, Mustache, Jinja2, Nunjucks)
+    Handlebars,
+    /// Hand-written text that may use any of `{{var}}`, `{var}`, `${var}`
+    /// and `$var`
+    Mixed,
+}
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Promptfoo => "promptfoo",
+            Tool::Langchain => "LangChain",
+            Tool::Fabric => "Fabric",
+            Tool::Markdown => "Markdown",
+            Tool::Csv => "CSV",
+        }
+    }
+
+    pub fn converter(self) -> Box<dyn Converter> {
+        match self {
+            Tool::Promptfoo => Box::new(promptfoo::PromptfooConverter),
+            Tool::Langchain => Box::new(langchain::LangchainConverter),
+            Tool::Fabric => Box::new(fabric::FabricConverter),
+            Tool::Markdown => Box::new(markdown::MarkdownConverter),
+            Tool::Csv => Box::new(csv::CsvConverter),
+        }
+    }
+}
+
+impl Input {
+    /// `path` is a file or directory; without one, stdin is read
+    pub fn read(tool: Tool, path: Option<PathBuf>) -> Result<Self> {
+        match path {
+            Some(path) if path.is_dir() => Ok(Input::Directory(path)),
+            Some(path) => Ok(Input::File(path)),
+            None if tool == Tool::Fabric => {
+                Err(invalid(tool, "--input must be a patterns directory".to_string()))
+            }
+            None => {
+                let mut text = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+                Ok(Input::Stdin(text))
+            }
+        }
+    }
+
+    /// Contents of a single file or stdin
+    fn text(&self, tool: Tool) -> Result<String> {
+        match self {
+            Input::File(path) => Ok(fs::read_to_string(path)?),
+            Input::Stdin(text) => Ok(text.clone()),
+            Input::Directory(path) => Err(invalid(tool, format!("{} is a directory, expected a file", path.display()))),
+        }
+    }
+
+    /// Name for prompts that do not carry one
+    fn stem(&self) -> Option<String> {
+        match self {
+            Input::File(path) | Input::Directory(path) => file_stem(path),
+            Input::Stdin(_) => None,
+        }
+    }
+}
+
+/// Convert `input` with `tool`'s converter into prompts with valid, unique
+/// names
+pub fn convert(tool: Tool, input: &Input) -> Result<Vec<Prompt>> {
+    let mut prompts = tool.converter().convert(input)?;
+    let mut seen: Vec<String> = Vec::new();
+    for prompt in &mut prompts {
+        let base = prompt_name(&prompt.name).ok_or_else(|| {
+            invalid(tool, format!("cannot make a prompt name out of '{}'", prompt.name))
+        })?;
+        let mut name = base.clone();
+        let mut counter = 2;
+        while seen.contains(&name) {
+            name = format!("{}-{}", base, counter);
+            counter += 1;
+        }
+        seen.push(name.clone());
+        prompt.name = name;
+    }
+    Ok(prompts)
+}
+
+/// Rewrite the variables of `text` from `syntax` into Handlebars `{{var}}`
+pub fn to_handlebars(text: &str, syntax: Syntax) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        match (syntax, rest) {
+            (Syntax::Handlebars, _) => {
+                out.push(rest[0]);
+                i += 1;
+                continue;
+            }
+            (Syntax::FString, ['{', '{', ..]) => {
+                out.push('{');
+                i += 2;
+                continue;
+            }
+            (Syntax::FString, ['}', '}', ..]) => {
+                out.push('}');
+                i += 2;
+                continue;
+            }
+            // Existing Handlebars expressions are copied whole
+            (Syntax::Mixed, ['{', '{', ..]) => {
+                let end = find(rest, &['}', '}']).map_or(rest.len(), |end| end + 2);
+                out.extend(&rest[..end]);
+                i += end;
+                continue;
+            }
+            (Syntax::Mixed, ['$', '{', ..]) => {
+                if let Some(len) = braced_identifier(&rest[1..]) {
+                    push_variable(&mut out, &rest[2..len]);
+                    i += len + 1;
+                    continue;
+                }
+            }
+            (Syntax::Mixed, ['$', ..]) => {
+                let len = identifier_len(&rest[1..]);
+                if len > 0 {
+                    push_variable(&mut out, &rest[1..=len]);
+                    i += len + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        if rest[0] == '{' {
+            if let Some(len) = braced_identifier(rest) {
+                push_variable(&mut out, &rest[1..len - 1]);
+                i += len;
+                continue;
+            }
+        }
+        out.push(rest[0]);
+        i += 1;
+    }
+    out
+}
+
+fn push_variable(out: &mut String, name: &[char]) {
+    out.push_str("{{");
+    out.extend(name);
+    out.push_str("}}");
+}
+
+/// Length of an identifier at the start of `chars`
+fn identifier_len(chars: &[char]) -> usize {
+    match chars.first() {
+        Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+            chars.iter().take_while(|c| c.is_ascii_alphanumeric() || **c == '_').count()
+        }
+        _ => 0,
+    }
+}
+
+/// Length of `{identifier}` at the start of `chars`, braces included
+fn braced_identifier(chars: &[char]) -> Option<usize> {
+    let len = identifier_len(chars.get(1..)?);
+    (len > 0 && chars.get(len + 1) == Some(&'}')).then_some(len + 2)
+}
+
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// A valid prompt name close to `raw`: other characters become hyphens
+fn prompt_name(raw: &str) -> Option<String> {
+    let mut name = String::new();
+    for c in raw.trim().chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            name.push(c);
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    let name: String = name.trim_end_matches('-').chars().take(100).collect();
+    let name = name.trim_end_matches('-');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Build a prompt from converted content. Variables are the ones used in
+/// `content`, with descriptions and defaults from `declared` where given.
+fn build_prompt(name: String, content: String, tags: Vec<String>, declared: &[Variable]) -> Result<Prompt> {
+    let variables = TemplateEngine::new()
+        .extract_variables(&content)?
+        .into_iter()
+        .map(|name| {
+            declared
+                .iter()
+                .find(|v| v.name == name)
+                .cloned()
+                .unwrap_or_else(|| Variable::new(name))
+        })
+        .collect();
+    let mut unique_tags: Vec<String> = Vec::new();
+    for tag in tags.into_iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()) {
+        if !unique_tags.contains(&tag) {
+            unique_tags.push(tag);
+        }
+    }
+    Ok(Prompt::new(name, content).with_variables(variables).with_tags(unique_tags))
+}
+
+fn file_stem(path: &Path) -> Option<String> {
+    path.file_stem().and_then(|s| s.to_str()).map(str::to_string)
+}
+
+fn invalid(tool: Tool, details: String) -> PromptedsError {
+    PromptedsError::ImportError {
+        details: format!("Invalid {} input: {}", tool.name(), details),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_are_translated_to_handlebars() {
+        assert_eq!(
+            to_handlebars("Hi {name}, {{literal}} {0} { spaced }", Syntax::FString),
+            "Hi {{name}}, {literal} {0} { spaced }"
+        );
+        assert_eq!(
+            to_handlebars("{{ kept }} {single} ${braced} $plain costs $5 {{#if x}}{y}{{/if}}", Syntax::Mixed),
+            "{{ kept }} {{single}} {{braced}} {{plain}} costs $5 {{#if x}}{{y}}{{/if}}"
+        );
+        assert_eq!(to_handlebars("{{input}} {x} $y", Syntax::Handlebars), "{{input}} {x} $y");
+    }
+
+    #[test]
+    fn test_names_are_made_valid() {
+        assert_eq!(prompt_name("owner/Summarize text!").as_deref(), Some("owner-Summarize-text"));
+        assert_eq!(prompt_name("  extract_wisdom ").as_deref(), Some("extract_wisdom"));
+        assert_eq!(prompt_name("¿?"), None);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::error::Result;
+use crate::database::models::Prompt;
+use super::{build_prompt, file_stem, invalid, to_handlebars, Converter, Input, Syntax, Tool};
+use serde_norway::Value;
+use std::fs;
+use std::path::Path;
+
+/// promptfoo prompt files: text files with prompts separated by `---`
+/// lines, chat files (a JSON or YAML list of `{role, content}` messages), and
+/// `promptfooconfig.yaml` whose `prompts:` list holds raw prompts, labelled
+/// `{raw, label}` entries or `file://` references to either kind of file.
+/// promptfoo templates are Nunjucks, so `{{var}}` carries over unchanged.
+pub struct PromptfooConverter;
+
+impl Converter for PromptfooConverter {
+    fn convert(&self, input: &Input) -> Result<Vec<Prompt>> {
+        let text = input.text(Tool::Promptfoo)?;
+        let stem = input.stem().unwrap_or_else(|| "promptfoo".to_string());
+        let base = match input {
+            Input::File(path) => path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            _ => std::env::current_dir()?,
+        };
+
+        let config = serde_norway::from_str::<Value>(&text).ok();
+        match config.as_ref().and_then(|config| config.get("prompts")) {
+            Some(Value::Sequence(entries)) => {
+                let mut prompts = Vec::new();
+                for (index, entry) in entries.iter().enumerate() {
+                    prompts.extend(config_entry(entry, &base, &format!("{}-{}", stem, index + 1))?);
+                }
+                Ok(prompts)
+            }
+            Some(Value::String(entry)) => config_entry(&Value::String(entry.clone()), &base, &stem),
+            _ => prompt_file(&text, &stem),
+        }
+    }
+}
+
+/// One item of a config's `prompts:` list
+fn config_entry(entry: &Value, base: &Path, fallback: &str) -> Result<Vec<Prompt>> {
+    let (raw, label) = match entry {
+        Value::String(raw) => (raw.as_str(), None),
+        Value::Mapping(_) => {
+            let raw = ["raw", "id"]
+                .iter()
+                .find_map(|key| entry.get(key).and_then(Value::as_str))
+                .ok_or_else(|| invalid(Tool::Promptfoo, "prompt entry without 'raw' or 'id'".to_string()))?;
+            (raw, entry.get("label").and_then(Value::as_str))
+        }
+        _ => return Err(invalid(Tool::Promptfoo, "prompts must be strings or mappings".to_string())),
+    };
+
+    if let Some(reference) = raw.strip_prefix("file://") {
+        let path = base.join(reference);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| invalid(Tool::Promptfoo, format!("{}: {}", path.display(), e)))?;
+        let name = label.map(str::to_string).or_else(|| file_stem(&path)).unwrap_or_default();
+        return prompt_file(&text, &name);
+    }
+    let name = label.unwrap_or(fallback).to_string();
+    Ok(vec![build_prompt(name, to_handlebars(raw.trim(), Syntax::Handlebars), Vec::new(), &[])?])
+}
+
+/// A prompt file: one chat prompt, or text prompts separated by `---`
+fn prompt_file(text: &str, name: &str) -> Result<Vec<Prompt>> {
+    if let Ok(Value::Sequence(messages)) = serde_norway::from_str::<Value>(text) {
+        if !messages.is_empty() && messages.iter().all(|m| m.get("role").is_some() && m.get("content").is_some()) {
+            let content = messages
+                .iter()
+                .map(|message| {
+                    let role = message.get("role").and_then(Value::as_str).unwrap_or("user");
+                    let content = message.get("content").and_then(Value::as_str).unwrap_or("");
+                    format!("{}:\n{}", role, content.trim())
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            return Ok(vec![build_prompt(name.to_string(), content, Vec::new(), &[])?]);
+        }
+    }
+
+    let parts: Vec<String> = split_prompts(text)
+        .into_iter()
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect();
+    let numbered = parts.len() > 1;
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let name = if numbered { format!("{}-{}", name, index + 1) } else { name.to_string() };
+            build_prompt(name, to_handlebars(&part, Syntax::Handlebars), Vec::new(), &[])
+        })
+        .collect()
+}
+
+/// Text split at lines consisting of `---`
+fn split_prompts(text: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    for line in text.lines() {
+        if line.trim() == "---" {
+            parts.push(String::new());
+        } else if let Some(part) = parts.last_mut() {
+            part.push_str(line);
+            part.push('\n');
+        }
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configs_reference_prompt_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("prompts.txt"), "Summarize {{ text }}\n---\nTranslate {{text}} to {{language}}\n").unwrap();
+        fs::write(
+            dir.path().join("chat.json"),
+            r#"[{"role": "system", "content": "You are terse."}, {"role": "user", "content": "{{question}}"}]"#,
+        )
+        .unwrap();
+        let config = "prompts:\n  - file://prompts.txt\n  - file://chat.json\n  - raw: 'Hello {{name}}'\n    label: greeting\n";
+        fs::write(dir.path().join("promptfooconfig.yaml"), config).unwrap();
+
+        let input = Input::File(dir.path().join("promptfooconfig.yaml"));
+        let prompts = PromptfooConverter.convert(&input).unwrap();
+        let names: Vec<&str> = prompts.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["prompts-1", "prompts-2", "chat", "greeting"]);
+        assert_eq!(prompts[1].variables.len(), 2);
+        assert_eq!(prompts[2].content, "system:\nYou are terse.\n\nuser:\n{{question}}");
+    }
+}
@@ -0,0 +1,342 @@
+//! Encryption of prompt content at rest and of export bundles. Keys are
+//! derived from a passphrase with Argon2id; values are sealed with
+//! XChaCha20-Poly1305 under a random nonce.
+
+use crate::error::{PromptedsError, Result};
+use crate::utils;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rusqlite::types::{FromSql, FromSqlResult, ValueRef};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+
+/// Derived key of an unlocked library, as printed by `encryption unlock`
+pub const KEY_ENV: &str = "EDISONPROMPT_KEY";
+/// Passphrase for scripts, used instead of asking
+pub const PASSPHRASE_ENV: &str = "EDISONPROMPT_PASSPHRASE";
+
+/// First byte of a sealed value: its layout version
+const SEALED_V1: u8 = 1;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+/// Sealed under the key when encryption is enabled, to recognise it later
+const CHECK_TEXT: &[u8] = b"edisonprompt";
+const BUNDLE_VERSION: u32 = 1;
+
+/// Argon2id settings and salt of a derived key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyParams {
+    #[serde(with = "base64_bytes")]
+    pub salt: Vec<u8>,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// A derived key
+pub struct Cipher {
+    key: [u8; KEY_LEN],
+}
+
+/// A text column that holds plain text, or a sealed BLOB in an encrypted
+/// library
+pub enum Stored {
+    Text(String),
+    Sealed(Vec<u8>),
+}
+
+/// Encryption state of a library: its key parameters once read, and its
+/// key once unlocked
+#[derive(Default)]
+pub struct Vault {
+    settings: OnceCell<Option<(KeyParams, Vec<u8>)>>,
+    cipher: OnceCell<Cipher>,
+}
+
+/// An encrypted export file
+#[derive(Serialize, Deserialize)]
+pub struct Bundle {
+    pub edisonprompt_bundle: u32,
+    /// Format of the export inside: json, yaml or toml
+    pub format: String,
+    pub kdf: KeyParams,
+    #[serde(with = "base64_bytes")]
+    pub ciphertext: Vec<u8>,
+}
+
+impl KeyParams {
+    /// Parameters with a fresh salt, at the OWASP recommended cost
+    pub fn generate() -> Self {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self { salt, memory_kib: 19 * 1024, iterations: 2, parallelism: 1 }
+    }
+
+    pub fn derive(&self, passphrase: &str) -> Result<Cipher> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(KEY_LEN))
+            .map_err(|e| crypto_error(format!("invalid key parameters: {}", e)))?;
+        let mut key = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|e| crypto_error(format!("cannot derive key: {}", e)))?;
+        Ok(Cipher { key })
+    }
+}
+
+impl Cipher {
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim();
+        if hex.len() != KEY_LEN * 2 || !hex.is_ascii() {
+            return None;
+        }
+        let mut key = [0u8; KEY_LEN];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(Self { key })
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.key.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// `plaintext` encrypted and authenticated together with `context`,
+    /// which must be given again to open it
+    pub fn seal(&self, plaintext: &[u8], context: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&self.key.into())
+            .encrypt(&nonce, Payload { msg: plaintext, aad: context })
+            .expect("encrypting in memory cannot fail");
+        let mut sealed = Vec::with_capacity(1 + NONCE_LEN + ciphertext.len());
+        sealed.push(SEALED_V1);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// The plaintext of a sealed value; fails for another key or context,
+    /// or if it was tampered with
+    pub fn open(&self, sealed: &[u8], context: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < 1 + NONCE_LEN || sealed[0] != SEALED_V1 {
+            return None;
+        }
+        let nonce = XNonce::from_slice(&sealed[1..1 + NONCE_LEN]);
+        XChaCha20Poly1305::new(&self.key.into())
+            .decrypt(nonce, Payload { msg: &sealed[1 + NONCE_LEN..], aad: context })
+            .ok()
+    }
+
+    /// Value stored with the key parameters to recognise this key
+    pub fn check_value(&self) -> Vec<u8> {
+        self.seal(CHECK_TEXT, b"check")
+    }
+
+    pub fn matches(&self, check_value: &[u8]) -> bool {
+        self.open(check_value, b"check").as_deref() == Some(CHECK_TEXT)
+    }
+}
+
+impl FromSql for Stored {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Blob(bytes) => Ok(Stored::Sealed(bytes.to_vec())),
+            other => String::column_result(other).map(Stored::Text),
+        }
+    }
+}
+
+impl Vault {
+    /// Whether the library's content is encrypted
+    pub fn is_enabled(&self, conn: &Connection) -> Result<bool> {
+        Ok(self.settings(conn)?.is_some())
+    }
+
+    /// `text` as it is stored: sealed with `context` when encryption is on
+    pub fn seal(&self, conn: &Connection, text: &str, context: &str) -> Result<rusqlite::types::Value> {
+        if !self.is_enabled(conn)? {
+            return Ok(rusqlite::types::Value::Text(text.to_string()));
+        }
+        let sealed = self.cipher(conn)?.seal(text.as_bytes(), context.as_bytes());
+        Ok(rusqlite::types::Value::Blob(sealed))
+    }
+
+    /// The text of a stored value, unlocking the library if it is sealed
+    pub fn reveal(&self, conn: &Connection, stored: Stored, context: &str) -> Result<String> {
+        match stored {
+            Stored::Text(text) => Ok(text),
+            Stored::Sealed(sealed) => {
+                let plaintext = self
+                    .cipher(conn)?
+                    .open(&sealed, context.as_bytes())
+                    .ok_or_else(|| crypto_error(format!("cannot decrypt {}: data is damaged", context)))?;
+                String::from_utf8(plaintext).map_err(|_| crypto_error(format!("{} is not valid text", context)))
+            }
+        }
+    }
+
+    /// The library's key: from `EDISONPROMPT_KEY`, `EDISONPROMPT_PASSPHRASE`
+    /// or by asking
+    pub fn cipher(&self, conn: &Connection) -> Result<&Cipher> {
+        if let Some(cipher) = self.cipher.get() {
+            return Ok(cipher);
+        }
+        let Some((params, check)) = self.settings(conn)? else {
+            return Err(crypto_error("this library is not encrypted".to_string()));
+        };
+
+        let cipher = match key_from_env(check) {
+            Some(cipher) => cipher,
+            None => {
+                let passphrase = passphrase("Library passphrase", false)?;
+                let cipher = params.derive(&passphrase)?;
+                if !cipher.matches(check) {
+                    return Err(crypto_error("wrong passphrase".to_string()));
+                }
+                cipher
+            }
+        };
+        Ok(self.cipher.get_or_init(|| cipher))
+    }
+
+    /// Whether `EDISONPROMPT_KEY` holds the key of this encrypted library
+    pub fn env_unlocks(&self, conn: &Connection) -> Result<bool> {
+        Ok(self.settings(conn)?.is_some_and(|(_, check)| key_from_env(check).is_some()))
+    }
+
+    /// Use `cipher` from now on, after encryption was turned on with it
+    pub fn unlocked(cipher: Cipher) -> Self {
+        let vault = Self::default();
+        let _ = vault.cipher.set(cipher);
+        vault
+    }
+
+    /// Key parameters and check value, if the library is encrypted
+    pub fn settings(&self, conn: &Connection) -> Result<Option<&(KeyParams, Vec<u8>)>> {
+        if let Some(settings) = self.settings.get() {
+            return Ok(settings.as_ref());
+        }
+        let settings = conn
+            .query_row(
+                "SELECT salt, memory_kib, iterations, parallelism, check_value FROM encryption WHERE id = 1",
+                [],
+                |row| {
+                    let params = KeyParams {
+                        salt: row.get(0)?,
+                        memory_kib: row.get(1)?,
+                        iterations: row.get(2)?,
+                        parallelism: row.get(3)?,
+                    };
+                    Ok((params, row.get::<_, Vec<u8>>(4)?))
+                },
+            )
+            .optional()?;
+        Ok(self.settings.get_or_init(|| settings).as_ref())
+    }
+}
+
+impl Bundle {
+    /// An encrypted bundle of export `text` in `format`
+    pub fn seal(text: &str, format: &str, passphrase: &str) -> Result<Self> {
+        let kdf = KeyParams::generate();
+        let ciphertext = kdf.derive(passphrase)?.seal(text.as_bytes(), b"bundle");
+        Ok(Self { edisonprompt_bundle: BUNDLE_VERSION, format: format.to_string(), kdf, ciphertext })
+    }
+
+    /// The bundle in `text`, if it is one
+    pub fn detect(text: &str) -> Option<Self> {
+        let bundle: Self = serde_json::from_str(text).ok()?;
+        (bundle.edisonprompt_bundle == BUNDLE_VERSION).then_some(bundle)
+    }
+
+    /// The export text inside
+    pub fn open(&self, passphrase: &str) -> Result<String> {
+        let plaintext = self
+            .kdf
+            .derive(passphrase)?
+            .open(&self.ciphertext, b"bundle")
+            .ok_or_else(|| crypto_error("wrong passphrase, or the bundle is damaged".to_string()))?;
+        String::from_utf8(plaintext).map_err(|_| crypto_error("the bundle does not hold text".to_string()))
+    }
+}
+
+/// The key in `EDISONPROMPT_KEY`, if it is set and matches `check`
+fn key_from_env(check: &[u8]) -> Option<Cipher> {
+    let cipher = Cipher::from_hex(&std::env::var(KEY_ENV).ok()?)?;
+    cipher.matches(check).then_some(cipher)
+}
+
+/// Context a prompt's content is sealed with, tying it to the prompt
+pub fn content_context(prompt_id: &uuid::Uuid) -> String {
+    format!("content:{}", prompt_id)
+}
+
+/// Context a variable's default value is sealed with
+pub fn default_context(prompt_id: &uuid::Uuid, variable: &str) -> String {
+    format!("default:{}:{}", prompt_id, variable)
+}
+
+/// Context a kept render is sealed with
+pub fn render_context(prompt_id: &uuid::Uuid, rendered_at: &str) -> String {
+    format!("render:{}:{}", prompt_id, rendered_at)
+}
+
+/// A passphrase from `EDISONPROMPT_PASSPHRASE`, or asked for on the
+/// terminal; `confirm` asks twice, for new passphrases
+pub fn passphrase(prompt: &str, confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let passphrase = utils::read_secret(prompt).map_err(|_| PromptedsError::LibraryLocked)?;
+    if passphrase.is_empty() {
+        return Err(crypto_error("the passphrase must not be empty".to_string()));
+    }
+    if confirm && utils::read_secret("Repeat passphrase")? != passphrase {
+        return Err(crypto_error("the passphrases do not match".to_string()));
+    }
+    Ok(passphrase)
+}
+
+fn crypto_error(details: String) -> PromptedsError {
+    PromptedsError::EncryptionError { details }
+}
+
+mod base64_bytes {
+    use super::{Engine, STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        STANDARD.decode(text).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_values_open_only_with_their_key_and_context() {
+        let params = KeyParams { memory_kib: 64, iterations: 1, ..KeyParams::generate() };
+        let cipher = params.derive("correct horse").unwrap();
+        let sealed = cipher.seal(b"client details", b"content:1");
+        assert_eq!(cipher.open(&sealed, b"content:1").as_deref(), Some(&b"client details"[..]));
+        assert!(cipher.open(&sealed, b"content:2").is_none());
+        assert!(!params.derive("wrong horse").unwrap().matches(&cipher.check_value()));
+        assert!(Cipher::from_hex(&cipher.to_hex()).unwrap().matches(&cipher.check_value()));
+
+        let bundle = Bundle::seal("{}", "json", "secret").unwrap();
+        let text = serde_json::to_string(&bundle).unwrap();
+        assert_eq!(Bundle::detect(&text).unwrap().open("secret").unwrap(), "{}");
+        assert!(Bundle::detect(&text).unwrap().open("guess").is_err());
+        assert!(Bundle::detect("{\"format_version\": 2}").is_none());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::database::embeddings::fnv1a;
+use crate::database::models::{Prompt, Variable};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Number of hash functions in a MinHash signature
+const SIGNATURE_SIZE: usize = 64;
+
+/// Words per shingle when comparing contents
+const SHINGLE_WORDS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    /// Every prompt in the group has the same normalized content
+    Exact,
+    /// Contents overlap above the similarity threshold
+    Near,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Lowest similarity between two prompts linked in the group
+    pub similarity: f64,
+    pub prompts: Vec<Prompt>,
+}
+
+/// Hash of the content with case and whitespace differences removed
+pub fn content_hash(content: &str) -> u64 {
+    let normalized = content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    fnv1a(normalized.as_bytes())
+}
+
+/// Group prompts that are exact duplicates or whose estimated shingle
+/// similarity is at least `threshold`
+pub fn find_duplicates(prompts: &[Prompt], threshold: f64) -> Vec<DuplicateGroup> {
+    let hashes: Vec<u64> = prompts.iter().map(|p| content_hash(&p.content)).collect();
+    let shingle_sets: Vec<HashSet<u64>> = prompts.iter().map(|p| shingles(&p.content)).collect();
+    let signatures: Vec<[u64; SIGNATURE_SIZE]> = shingle_sets.iter().map(minhash).collect();
+
+    let mut parents: Vec<usize> = (0..prompts.len()).collect();
+    let mut lowest: HashMap<usize, f64> = HashMap::new();
+    let mut edges = Vec::new();
+
+    for i in 0..prompts.len() {
+        for j in (i + 1)..prompts.len() {
+            let similarity = if hashes[i] == hashes[j] {
+                1.0
+            } else {
+                let estimate = estimate_similarity(&signatures[i], &signatures[j]);
+                // The estimate only screens candidates; report the exact value
+                if estimate + 0.1 < threshold {
+                    continue;
+                }
+                jaccard(&shingle_sets[i], &shingle_sets[j])
+            };
+
+            if similarity >= threshold {
+                union(&mut parents, i, j);
+                edges.push((i, similarity));
+            }
+        }
+    }
+
+    for (i, similarity) in edges {
+        let root = find(&mut parents, i);
+        let entry = lowest.entry(root).or_insert(1.0);
+        *entry = entry.min(similarity);
+    }
+
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..prompts.len() {
+        let root = find(&mut parents, i);
+        members.entry(root).or_default().push(i);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = members
+        .into_iter()
+        .filter(|(_, indexes)| indexes.len() > 1)
+        .map(|(root, indexes)| {
+            let exact = indexes.iter().all(|i| hashes[*i] == hashes[indexes[0]]);
+            DuplicateGroup {
+                kind: if exact { DuplicateKind::Exact } else { DuplicateKind::Near },
+                similarity: lowest.get(&root).copied().unwrap_or(1.0),
+                prompts: indexes.into_iter().map(|i| prompts[i].clone()).collect(),
+            }
+        })
+        .collect();
+
+    for group in &mut groups {
+        group.prompts.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.name.cmp(&b.name)));
+    }
+    groups.sort_by(|a, b| b.similarity.total_cmp(&a.similarity).then_with(|| a.prompts[0].name.cmp(&b.prompts[0].name)));
+    groups
+}
+
+/// Keep `keep` with the union of the group's tags and variables. Variables
+/// the keeper already has win; others fill in missing descriptions and defaults.
+pub fn merge_group(keep: &Prompt, others: &[Prompt]) -> Prompt {
+    let mut merged = keep.clone();
+
+    for other in others {
+        for tag in &other.tags {
+            if !merged.tags.contains(tag) {
+                merged.tags.push(tag.clone());
+            }
+        }
+
+        for variable in &other.variables {
+            match merged.variables.iter_mut().find(|v| v.name == variable.name) {
+                Some(existing) => fill_missing(existing, variable),
+                None => merged.variables.push(variable.clone()),
+            }
+        }
+    }
+
+    merged.updated_at = chrono::Utc::now();
+    merged
+}
+
+fn fill_missing(existing: &mut Variable, other: &Variable) {
+    if existing.description.is_none() {
+        existing.description = other.description.clone();
+    }
+    if existing.default_value.is_none() {
+        existing.default_value = other.default_value.clone();
+    }
+}
+
+fn shingles(content: &str) -> HashSet<u64> {
+    let words: Vec<String> = content.split_whitespace().map(|w| w.to_lowercase()).collect();
+
+    if words.len() < SHINGLE_WORDS {
+        return std::iter::once(fnv1a(words.join(" ").as_bytes())).collect();
+    }
+
+    words
+        .windows(SHINGLE_WORDS)
+        .map(|window| fnv1a(window.join(" ").as_bytes()))
+        .collect()
+}
+
+fn minhash(shingles: &HashSet<u64>) -> [u64; SIGNATURE_SIZE] {
+    let mut signature = [u64::MAX; SIGNATURE_SIZE];
+    for shingle in shingles {
+        for (seed, slot) in signature.iter_mut().enumerate() {
+            let mut bytes = [0u8; 16];
+            bytes[..8].copy_from_slice(&(seed as u64).to_le_bytes());
+            bytes[8..].copy_from_slice(&shingle.to_le_bytes());
+            *slot = (*slot).min(fnv1a(&bytes));
+        }
+    }
+    signature
+}
+
+fn estimate_similarity(a: &[u64; SIGNATURE_SIZE], b: &[u64; SIGNATURE_SIZE]) -> f64 {
+    a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / SIGNATURE_SIZE as f64
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    let total = a.len() + b.len() - shared;
+    if total == 0 {
+        0.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let root_a = find(parents, a);
+    let root_b = find(parents, b);
+    if root_a != root_b {
+        parents[root_b] = root_a;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(name: &str, content: &str) -> Prompt {
+        Prompt::new(name.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_find_exact_and_near_duplicates() {
+        let prompts = vec![
+            prompt("review", "Review this code for bugs and style issues in {{lang}}"),
+            prompt("review-1", "Review this code for bugs and  style issues in {{lang}}"),
+            prompt("review-loose", "Please review this code for bugs and style issues in {{lang}} today"),
+            prompt("email", "Write a friendly email to {{who}}"),
+        ];
+
+        let groups = find_duplicates(&prompts, 0.5);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kind, DuplicateKind::Near);
+        assert_eq!(groups[0].prompts.len(), 3);
+
+        let exact = find_duplicates(&prompts[..2], 0.99);
+        assert_eq!(exact[0].kind, DuplicateKind::Exact);
+    }
+
+    #[test]
+    fn test_merge_group_unions_tags_and_variables() {
+        let keep = prompt("a", "{{x}}")
+            .with_tags(vec!["one".to_string()])
+            .with_variables(vec![Variable::new("x".to_string())]);
+        let other = prompt("b", "{{x}} {{y}}")
+            .with_tags(vec!["one".to_string(), "two".to_string()])
+            .with_variables(vec![
+                Variable::new("x".to_string()).with_default("1".to_string()),
+                Variable::new("y".to_string()),
+            ]);
+
+        let merged = merge_group(&keep, &[other]);
+        assert_eq!(merged.id, keep.id);
+        assert_eq!(merged.tags, vec!["one", "two"]);
+        assert_eq!(merged.variables.len(), 2);
+        assert_eq!(merged.variables[0].default_value.as_deref(), Some("1"));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::{PromptedsError, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Dimensions of the built-in hashing embedder
+pub const HASHING_DIMENSIONS: usize = 256;
+
+/// Words too common to say anything about what a prompt is for
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "below", "by", "for", "from", "in", "into", "is",
+    "it", "its", "of", "on", "or", "please", "prompt", "that", "the", "this", "to", "was", "were",
+    "will", "with", "you", "your",
+];
+
+/// Turns text into a fixed-size vector. Implementations must be deterministic
+/// and must run locally: vectors are stored and compared across invocations.
+pub trait Embedder {
+    /// Identifies the model; vectors from different models are never compared
+    fn model_id(&self) -> String;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+/// Offline baseline: feature hashing of stemmed words and character trigrams
+/// with sublinear term frequency. Needs no model download.
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions }
+    }
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self::new(HASHING_DIMENSIONS)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn model_id(&self) -> String {
+        format!("hashing-{}", self.dimensions)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut features: HashMap<String, f32> = HashMap::new();
+
+        for word in tokenize(text) {
+            let stem = stem(&word);
+            let padded: Vec<char> = format!(" {} ", stem).chars().collect();
+            for gram in padded.windows(3) {
+                *features.entry(format!("c:{}", gram.iter().collect::<String>())).or_default() += 0.3;
+            }
+            *features.entry(format!("w:{}", stem)).or_default() += 1.0;
+        }
+
+        let mut vector = vec![0.0f32; self.dimensions];
+        for (feature, tf) in features {
+            let hash = fnv1a(feature.as_bytes());
+            let index = (hash % self.dimensions as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[index] += sign * (1.0 + tf.ln_1p());
+        }
+
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// Runs a local program that reads text on stdin and prints a JSON array of
+/// numbers on stdout, so any offline model can be plugged in.
+pub struct CommandEmbedder {
+    command: String,
+}
+
+impl CommandEmbedder {
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl Embedder for CommandEmbedder {
+    fn model_id(&self) -> String {
+        format!("command:{}", self.command)
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+        let mut child = Command::new(shell)
+            .arg(flag)
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| PromptedsError::EmbeddingError {
+                details: format!("Failed to run embedder '{}': {}", self.command, e),
+            })?;
+
+        // Write from another thread while stdout is read, so an embedder that
+        // prints before it has read all of its input cannot fill both pipes
+        let writer = child.stdin.take().map(|mut stdin| {
+            let text = text.to_string();
+            std::thread::spawn(move || stdin.write_all(text.as_bytes()))
+        });
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(PromptedsError::EmbeddingError {
+                details: format!("Embedder '{}' exited with {}", self.command, output.status),
+            });
+        }
+        if let Some(writer) = writer {
+            match writer.join() {
+                Ok(Err(e)) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        let mut vector: Vec<f32> = serde_json::from_slice(&output.stdout).map_err(|e| {
+            PromptedsError::EmbeddingError {
+                details: format!("Embedder '{}' did not print a JSON array: {}", self.command, e),
+            }
+        })?;
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+/// Build the embedder named in `search.embedder`: `hashing` or `command:<program>`
+pub fn from_config(spec: &str) -> Result<Box<dyn Embedder>> {
+    match spec.split_once(':') {
+        Some(("command", command)) if !command.trim().is_empty() => {
+            Ok(Box::new(CommandEmbedder::new(command.trim().to_string())))
+        }
+        _ if spec == "hashing" => Ok(Box::new(HashingEmbedder::default())),
+        _ => Err(PromptedsError::EmbeddingError {
+            details: format!("Unknown embedder '{}', expected 'hashing' or 'command:<program>'", spec),
+        }),
+    }
+}
+
+/// Text that represents a prompt for embedding: name words, tags and content
+pub fn prompt_text(name: &str, tags: &[String], content: &str) -> String {
+    format!("{} {} {}", name.replace(['-', '_'], " "), tags.join(" "), content)
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm_a: f64 = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norm_b: f64 = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Little-endian `f32` encoding used for the `vector` BLOB column
+pub fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+}
+
+/// Crude suffix stripping so "summarizes", "summarizing" and "summarise"
+/// land on the same feature
+fn stem(word: &str) -> String {
+    let mut word = word.replace("ise", "ize");
+    for suffix in ["ing", "ed", "es", "s", "e", "ly"] {
+        if word.len() > suffix.len() + 3 && word.ends_with(suffix) {
+            word.truncate(word.len() - suffix.len());
+            break;
+        }
+    }
+    word
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_embedder_reads_output_while_writing_input() {
+        // Fills the stdout pipe before reading any of its input
+        let embedder = CommandEmbedder::new(
+            "head -c 200000 /dev/zero | tr '\\0' ' '; echo '[3, 4]'; cat > /dev/null".to_string(),
+        );
+        let vector = embedder.embed(&"word ".repeat(100_000)).unwrap();
+        assert_eq!(vector, vec![0.6, 0.8]);
+    }
+
+    #[test]
+    fn test_hashing_embedder_relates_paraphrases() {
+        let embedder = HashingEmbedder::default();
+        let query = embedder.embed("the prompt that summarizes PRs and pull requests").unwrap();
+        let related = embedder
+            .embed(&prompt_text("changelog-writer", &[], "Summarize the pull request below"))
+            .unwrap();
+        let unrelated = embedder
+            .embed(&prompt_text("recipe", &[], "Suggest a dinner recipe using chickpeas"))
+            .unwrap();
+
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated));
+    }
+
+    #[test]
+    fn test_vector_roundtrip() {
+        let vector = vec![0.5, -1.25, 3.0];
+        assert_eq!(decode(&encode(&vector)), vector);
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert_eq!(from_config("hashing").unwrap().model_id(), "hashing-256");
+        assert_eq!(from_config("command:embed.sh").unwrap().model_id(), "command:embed.sh");
+        assert!(from_config("openai").is_err());
+    }
+}
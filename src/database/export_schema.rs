@@ -0,0 +1,234 @@
+//! The published JSON Schema of export files, and a validator for the
+//! subset of JSON Schema it uses, so import checks files against the same
+//! document `export --schema` prints.
+
+use super::search::{edit_similarity, trigram_similarity};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// JSON Schema of the current export format
+pub const SCHEMA: &str = include_str!("../../schemas/export.schema.json");
+
+/// One way a document fails the schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Where in the document, e.g. `prompts[2].variables[0].name`; empty
+    /// for the document itself
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Every place `document` breaks the export schema
+pub fn validate(document: &Value) -> Vec<Problem> {
+    let schema: Value = serde_json::from_str(SCHEMA).expect("bundled export schema is valid JSON");
+    let mut validator = Validator { root: &schema, problems: Vec::new() };
+    validator.check(&schema, document, "");
+    validator.problems
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Validator<'a> {
+    fn check(&mut self, schema: &'a Value, value: &Value, path: &str) {
+        let Some(schema) = schema.as_object() else { return };
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix("#/")
+                .and_then(|pointer| self.root.pointer(&format!("/{}", pointer)))
+                .expect("bundled export schema references resolve");
+            self.check(target, value, path);
+            return;
+        }
+
+        let mut failures = Vec::new();
+        let mut fail = |message: String| failures.push(message);
+
+        if let Some(types) = schema.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => types.as_str().into_iter().collect(),
+            };
+            if !allowed.iter().any(|t| has_type(value, t)) {
+                let message = format!("expected {}, found {}", allowed.join(" or "), type_name(value));
+                self.problems.push(Problem { path: path.to_string(), message });
+                return;
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if value != expected {
+                fail(format!("expected {}, found {}", expected, value));
+            }
+        }
+        if let Some(Value::Array(options)) = schema.get("enum") {
+            if !options.contains(value) {
+                let options: Vec<String> = options.iter().map(Value::to_string).collect();
+                fail(format!("expected one of {}, found {}", options.join(", "), value));
+            }
+        }
+
+        if let Value::String(text) = value {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if length < min {
+                    fail(if min == 1 { "must not be empty".to_string() } else { format!("shorter than {} characters", min) });
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if length > max {
+                    fail(format!("longer than {} characters", max));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                let regex = Regex::new(pattern).expect("bundled export schema patterns compile");
+                if !regex.is_match(text) {
+                    fail(format!("{:?} does not match {}", text, pattern));
+                }
+            }
+            match schema.get("format").and_then(Value::as_str) {
+                Some("date-time") if chrono::DateTime::parse_from_rfc3339(text).is_err() => {
+                    fail(format!("{:?} is not an RFC 3339 date-time", text));
+                }
+                Some("uuid") if uuid::Uuid::parse_str(text).is_err() => {
+                    fail(format!("{:?} is not a UUID", text));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if value.as_f64().is_some_and(|number| number < min) {
+                fail(format!("must be at least {}", min));
+            }
+        }
+        self.problems.extend(failures.into_iter().map(|message| Problem { path: path.to_string(), message }));
+
+        match value {
+            Value::Object(object) => self.check_object(schema, object, path),
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (index, item) in items.iter().enumerate() {
+                        self.check(item_schema, item, &format!("{}[{}]", path, index));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_object(&mut self, schema: &'a Map<String, Value>, object: &Map<String, Value>, path: &str) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    self.problems.push(Problem { path: path.to_string(), message: format!("missing field '{}'", key) });
+                }
+            }
+        }
+        for (key, value) in object {
+            match properties.and_then(|p| p.get(key)) {
+                Some(property) => self.check(property, value, &child(key)),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    let known: Vec<&str> = properties.into_iter().flat_map(|p| p.keys()).map(String::as_str).collect();
+                    let message = match suggest_field(key, &known) {
+                        Some(suggestion) => format!("unknown field; did you mean '{}'?", suggestion),
+                        None => "unknown field".to_string(),
+                    };
+                    self.problems.push(Problem { path: child(key), message });
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Closest known field to a mistyped `key`, scored like mistyped settings
+fn suggest_field<'k>(key: &str, known: &[&'k str]) -> Option<&'k str> {
+    known
+        .iter()
+        .map(|candidate| (*candidate, trigram_similarity(key, candidate).max(edit_similarity(key, candidate))))
+        .filter(|(_, score)| *score >= 0.5)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(candidate, _)| candidate)
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_problems_are_reported_with_their_location() {
+        let document = json!({
+            "format_version": 2,
+            "version": "0.1.0",
+            "exported_at": "2025-07-15T10:30:00Z",
+            "prompts": [{
+                "id": "not-a-uuid",
+                "name": "bad name",
+                "content": 42,
+                "variables": [{"name": "ok"}, {"nmae": "typo"}],
+                "tags": [],
+                "created_at": "2025-07-15T10:30:00Z",
+                "updated_at": "yesterday"
+            }],
+            "saved_searches": [{"name": "all", "sort": "random", "limit": 0, "created_at": "2025-07-15T10:30:00Z"}],
+            "extra": true
+        });
+        let problems: Vec<String> = validate(&document).iter().map(Problem::to_string).collect();
+        assert_eq!(
+            problems,
+            [
+                "extra: unknown field",
+                "prompts[0].content: expected string, found integer",
+                "prompts[0].id: \"not-a-uuid\" is not a UUID",
+                "prompts[0].name: \"bad name\" does not match ^[a-zA-Z0-9_-]+$",
+                "prompts[0].updated_at: \"yesterday\" is not an RFC 3339 date-time",
+                "prompts[0].variables[1]: missing field 'name'",
+                "prompts[0].variables[1].nmae: unknown field; did you mean 'name'?",
+                "saved_searches[0].limit: must be at least 1",
+                "saved_searches[0].sort: expected one of \"name\", \"created\", \"updated\", null, found \"random\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::error::{PromptedsError, Result};
+use super::export_schema::{self, Problem};
+use super::models::ExportData;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Layout of export files. Bump it when fields are added, removed or change
+/// meaning, update `schemas/export.schema.json`, and add a shim that
+/// upgrades the previous version to `UPGRADES`.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// `UPGRADES[n - 1]` turns a format `n` document into format `n + 1`
+const UPGRADES: [fn(&mut Map<String, Value>); 1] = [upgrade_v1];
+
+/// Most schema problems listed in an import error
+const MAX_PROBLEMS: usize = 20;
+
+/// File formats of the export envelope. YAML and TOML write multi-line
+/// content as block strings, which are easier to read and edit by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl DataFormat {
+    /// The format implied by a file extension, if it is a known one
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(DataFormat::Json),
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            "toml" => Some(DataFormat::Toml),
+            _ => None,
+        }
+    }
+
+    /// The format called `name`, as in `name()` or any other case
+    pub fn from_name(name: &str) -> Option<Self> {
+        [DataFormat::Json, DataFormat::Yaml, DataFormat::Toml]
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DataFormat::Json => "JSON",
+            DataFormat::Yaml => "YAML",
+            DataFormat::Toml => "TOML",
+        }
+    }
+}
+
+/// Serialize `data`; `pretty` only affects JSON, the other formats are
+/// always laid out for reading
+pub fn to_string(data: &ExportData, format: DataFormat, pretty: bool) -> Result<String> {
+    let failed = |e: String| PromptedsError::ExportError {
+        details: format!("cannot write {}: {}", format.name(), e),
+    };
+    match format {
+        DataFormat::Json if pretty => Ok(serde_json::to_string_pretty(data)?),
+        DataFormat::Json => Ok(serde_json::to_string(data)?),
+        DataFormat::Yaml => serde_norway::to_string(data).map_err(|e| failed(e.to_string())),
+        DataFormat::Toml => toml::to_string_pretty(data).map_err(|e| failed(e.to_string())),
+    }
+}
+
+/// Read an export file: upgrade it from older format versions, then check
+/// it against the export schema so nothing is silently dropped
+pub fn parse(text: &str, format: DataFormat) -> Result<ExportData> {
+    let invalid = |e: String| PromptedsError::ImportError {
+        details: format!("Invalid {} format: {}", format.name(), e),
+    };
+    let mut document: Value = match format {
+        DataFormat::Json => serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?,
+        DataFormat::Yaml => serde_norway::from_str(text).map_err(|e| invalid(e.to_string()))?,
+        DataFormat::Toml => toml::from_str(text).map_err(|e| invalid(e.message().to_string()))?,
+    };
+
+    let version = match document.get("format_version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| invalid(format!("format_version must be a positive integer, found {}", version)))?,
+    };
+    if version > FORMAT_VERSION as u64 {
+        return Err(PromptedsError::ImportError {
+            details: format!(
+                "the file uses export format {}, but this edisonprompt reads formats up to {}; upgrade edisonprompt to import it",
+                version, FORMAT_VERSION
+            ),
+        });
+    }
+    if let Value::Object(fields) = &mut document {
+        for upgrade in &UPGRADES[version as usize - 1..] {
+            upgrade(fields);
+        }
+        fields.insert("format_version".to_string(), FORMAT_VERSION.into());
+    }
+
+    let problems = export_schema::validate(&document);
+    if !problems.is_empty() {
+        let mut lines: Vec<String> = problems
+            .iter()
+            .take(MAX_PROBLEMS)
+            .map(|problem| format!("  {}", locate(problem, &document)))
+            .collect();
+        if problems.len() > MAX_PROBLEMS {
+            lines.push(format!("  ... and {} more", problems.len() - MAX_PROBLEMS));
+        }
+        return Err(invalid(format!("{} problem(s):\n{}", problems.len(), lines.join("\n"))));
+    }
+    serde_json::from_value(document).map_err(|e| invalid(e.to_string()))
+}
+
+/// Format 1 files, written before format versions existed, carry only the
+/// app `version`; the earliest called it `schema_version`
+fn upgrade_v1(document: &mut Map<String, Value>) {
+    if let Some(version) = document.remove("schema_version") {
+        document.entry("version").or_insert(version);
+    }
+}
+
+/// `problem` with the name of the prompt or saved search it is in
+fn locate(problem: &Problem, document: &Value) -> String {
+    for (list, kind) in [("prompts", "prompt"), ("saved_searches", "saved search")] {
+        let Some((index, _)) = problem.path.strip_prefix(list).and_then(|rest| rest.strip_prefix('[')?.split_once(']')) else {
+            continue;
+        };
+        let name = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| document.get(list)?.get(index)?.get("name")?.as_str());
+        if let Some(name) = name {
+            return format!("{} ({} '{}'): {}", problem.path, kind, name, problem.message);
+        }
+    }
+    problem.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{Prompt, SavedSearch, Variable};
+    use chrono::TimeZone;
+
+    fn sample() -> ExportData {
+        let created = chrono::Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 58).unwrap()
+            + chrono::Duration::nanoseconds(123_456_789);
+        ExportData {
+            format_version: FORMAT_VERSION,
+            version: "0.1.0".to_string(),
+            exported_at: created,
+            prompts: vec![
+                Prompt {
+                    id: uuid::Uuid::new_v4(),
+                    name: "review".to_string(),
+                    content: "Review this {{language}} code:\n\n  {{code}}\n\nQuote \"\"\" and 'marks' # not a comment".to_string(),
+                    variables: vec![
+                        Variable::new("language".to_string())
+                            .with_description("Language: name".to_string())
+                            .with_default("rust".to_string()),
+                        Variable::new("code".to_string()),
+                        Variable::new("token".to_string()).with_secret(Some("env:API_TOKEN".to_string())),
+                    ],
+                    tags: vec!["code".to_string(), "yes".to_string(), "1.0".to_string()],
+                    created_at: created,
+                    updated_at: chrono::Utc::now(),
+                },
+                Prompt {
+                    id: uuid::Uuid::new_v4(),
+                    name: "plain".to_string(),
+                    content: "One line\twith a tab\n\tand trailing space ".to_string(),
+                    variables: Vec::new(),
+                    tags: Vec::new(),
+                    created_at: created,
+                    updated_at: created,
+                },
+            ],
+            saved_searches: vec![SavedSearch {
+                name: "code".to_string(),
+                query: Some("review OR refactor".to_string()),
+                tags: vec!["code".to_string()],
+                sort: None,
+                limit: Some(5),
+                created_at: created,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_every_format_round_trips_losslessly() {
+        let data = sample();
+        let expected = serde_json::to_value(&data).unwrap();
+
+        for format in [DataFormat::Json, DataFormat::Yaml, DataFormat::Toml] {
+            let text = to_string(&data, format, true).unwrap();
+            let parsed = parse(&text, format).unwrap();
+            assert_eq!(serde_json::to_value(&parsed).unwrap(), expected, "{:?}:\n{}", format, text);
+        }
+    }
+
+    #[test]
+    fn test_multi_line_content_is_written_as_block_strings() {
+        let data = sample();
+        let yaml = to_string(&data, DataFormat::Yaml, false).unwrap();
+        assert!(yaml.contains("content: |"), "{}", yaml);
+        let toml = to_string(&data, DataFormat::Toml, false).unwrap();
+        assert!(toml.contains("content = \"\"\""), "{}", toml);
+    }
+
+    #[test]
+    fn test_older_formats_are_upgraded_and_newer_ones_refused() {
+        let v1 = r#"{"schema_version": "1.0", "exported_at": "2025-07-15T10:30:00Z", "prompts": [{
+            "id": "0b5e8f4e-5d43-4c3b-9a47-2f6f0c7f1a10", "name": "hello", "content": "Hi {{name}}",
+            "variables": [{"name": "name", "description": null, "default_value": null}], "tags": [],
+            "created_at": "2025-07-15T10:30:00Z", "updated_at": "2025-07-15T10:30:00Z"}]}"#;
+        let data = parse(v1, DataFormat::Json).unwrap();
+        assert_eq!((data.format_version, data.version.as_str()), (FORMAT_VERSION, "1.0"));
+        assert_eq!(data.prompts[0].name, "hello");
+
+        let future = r#"{"format_version": 99, "version": "9.0.0", "exported_at": "2030-01-01T00:00:00Z", "prompts": []}"#;
+        let error = parse(future, DataFormat::Json).unwrap_err().to_string();
+        assert!(error.contains("export format 99"), "{}", error);
+
+        let typo = v1.replace("\"tags\"", "\"tag\"");
+        let error = parse(&typo, DataFormat::Json).unwrap_err().to_string();
+        assert!(error.contains("prompts[0] (prompt 'hello'): missing field 'tags'"), "{}", error);
+        assert!(error.contains("prompts[0].tag (prompt 'hello'): unknown field; did you mean 'tags'?"), "{}", error);
+    }
+}
@@ -1,19 +1,83 @@
+pub mod backup;
+pub mod converters;
+pub mod crypto;
+pub mod dedupe;
+pub mod embeddings;
+pub mod export_schema;
+pub mod interchange;
 pub mod models;
 pub mod schema;
 pub mod search;
+pub mod stats;
+pub mod sync;
 
 use crate::error::Result;
-use search::SearchEngine;
-use models::{Prompt, PromptSummary, SearchResult};
-use rusqlite::{Connection, OpenFlags};
-use std::path::Path;
+use crypto::{Cipher, KeyParams, Stored, Vault};
+use embeddings::{Embedder, HashingEmbedder};
+use search::{Ranking, SearchEngine};
+use models::{Prompt, PromptSource, PromptSummary, SavedSearch, SearchResult, UsageEvent};
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, Transaction, TransactionBehavior};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub struct Database {
     conn: Connection,
+    ranking: Ranking,
+    embedder: Box<dyn Embedder>,
+    track_usage: bool,
+    /// Renders kept for `get --paste-last`
+    render_history: usize,
+    project: Option<ProjectLayer>,
+    backups: Option<backup::Rotation>,
+    vault: Vault,
+}
+
+/// Content and variable defaults of a prompt as they are stored, sealed
+/// when the library is encrypted
+struct Sealed {
+    content: rusqlite::types::Value,
+    defaults: Vec<Option<rusqlite::types::Value>>,
+}
+
+/// How the SQLite connection is set up
+#[derive(Debug, Clone)]
+pub struct ConnectionOptions {
+    /// How long to wait for another process's lock before failing
+    pub busy_timeout: Duration,
+    /// Write-ahead logging lets readers run alongside a writer
+    pub wal_mode: bool,
+    /// Automatic backups before migrations and other destructive changes
+    pub backups: Option<backup::Rotation>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        Self {
+            busy_timeout: Duration::from_millis(5000),
+            wal_mode: true,
+            backups: None,
+        }
+    }
+}
+
+/// Attempts at a write that still finds the database locked after the
+/// busy timeout, waiting twice as long before each retry
+const WRITE_ATTEMPTS: u32 = 4;
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Read-only prompts of the current project, consulted before the library
+struct ProjectLayer {
+    database: Box<Database>,
+    path: PathBuf,
 }
 
 impl Database {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path, &ConnectionOptions::default())
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P, options: &ConnectionOptions) -> Result<Self> {
         let path = path.as_ref();
         
         // Create parent directory if it doesn't exist
@@ -27,15 +91,174 @@ impl Database {
             OpenFlags::SQLITE_OPEN_READ_WRITE
         )?;
 
+        // Before anything touches the schema, which may need to wait for a lock
+        conn.busy_timeout(options.busy_timeout)?;
+        let journal_mode = if options.wal_mode { "WAL" } else { "DELETE" };
+        retry_busy(|| {
+            conn.query_row(&format!("PRAGMA journal_mode = {}", journal_mode), [], |_| Ok(()))?;
+            Ok(())
+        })?;
+
+        // Keep a copy of an existing database in the old schema
+        if let Some(rotation) = &options.backups {
+            let existing: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'prompts')",
+                [],
+                |row| row.get(0),
+            )?;
+            if existing && schema::needs_migration(&conn)? {
+                backup::rotate(&conn, rotation, "pre-migration")?;
+            }
+        }
+
+        let mut database = Self::from_connection(conn)?;
+        database.backups = options.backups.clone();
+        Ok(database)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
         // Initialize schema
-        schema::initialize_database(&conn)?;
+        retry_busy(|| schema::initialize_database(&conn))?;
+
+        Ok(Self {
+            conn,
+            ranking: Ranking::default(),
+            embedder: Box::new(HashingEmbedder::default()),
+            track_usage: true,
+            render_history: 0,
+            project: None,
+            backups: None,
+            vault: Vault::default(),
+        })
+    }
+
+    /// Layer the prompts in a project's prompts file (export format, in
+    /// JSON, YAML or TOML by extension) over this library. They are loaded
+    /// into memory and never written to.
+    pub fn with_project_layer(mut self, path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let format = interchange::DataFormat::from_path(path).unwrap_or(interchange::DataFormat::Json);
+        let data = interchange::parse(&content, format).map_err(|e| {
+            crate::error::PromptedsError::ImportError {
+                details: format!("{}: {}", path.display(), e),
+            }
+        })?;
+
+        // A checked-out repository decides what is in this file, so its
+        // secret commands are not run
+        let mut layer = Database::open_in_memory()?;
+        for mut prompt in data.prompts {
+            crate::template::secrets::drop_untrusted_commands(&mut prompt, None);
+            layer.create_prompt(&prompt).map_err(|e| crate::error::PromptedsError::ImportError {
+                details: format!("{}: prompt '{}': {}", path.display(), prompt.name, e),
+            })?;
+        }
+
+        self.project = Some(ProjectLayer {
+            database: Box::new(layer),
+            path: path.to_path_buf(),
+        });
+        Ok(self)
+    }
+
+    /// Library prompts hidden by a project prompt of the same name
+    pub fn shadowed_prompts(&self) -> Result<Vec<String>> {
+        let project_names = self.project_names()?;
+        let mut shadowed: Vec<String> = SearchEngine::new(&self.conn)
+            .all_names()?
+            .into_iter()
+            .filter(|name| project_names.contains(name))
+            .collect();
+        shadowed.sort();
+        Ok(shadowed)
+    }
+
+    /// Fail if `name` is a project prompt, which cannot be changed through
+    /// the library
+    pub fn ensure_in_library(&self, name: &str) -> Result<()> {
+        match self.project {
+            Some(ref project) if project.database.prompt_exists(name)? => {
+                Err(crate::error::PromptedsError::ProjectPrompt {
+                    name: name.to_string(),
+                    path: project.path.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn project_names(&self) -> Result<HashSet<String>> {
+        match self.project {
+            Some(ref project) => Ok(SearchEngine::new(&project.database.conn).all_names()?.into_iter().collect()),
+            None => Ok(HashSet::new()),
+        }
+    }
+
+    /// Put project results ahead of library results they shadow, then
+    /// re-rank everything by score
+    fn layer_results<F>(&self, results: Vec<SearchResult>, limit: usize, fetch: F) -> Result<Vec<SearchResult>>
+    where
+        F: FnOnce(&Database) -> Result<Vec<SearchResult>>,
+    {
+        let Some(ref project) = self.project else {
+            return Ok(results);
+        };
 
-        Ok(Self { conn })
+        let project_names = self.project_names()?;
+        let mut merged = fetch(&project.database)?;
+        merged.extend(results.into_iter().filter(|r| !project_names.contains(&r.prompt.name)));
+        merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+        merged.truncate(limit);
+        Ok(merged)
+    }
+
+    /// Use custom weights and boosts for full-text search ordering
+    pub fn with_ranking(mut self, ranking: Ranking) -> Self {
+        self.ranking = ranking;
+        self
+    }
+
+    /// Use a different embedder for semantic search. Existing vectors from
+    /// another model are ignored until `reindex_embeddings` runs.
+    pub fn with_embedder(mut self, embedder: Box<dyn Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Turn usage recording on or off; when off `record_usage` does nothing
+    pub fn with_usage_tracking(mut self, track: bool) -> Self {
+        self.track_usage = track;
+        self
+    }
+
+    /// Keep the last `keep` renders for `recent_render`; 0 keeps none
+    pub fn with_render_history(mut self, keep: usize) -> Self {
+        self.render_history = keep;
+        self
     }
 
     pub fn create_prompt(&mut self, prompt: &Prompt) -> Result<()> {
-        let tx = self.conn.transaction()?;
-        
+        let vector = self.embed_prompt(prompt);
+        let sealed = self.seal_prompt(prompt)?;
+        self.write(|tx| Self::write_new(tx, prompt, &sealed, &self.embedder.model_id(), vector.as_deref()))
+    }
+
+    /// Create `prompt` in place of the prompt called `name`. Both happen in
+    /// one transaction, so a failed insert leaves the old prompt in place.
+    pub fn replace_prompt(&mut self, name: &str, prompt: &Prompt) -> Result<()> {
+        let vector = self.embed_prompt(prompt);
+        let sealed = self.seal_prompt(prompt)?;
+        self.write(|tx| {
+            tx.execute("DELETE FROM prompts WHERE name = ?1", rusqlite::params![name])?;
+            Self::write_new(tx, prompt, &sealed, &self.embedder.model_id(), vector.as_deref())
+        })
+    }
+
+    fn write_new(tx: &Connection, prompt: &Prompt, sealed: &Sealed, model: &str, vector: Option<&[f32]>) -> Result<()> {
         // Insert prompt
         tx.execute(
             "INSERT INTO prompts (id, name, content, created_at, updated_at) 
@@ -43,22 +266,24 @@ impl Database {
             rusqlite::params![
                 prompt.id.to_string(),
                 prompt.name,
-                prompt.content,
+                sealed.content,
                 prompt.created_at.to_rfc3339(),
                 prompt.updated_at.to_rfc3339()
             ],
         )?;
 
         // Insert variables
-        for variable in &prompt.variables {
+        for (variable, default_value) in prompt.variables.iter().zip(&sealed.defaults) {
             tx.execute(
-                "INSERT INTO variables (prompt_id, name, description, default_value) 
-                 VALUES (?1, ?2, ?3, ?4)",
+                "INSERT INTO variables (prompt_id, name, description, default_value, secret, source) 
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                 rusqlite::params![
                     prompt.id.to_string(),
                     variable.name,
                     variable.description,
-                    variable.default_value
+                    default_value,
+                    variable.secret,
+                    variable.source
                 ],
             )?;
         }
@@ -85,24 +310,57 @@ impl Database {
             )?;
         }
 
-        tx.commit()?;
+        Self::store_embedding(tx, &prompt.id, model, vector)?;
         Ok(())
     }
 
     pub fn get_prompt(&self, name: &str) -> Result<Prompt> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, content, created_at, updated_at FROM prompts WHERE name = ?1"
-        )?;
+        if let Some(ref project) = self.project {
+            if let Some(prompt) = project.database.find_prompt("name", name)? {
+                return Ok(prompt);
+            }
+        }
+
+        self.find_prompt("name", name)?
+            .ok_or_else(|| self.not_found(name))
+    }
+
+    pub fn get_prompt_by_id(&self, id: &uuid::Uuid) -> Result<Prompt> {
+        self.find_prompt("id", &id.to_string())?
+            .ok_or_else(|| crate::error::PromptedsError::PromptNotFound {
+                name: id.to_string(),
+                suggestions: Vec::new(),
+            })
+    }
 
-        let prompt_data = stmt.query_row(rusqlite::params![name], |row| {
+    /// The library prompt an imported `prompt` corresponds to: the one with
+    /// its id, or else the one with its name
+    pub fn matching_prompt(&self, prompt: &Prompt) -> Result<Option<Prompt>> {
+        match self.find_prompt("id", &prompt.id.to_string())? {
+            Some(found) => Ok(Some(found)),
+            None => self.find_prompt("name", &prompt.name),
+        }
+    }
+
+    fn find_prompt(&self, column: &str, value: &str) -> Result<Option<Prompt>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, name, content, created_at, updated_at FROM prompts WHERE {} = ?1",
+            column
+        ))?;
+
+        let prompt_data = stmt.query_row(rusqlite::params![value], |row| {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
+                row.get::<_, Stored>(2)?,
                 row.get::<_, String>(3)?,
                 row.get::<_, String>(4)?,
             ))
-        }).map_err(|_| crate::error::PromptedsError::PromptNotFound { name: name.to_string() })?;
+        }).optional()?;
+
+        let Some(prompt_data) = prompt_data else {
+            return Ok(None);
+        };
 
         let id = uuid::Uuid::parse_str(&prompt_data.0)?;
         let created_at = chrono::DateTime::parse_from_rfc3339(&prompt_data.3)?.with_timezone(&chrono::Utc);
@@ -114,87 +372,94 @@ impl Database {
         // Get tags
         let tags = self.get_prompt_tags(&id)?;
 
-        Ok(Prompt {
+        Ok(Some(Prompt {
             id,
             name: prompt_data.1,
-            content: prompt_data.2,
+            content: self.vault.reveal(&self.conn, prompt_data.2, &crypto::content_context(&id))?,
             variables,
             tags,
             created_at,
             updated_at,
-        })
+        }))
     }
 
     pub fn update_prompt(&mut self, prompt: &Prompt) -> Result<()> {
-        let tx = self.conn.transaction()?;
-
-        // Update prompt
-        tx.execute(
-            "UPDATE prompts SET content = ?1, updated_at = ?2 WHERE id = ?3",
-            rusqlite::params![
-                prompt.content,
-                prompt.updated_at.to_rfc3339(),
-                prompt.id.to_string()
-            ],
-        )?;
-
-        // Delete existing variables
-        tx.execute(
-            "DELETE FROM variables WHERE prompt_id = ?1",
-            rusqlite::params![prompt.id.to_string()],
-        )?;
-
-        // Insert new variables
-        for variable in &prompt.variables {
-            tx.execute(
-                "INSERT INTO variables (prompt_id, name, description, default_value) 
-                 VALUES (?1, ?2, ?3, ?4)",
-                rusqlite::params![
-                    prompt.id.to_string(),
-                    variable.name,
-                    variable.description,
-                    variable.default_value
-                ],
-            )?;
-        }
+        let vector = self.embed_prompt(prompt);
+        let sealed = self.seal_prompt(prompt)?;
+        self.write(|tx| {
+            Self::write_update(tx, prompt, &sealed)?;
+            Self::store_embedding(tx, &prompt.id, &self.embedder.model_id(), vector.as_deref())
+        })
+    }
 
-        // Delete existing tag associations
-        tx.execute(
-            "DELETE FROM prompt_tags WHERE prompt_id = ?1",
-            rusqlite::params![prompt.id.to_string()],
-        )?;
+    /// Save `prompt` only if the stored copy was last updated at
+    /// `expected_updated_at`, i.e. nobody else saved it since it was read.
+    /// Fails with `PromptConflict` otherwise.
+    pub fn update_prompt_if_unchanged(
+        &mut self,
+        prompt: &Prompt,
+        expected_updated_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let vector = self.embed_prompt(prompt);
+        let sealed = self.seal_prompt(prompt)?;
+        self.write(|tx| {
+            let stored: Option<String> = tx.query_row(
+                "SELECT updated_at FROM prompts WHERE id = ?1",
+                rusqlite::params![prompt.id.to_string()],
+                |row| row.get(0),
+            ).optional()?;
+            let Some(stored) = stored else {
+                return Err(self.not_found(&prompt.name));
+            };
+            let stored = chrono::DateTime::parse_from_rfc3339(&stored)?.with_timezone(&chrono::Utc);
+            if stored != *expected_updated_at {
+                return Err(crate::error::PromptedsError::PromptConflict { name: prompt.name.clone() });
+            }
 
-        // Insert new tags
-        for tag in &prompt.tags {
-            tx.execute(
-                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
-                rusqlite::params![tag],
-            )?;
+            Self::write_update(tx, prompt, &sealed)?;
+            Self::store_embedding(tx, &prompt.id, &self.embedder.model_id(), vector.as_deref())
+        })
+    }
 
-            let tag_id: i64 = tx.query_row(
-                "SELECT id FROM tags WHERE name = ?1",
-                rusqlite::params![tag],
-                |row| row.get(0),
-            )?;
+    /// Save `merged` and delete `duplicates` in one transaction. Usage
+    /// counts of the removed prompts are added to the one that is kept.
+    pub fn merge_prompts(&mut self, merged: &Prompt, duplicates: &[Prompt]) -> Result<()> {
+        let vector = self.embed_prompt(merged);
+        let sealed = self.seal_prompt(merged)?;
+        self.write(|tx| {
+            Self::write_update(tx, merged, &sealed)?;
+            Self::store_embedding(tx, &merged.id, &self.embedder.model_id(), vector.as_deref())?;
 
-            tx.execute(
-                "INSERT INTO prompt_tags (prompt_id, tag_id) VALUES (?1, ?2)",
-                rusqlite::params![prompt.id.to_string(), tag_id],
-            )?;
-        }
+            for duplicate in duplicates {
+                tx.execute(
+                    "INSERT INTO prompt_flags (prompt_id, pinned, favorite)
+                     SELECT ?1, pinned, favorite FROM prompt_flags WHERE prompt_id = ?2
+                     ON CONFLICT(prompt_id) DO UPDATE SET
+                        pinned = MAX(pinned, excluded.pinned),
+                        favorite = MAX(favorite, excluded.favorite)",
+                    rusqlite::params![merged.id.to_string(), duplicate.id.to_string()],
+                )?;
+                tx.execute(
+                    "UPDATE usage_events SET prompt_id = ?1 WHERE prompt_id = ?2",
+                    rusqlite::params![merged.id.to_string(), duplicate.id.to_string()],
+                )?;
+                tx.execute(
+                    "DELETE FROM prompts WHERE id = ?1",
+                    rusqlite::params![duplicate.id.to_string()],
+                )?;
+            }
 
-        tx.commit()?;
-        Ok(())
+            Ok(())
+        })
     }
 
     pub fn delete_prompt(&mut self, name: &str) -> Result<()> {
-        let rows_affected = self.conn.execute(
-            "DELETE FROM prompts WHERE name = ?1",
-            rusqlite::params![name],
-        )?;
+        let rows_affected = self.write(|tx| {
+            Ok(tx.execute("DELETE FROM prompts WHERE name = ?1", rusqlite::params![name])?)
+        })?;
 
         if rows_affected == 0 {
-            return Err(crate::error::PromptedsError::PromptNotFound { name: name.to_string() });
+            return Err(self.not_found(name));
         }
 
         Ok(())
@@ -210,20 +475,81 @@ impl Database {
     }
 
     pub fn list_prompts(&self, tag_filter: Option<&str>, limit: Option<usize>) -> Result<Vec<PromptSummary>> {
+        let tags: Vec<String> = tag_filter.map(str::to_string).into_iter().collect();
+        self.list_prompts_matching(None, &tags, limit)
+    }
+
+    /// List prompts that match an optional full-text query and carry every
+    /// one of `tags`, including project prompts
+    pub fn list_prompts_matching(
+        &self,
+        query: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<PromptSummary>> {
+        let Some(ref project) = self.project else {
+            return self.list_library_prompts(query, tags, limit);
+        };
+
+        let project_names = self.project_names()?;
+        let mut summaries = project.database.list_library_prompts(query, tags, None)?;
+        for summary in &mut summaries {
+            summary.source = PromptSource::Project;
+        }
+        summaries.extend(
+            self.list_library_prompts(query, tags, None)?
+                .into_iter()
+                .filter(|s| !project_names.contains(&s.name)),
+        );
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        if let Some(limit) = limit {
+            summaries.truncate(limit);
+        }
+        Ok(summaries)
+    }
+
+    fn list_library_prompts(
+        &self,
+        query: Option<&str>,
+        tags: &[String],
+        limit: Option<usize>,
+    ) -> Result<Vec<PromptSummary>> {
         let mut sql = String::from(
             "SELECT p.name, p.created_at, p.updated_at,
                     COUNT(DISTINCT v.id) as variable_count,
-                    COUNT(DISTINCT pt.tag_id) as tag_count
+                    COUNT(DISTINCT pt.tag_id) as tag_count,
+                    COALESCE(f.pinned, 0), COALESCE(f.favorite, 0),
+                    (SELECT MAX(e.used_at) FROM usage_events e
+                     WHERE e.prompt_id = p.id AND e.rendered) as last_used_at
              FROM prompts p
              LEFT JOIN variables v ON p.id = v.prompt_id
-             LEFT JOIN prompt_tags pt ON p.id = pt.prompt_id"
+             LEFT JOIN prompt_tags pt ON p.id = pt.prompt_id
+             LEFT JOIN prompt_flags f ON p.id = f.prompt_id"
         );
 
-        let mut params = Vec::new();
+        let mut conditions = Vec::new();
+        let mut params: Vec<&str> = Vec::new();
+
+        if let Some(query) = query {
+            params.push(query);
+            conditions.push(format!(
+                "p.rowid IN (SELECT rowid FROM prompts_fts WHERE prompts_fts MATCH ?{})",
+                params.len()
+            ));
+        }
 
-        if let Some(tag) = tag_filter {
-            sql.push_str(" INNER JOIN tags t ON pt.tag_id = t.id WHERE t.name = ?1");
+        for tag in tags {
             params.push(tag);
+            conditions.push(format!(
+                "p.id IN (SELECT tp.prompt_id FROM prompt_tags tp
+                          INNER JOIN tags t ON tp.tag_id = t.id WHERE t.name = ?{})",
+                params.len()
+            ));
+        }
+
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
         }
 
         sql.push_str(" GROUP BY p.id, p.name, p.created_at, p.updated_at ORDER BY p.updated_at DESC");
@@ -240,6 +566,9 @@ impl Database {
                 row.get::<_, String>(2)?,
                 row.get::<_, i64>(3)?,
                 row.get::<_, i64>(4)?,
+                row.get::<_, bool>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<String>>(7)?,
             ))
         })?;
 
@@ -248,6 +577,10 @@ impl Database {
             let data = row?;
             let created_at = chrono::DateTime::parse_from_rfc3339(&data.1)?.with_timezone(&chrono::Utc);
             let updated_at = chrono::DateTime::parse_from_rfc3339(&data.2)?.with_timezone(&chrono::Utc);
+            let last_used_at = match data.7 {
+                Some(s) => Some(chrono::DateTime::parse_from_rfc3339(&s)?.with_timezone(&chrono::Utc)),
+                None => None,
+            };
             
             summaries.push(PromptSummary {
                 name: data.0,
@@ -255,6 +588,10 @@ impl Database {
                 tag_count: data.4 as usize,
                 created_at,
                 updated_at,
+                pinned: data.5,
+                favorite: data.6,
+                last_used_at,
+                source: PromptSource::Library,
             });
         }
 
@@ -262,8 +599,229 @@ impl Database {
     }
 
     pub fn search_prompts(&self, query: &str, limit: usize, highlight: bool) -> Result<Vec<SearchResult>> {
-        let search = SearchEngine::new(&self.conn);
-        search.search(query, limit, highlight)
+        let search = SearchEngine::new(&self.conn).with_vault(&self.vault);
+        let results = search.search(query, limit, highlight, &self.ranking)?;
+        self.layer_results(results, limit, |project| {
+            SearchEngine::new(&project.conn)
+                .with_vault(&project.vault)
+                .search(query, limit, highlight, &self.ranking)
+        })
+    }
+
+    /// Create or replace a saved search
+    pub fn save_search(&self, search: &SavedSearch) -> Result<()> {
+        let tags = serde_json::to_string(&search.tags)?;
+        self.write(|tx| {
+            tx.execute(
+                "INSERT OR REPLACE INTO saved_searches (name, query, tags, sort, result_limit, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    search.name,
+                    search.query,
+                    tags,
+                    search.sort,
+                    search.limit.map(|l| l as i64),
+                    search.created_at.to_rfc3339()
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_saved_search(&self, name: &str) -> Result<SavedSearch> {
+        self.list_saved_searches()?
+            .into_iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| crate::error::PromptedsError::SavedSearchNotFound { name: name.to_string() })
+    }
+
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, query, tags, sort, result_limit, created_at FROM saved_searches ORDER BY name"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, String>(5)?,
+            ))
+        })?;
+
+        let mut searches = Vec::new();
+        for row in rows {
+            let data = row?;
+            searches.push(SavedSearch {
+                name: data.0,
+                query: data.1,
+                tags: serde_json::from_str(&data.2)?,
+                sort: data.3,
+                limit: data.4.map(|l| l as usize),
+                created_at: chrono::DateTime::parse_from_rfc3339(&data.5)?.with_timezone(&chrono::Utc),
+            });
+        }
+
+        Ok(searches)
+    }
+
+    pub fn delete_saved_search(&self, name: &str) -> Result<()> {
+        let rows_affected = self.write(|tx| {
+            Ok(tx.execute("DELETE FROM saved_searches WHERE name = ?1", rusqlite::params![name])?)
+        })?;
+
+        if rows_affected == 0 {
+            return Err(crate::error::PromptedsError::SavedSearchNotFound { name: name.to_string() });
+        }
+
+        Ok(())
+    }
+
+    /// Record one use of a prompt for `stats` and the usage boost in search
+    /// ranking. Does nothing when usage tracking is disabled.
+    pub fn record_usage(&self, event: &UsageEvent) -> Result<()> {
+        if !self.track_usage {
+            return Ok(());
+        }
+        // Project prompts live in memory only, so their use is not recorded
+        if let Some(ref project) = self.project {
+            if project.database.find_prompt("id", &event.prompt_id.to_string())?.is_some() {
+                return Ok(());
+            }
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let variables = serde_json::to_string(&event.variables)?;
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO usage_events (prompt_id, used_at, rendered, copied, variables)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![event.prompt_id.to_string(), now, event.rendered, event.copied, variables],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Keep a rendered prompt for `get --paste-last`, dropping the oldest
+    /// beyond the history size. Does nothing when no history is kept.
+    pub fn record_render(&self, prompt_id: &uuid::Uuid, rendered: &str) -> Result<()> {
+        if self.render_history == 0 {
+            return Ok(());
+        }
+        // Project prompts live in memory only, so their renders are not kept
+        if let Some(ref project) = self.project {
+            if project.database.find_prompt("id", &prompt_id.to_string())?.is_some() {
+                return Ok(());
+            }
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let content = self.vault.seal(&self.conn, rendered, &crypto::render_context(prompt_id, &now))?;
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO render_history (prompt_id, rendered_at, content) VALUES (?1, ?2, ?3)",
+                rusqlite::params![prompt_id.to_string(), now, content],
+            )?;
+            tx.execute(
+                "DELETE FROM render_history WHERE id NOT IN
+                    (SELECT id FROM render_history ORDER BY id DESC LIMIT ?1)",
+                rusqlite::params![self.render_history as i64],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// A kept render, `back` renders ago (1 is the last one)
+    pub fn recent_render(&self, back: usize) -> Result<Option<String>> {
+        let row = self.conn.query_row(
+            "SELECT prompt_id, rendered_at, content FROM render_history ORDER BY id DESC LIMIT 1 OFFSET ?1",
+            rusqlite::params![back.saturating_sub(1) as i64],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Stored>(2)?)),
+        ).optional()?;
+        let Some((prompt_id, rendered_at, content)) = row else {
+            return Ok(None);
+        };
+        let prompt_id = uuid::Uuid::parse_str(&prompt_id)?;
+        self.vault.reveal(&self.conn, content, &crypto::render_context(&prompt_id, &rendered_at)).map(Some)
+    }
+
+    /// Pin or unpin a prompt; pinned prompts are listed and offered first
+    pub fn set_pinned(&self, name: &str, pinned: bool) -> Result<()> {
+        self.set_flag(name, "pinned", pinned)
+    }
+
+    pub fn set_favorite(&self, name: &str, favorite: bool) -> Result<()> {
+        self.set_flag(name, "favorite", favorite)
+    }
+
+    fn set_flag(&self, name: &str, column: &str, value: bool) -> Result<()> {
+        self.ensure_in_library(name)?;
+        let prompt_id: Option<String> = self.conn.query_row(
+            "SELECT id FROM prompts WHERE name = ?1",
+            rusqlite::params![name],
+            |row| row.get(0),
+        ).optional()?;
+        let prompt_id = prompt_id.ok_or_else(|| self.not_found(name))?;
+
+        self.write(|tx| {
+            tx.execute(
+                &format!(
+                    "INSERT INTO prompt_flags (prompt_id, {column}) VALUES (?1, ?2)
+                     ON CONFLICT(prompt_id) DO UPDATE SET {column} = excluded.{column}"
+                ),
+                rusqlite::params![prompt_id, value],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn usage_stats(&self, days: u32, limit: usize) -> Result<stats::UsageStats> {
+        stats::collect(&self.conn, days, limit)
+    }
+
+    pub fn search_prompts_fuzzy(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let search = SearchEngine::new(&self.conn).with_vault(&self.vault);
+        let results = search.fuzzy_search(query, limit)?;
+        self.layer_results(results, limit, |project| project.search_prompts_fuzzy(query, limit))
+    }
+
+    /// Resolve a user-supplied name to an existing prompt name, accepting
+    /// any prefix that identifies exactly one prompt.
+    pub fn resolve_prompt_name(&self, name: &str) -> Result<String> {
+        if self.prompt_exists(name)? {
+            return Ok(name.to_string());
+        }
+
+        let mut matches = SearchEngine::new(&self.conn).names_with_prefix(name)?;
+        if let Some(ref project) = self.project {
+            if project.database.prompt_exists(name)? {
+                return Ok(name.to_string());
+            }
+            matches.extend(SearchEngine::new(&project.database.conn).names_with_prefix(name)?);
+            matches.sort();
+            matches.dedup();
+        }
+        match matches.len() {
+            0 => Err(self.not_found(name)),
+            1 => Ok(matches.remove(0)),
+            _ => Err(crate::error::PromptedsError::AmbiguousPromptName {
+                name: name.to_string(),
+                matches,
+            }),
+        }
+    }
+
+    /// Build a `PromptNotFound` error carrying "did you mean" suggestions
+    fn not_found(&self, name: &str) -> crate::error::PromptedsError {
+        let suggestions = SearchEngine::new(&self.conn)
+            .suggest_names(name, 3)
+            .unwrap_or_default();
+
+        crate::error::PromptedsError::PromptNotFound {
+            name: name.to_string(),
+            suggestions,
+        }
     }
 
     pub fn get_all_prompts(&self) -> Result<Vec<Prompt>> {
@@ -275,7 +833,7 @@ impl Database {
             Ok((
                 row.get::<_, String>(0)?,
                 row.get::<_, String>(1)?,
-                row.get::<_, String>(2)?,
+                row.get::<_, Stored>(2)?,
                 row.get::<_, String>(3)?,
                 row.get::<_, String>(4)?,
             ))
@@ -294,7 +852,7 @@ impl Database {
             prompts.push(Prompt {
                 id,
                 name: data.1,
-                content: data.2,
+                content: self.vault.reveal(&self.conn, data.2, &crypto::content_context(&id))?,
                 variables,
                 tags,
                 created_at,
@@ -305,26 +863,443 @@ impl Database {
         Ok(prompts)
     }
 
-    pub fn get_schema_version(&self) -> Result<i32> {
-        schema::get_schema_version(&self.conn)
+    /// Prompts closest in meaning to `text`, best match first
+    pub fn semantic_search(&self, text: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let query = self.embedder.embed(text)?;
+        let results = self.nearest_prompts(&query, None, limit)?;
+        self.layer_results(results, limit, |project| project.semantic_search(text, limit))
+    }
+
+    /// Prompts closest in meaning to the named prompt, excluding itself
+    pub fn similar_prompts(&self, name: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let prompt = self.get_prompt(name)?;
+        let vector = match self.stored_embedding(&prompt.id)? {
+            Some(vector) => vector,
+            None => self.embedder.embed(&self.embedding_text(&prompt)?)?,
+        };
+        self.nearest_prompts(&vector, Some(&prompt.id), limit)
+    }
+
+    /// Recompute the vector of every prompt with the current embedder and
+    /// drop vectors left behind by other models
+    pub fn reindex_embeddings(&mut self) -> Result<usize> {
+        let prompts = self.get_all_prompts()?;
+        let model = self.embedder.model_id();
+
+        let mut vectors = Vec::with_capacity(prompts.len());
+        for prompt in &prompts {
+            vectors.push((prompt.id, self.embedder.embed(&self.embedding_text(prompt)?)?));
+        }
+
+        self.write(|tx| {
+            tx.execute("DELETE FROM prompt_embeddings WHERE model != ?1", rusqlite::params![model])?;
+            for (id, vector) in &vectors {
+                Self::store_embedding(tx, id, &model, Some(vector))?;
+            }
+            Ok(())
+        })?;
+
+        Ok(vectors.len())
+    }
+
+    /// Number of prompts with a vector from the current embedder, and the
+    /// total number of prompts
+    pub fn embedding_coverage(&self) -> Result<(usize, usize)> {
+        let (indexed, total): (i64, i64) = self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM prompt_embeddings WHERE model = ?1),
+                    (SELECT COUNT(*) FROM prompts)",
+            rusqlite::params![self.embedder.model_id()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok((indexed as usize, total as usize))
+    }
+
+    /// Whether prompt content and variable defaults are stored encrypted
+    pub fn is_encrypted(&self) -> Result<bool> {
+        self.vault.is_enabled(&self.conn)
+    }
+
+    /// Key parameters of an encrypted library
+    pub fn encryption_params(&self) -> Result<Option<KeyParams>> {
+        Ok(self.vault.settings(&self.conn)?.map(|(params, _)| params.clone()))
+    }
+
+    /// The derived key of an encrypted library, asking for the passphrase
+    /// unless it is already unlocked
+    pub fn unlock(&self) -> Result<&Cipher> {
+        self.vault.cipher(&self.conn)
+    }
+
+    /// Whether `EDISONPROMPT_KEY` unlocks this encrypted library
+    pub fn unlocked_by_env(&self) -> Result<bool> {
+        self.vault.env_unlocks(&self.conn)
+    }
+
+    /// Encrypt every prompt's content and variable defaults under a key
+    /// derived from `passphrase`. Returns the number of prompts encrypted.
+    pub fn enable_encryption(&mut self, passphrase: &str) -> Result<usize> {
+        if self.is_encrypted()? {
+            return Err(crate::error::PromptedsError::EncryptionError {
+                details: "this library is already encrypted".to_string(),
+            });
+        }
+        let prompts = self.get_all_prompts()?;
+        let params = KeyParams::generate();
+        let cipher = params.derive(passphrase)?;
+        let check_value = cipher.check_value();
+
+        self.vault = Vault::unlocked(cipher);
+        let result = self.write(|tx| {
+            tx.execute(
+                "INSERT INTO encryption (id, salt, memory_kib, iterations, parallelism, check_value, created_at)
+                 VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    params.salt,
+                    params.memory_kib,
+                    params.iterations,
+                    params.parallelism,
+                    check_value,
+                    chrono::Utc::now().to_rfc3339()
+                ],
+            )?;
+            for prompt in &prompts {
+                Self::write_sealed(tx, prompt, &self.seal_prompt(prompt)?)?;
+            }
+            // Kept renders hold content in the clear
+            tx.execute("DELETE FROM render_history", [])?;
+            Ok(())
+        });
+        if let Err(e) = result {
+            self.vault = Vault::default();
+            return Err(e);
+        }
+
+        // Vectors computed from the content would leak it, and so would the
+        // free pages that held it in the clear
+        self.reindex_embeddings()?;
+        self.vacuum()?;
+        Ok(prompts.len())
+    }
+
+    /// Decrypt every prompt and store the library in the clear again.
+    /// Returns the number of prompts decrypted.
+    pub fn disable_encryption(&mut self) -> Result<usize> {
+        if !self.is_encrypted()? {
+            return Err(crate::error::PromptedsError::EncryptionError {
+                details: "this library is not encrypted".to_string(),
+            });
+        }
+        let prompts = self.get_all_prompts()?;
+
+        let plain = Vault::default();
+        self.write(|tx| {
+            tx.execute("DELETE FROM encryption", [])?;
+            for prompt in &prompts {
+                Self::write_sealed(tx, prompt, &Self::seal_with(&plain, tx, prompt)?)?;
+            }
+            tx.execute("DELETE FROM render_history", [])?;
+            Ok(())
+        })?;
+        self.vault = plain;
+
+        self.reindex_embeddings()?;
+        Ok(prompts.len())
+    }
+
+    /// Snapshot the library to `dest`, safely even while it is in use
+    pub fn backup_to(&self, dest: &Path, compress: bool) -> Result<()> {
+        backup::snapshot(&self.conn, dest, compress)
+    }
+
+    /// Take an automatic backup before a destructive change, if they are
+    /// enabled. Returns the backup file.
+    pub fn auto_backup(&self, reason: &str) -> Result<Option<PathBuf>> {
+        self.backups
+            .as_ref()
+            .map(|rotation| backup::rotate(&self.conn, rotation, reason))
+            .transpose()
+    }
+
+    /// Replace the library with a verified backup, upgrading its schema if
+    /// it is older
+    pub fn restore_from(&mut self, snapshot: &backup::Snapshot) -> Result<()> {
+        retry_busy(|| backup::restore(&mut self.conn, snapshot))?;
+        retry_busy(|| schema::initialize_database(&self.conn))?;
+        // The backup may have been taken with encryption on or off
+        self.vault = Vault::default();
+        Ok(())
+    }
+
+    /// Rebuild the full-text index from the prompts table
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        self.write(|tx| {
+            tx.execute("INSERT INTO prompts_fts(prompts_fts) VALUES ('rebuild')", [])?;
+            Ok(())
+        })
+    }
+
+    /// Rewrite the database file without free pages. The checkpoint moves
+    /// the rewritten pages out of the WAL so the file itself shrinks.
+    pub fn vacuum(&self) -> Result<()> {
+        retry_busy(|| {
+            self.conn.execute_batch("VACUUM")?;
+            self.conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            Ok(())
+        })
+    }
+
+    fn nearest_prompts(
+        &self,
+        query: &[f32],
+        exclude: Option<&uuid::Uuid>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT prompt_id, vector FROM prompt_embeddings WHERE model = ?1"
+        )?;
+        let rows = stmt.query_map(rusqlite::params![self.embedder.model_id()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?;
+
+        let mut scored = Vec::new();
+        for row in rows {
+            let (id, bytes) = row?;
+            let id = uuid::Uuid::parse_str(&id)?;
+            if exclude == Some(&id) {
+                continue;
+            }
+            scored.push((id, embeddings::cosine_similarity(query, &embeddings::decode(&bytes))));
+        }
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+
+        scored
+            .into_iter()
+            .map(|(id, score)| {
+                Ok(SearchResult {
+                    prompt: self.get_prompt_by_id(&id)?,
+                    score,
+                    highlighted_content: None,
+                    explanation: None,
+                })
+            })
+            .collect()
+    }
+
+    /// Run `write` in a transaction that takes the write lock up front, so a
+    /// concurrent writer makes it wait for the busy timeout rather than fail
+    /// halfway. Inside `transaction` it runs in a savepoint instead, so a
+    /// failed write is undone without ending the enclosing transaction.
+    fn write<T>(&self, mut write: impl FnMut(&Connection) -> Result<T>) -> Result<T> {
+        if !self.conn.is_autocommit() {
+            self.conn.execute_batch("SAVEPOINT write")?;
+            let result = write(&self.conn);
+            if result.is_err() {
+                self.conn.execute_batch("ROLLBACK TO write")?;
+            }
+            self.conn.execute_batch("RELEASE write")?;
+            return result;
+        }
+        retry_busy(|| {
+            let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+            let value = write(&tx)?;
+            tx.commit()?;
+            Ok(value)
+        })
+    }
+
+    /// Run `body` in one transaction. Its writes are committed together if
+    /// it returns `Ok(true)`, and all rolled back if it returns `Ok(false)`
+    /// or fails; the result says whether they were committed.
+    pub fn transaction(&mut self, body: impl FnOnce(&mut Self) -> Result<bool>) -> Result<bool> {
+        retry_busy(|| Ok(self.conn.execute_batch("BEGIN IMMEDIATE")?))?;
+        let result = body(self).and_then(|commit| {
+            self.conn.execute_batch(if commit { "COMMIT" } else { "ROLLBACK" })?;
+            Ok(commit)
+        });
+        if result.is_err() && !self.conn.is_autocommit() {
+            self.conn.execute_batch("ROLLBACK")?;
+        }
+        result
+    }
+
+    /// Store `prompt`'s content and defaults as `sealed`, leaving the rest
+    /// of it, and its timestamps, alone
+    fn write_sealed(tx: &Connection, prompt: &Prompt, sealed: &Sealed) -> Result<()> {
+        tx.execute(
+            "UPDATE prompts SET content = ?1 WHERE id = ?2",
+            rusqlite::params![sealed.content, prompt.id.to_string()],
+        )?;
+        for (variable, default_value) in prompt.variables.iter().zip(&sealed.defaults) {
+            tx.execute(
+                "UPDATE variables SET default_value = ?1 WHERE prompt_id = ?2 AND name = ?3",
+                rusqlite::params![default_value, prompt.id.to_string(), variable.name],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_update(tx: &Connection, prompt: &Prompt, sealed: &Sealed) -> Result<()> {
+        // Update prompt
+        tx.execute(
+            "UPDATE prompts SET name = ?1, content = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![
+                prompt.name,
+                sealed.content,
+                prompt.updated_at.to_rfc3339(),
+                prompt.id.to_string()
+            ],
+        )?;
+
+        // Delete existing variables
+        tx.execute(
+            "DELETE FROM variables WHERE prompt_id = ?1",
+            rusqlite::params![prompt.id.to_string()],
+        )?;
+
+        // Insert new variables
+        for (variable, default_value) in prompt.variables.iter().zip(&sealed.defaults) {
+            tx.execute(
+                "INSERT INTO variables (prompt_id, name, description, default_value, secret, source) 
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    prompt.id.to_string(),
+                    variable.name,
+                    variable.description,
+                    default_value,
+                    variable.secret,
+                    variable.source
+                ],
+            )?;
+        }
+
+        // Delete existing tag associations
+        tx.execute(
+            "DELETE FROM prompt_tags WHERE prompt_id = ?1",
+            rusqlite::params![prompt.id.to_string()],
+        )?;
+
+        // Insert new tags
+        for tag in &prompt.tags {
+            tx.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                rusqlite::params![tag],
+            )?;
+
+            let tag_id: i64 = tx.query_row(
+                "SELECT id FROM tags WHERE name = ?1",
+                rusqlite::params![tag],
+                |row| row.get(0),
+            )?;
+
+            tx.execute(
+                "INSERT INTO prompt_tags (prompt_id, tag_id) VALUES (?1, ?2)",
+                rusqlite::params![prompt.id.to_string(), tag_id],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn embed_prompt(&self, prompt: &Prompt) -> Option<Vec<f32>> {
+        // A failing external embedder must not block saving; the prompt is
+        // simply left unindexed until the next reindex
+        self.embedding_text(prompt)
+            .and_then(|text| self.embedder.embed(&text))
+            .ok()
+    }
+
+    /// What a prompt's vector is computed from. Vectors are stored in the
+    /// clear, so an encrypted prompt is embedded by its name and tags only.
+    fn embedding_text(&self, prompt: &Prompt) -> Result<String> {
+        let content = if self.vault.is_enabled(&self.conn)? { "" } else { prompt.content.as_str() };
+        Ok(embeddings::prompt_text(&prompt.name, &prompt.tags, content))
+    }
+
+    /// `prompt`'s content and defaults as they are to be stored; unlocks
+    /// an encrypted library before any write lock is taken
+    fn seal_prompt(&self, prompt: &Prompt) -> Result<Sealed> {
+        Self::seal_with(&self.vault, &self.conn, prompt)
+    }
+
+    fn seal_with(vault: &Vault, conn: &Connection, prompt: &Prompt) -> Result<Sealed> {
+        let content = vault.seal(conn, &prompt.content, &crypto::content_context(&prompt.id))?;
+        let defaults = prompt
+            .variables
+            .iter()
+            .map(|variable| {
+                // Secret values are never stored
+                variable
+                    .default_value
+                    .as_ref()
+                    .filter(|_| !variable.secret)
+                    .map(|value| {
+                        vault.seal(conn, value, &crypto::default_context(&prompt.id, &variable.name))
+                    })
+                    .transpose()
+            })
+            .collect::<Result<_>>()?;
+        Ok(Sealed { content, defaults })
+    }
+
+    fn store_embedding(
+        conn: &Connection,
+        prompt_id: &uuid::Uuid,
+        model: &str,
+        vector: Option<&[f32]>,
+    ) -> Result<()> {
+        match vector {
+            Some(vector) => conn.execute(
+                "INSERT OR REPLACE INTO prompt_embeddings (prompt_id, model, dimensions, vector, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    prompt_id.to_string(),
+                    model,
+                    vector.len() as i64,
+                    embeddings::encode(vector),
+                    chrono::Utc::now().to_rfc3339()
+                ],
+            )?,
+            None => conn.execute(
+                "DELETE FROM prompt_embeddings WHERE prompt_id = ?1",
+                rusqlite::params![prompt_id.to_string()],
+            )?,
+        };
+        Ok(())
+    }
+
+    fn stored_embedding(&self, prompt_id: &uuid::Uuid) -> Result<Option<Vec<f32>>> {
+        let bytes: Option<Vec<u8>> = self.conn.query_row(
+            "SELECT vector FROM prompt_embeddings WHERE prompt_id = ?1 AND model = ?2",
+            rusqlite::params![prompt_id.to_string(), self.embedder.model_id()],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(bytes.map(|bytes| embeddings::decode(&bytes)))
     }
 
     fn get_prompt_variables(&self, prompt_id: &uuid::Uuid) -> Result<Vec<models::Variable>> {
         let mut stmt = self.conn.prepare(
-            "SELECT name, description, default_value FROM variables WHERE prompt_id = ?1"
+            "SELECT name, description, default_value, secret, source FROM variables WHERE prompt_id = ?1"
         )?;
 
         let rows = stmt.query_map(rusqlite::params![prompt_id.to_string()], |row| {
-            Ok(models::Variable {
-                name: row.get(0)?,
-                description: row.get(1)?,
-                default_value: row.get(2)?,
-            })
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<Stored>>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
         })?;
 
         let mut variables = Vec::new();
         for row in rows {
-            variables.push(row?);
+            let (name, description, default_value, secret, source) = row?;
+            let default_value = default_value
+                .map(|stored| self.vault.reveal(&self.conn, stored, &crypto::default_context(prompt_id, &name)))
+                .transpose()?;
+            variables.push(models::Variable { name, description, default_value, secret, source });
         }
 
         Ok(variables)
@@ -338,7 +1313,7 @@ impl Database {
         )?;
 
         let rows = stmt.query_map(rusqlite::params![prompt_id.to_string()], |row| {
-            Ok(row.get::<_, String>(0)?)
+            row.get::<_, String>(0)
         })?;
 
         let mut tags = Vec::new();
@@ -348,4 +1323,250 @@ impl Database {
 
         Ok(tags)
     }
-}
\ No newline at end of file
+}
+
+/// Run `op` again with backoff while another process still holds a lock
+/// after the busy timeout
+fn retry_busy<T>(mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if is_busy(&e) && attempt < WRITE_ATTEMPTS => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) if is_busy(&e) => return Err(crate::error::PromptedsError::DatabaseBusy),
+            result => return result,
+        }
+    }
+}
+
+/// Another connection holds a lock the operation needed
+fn is_busy(error: &crate::error::PromptedsError) -> bool {
+    matches!(
+        error,
+        crate::error::PromptedsError::Database(rusqlite::Error::SqliteFailure(e, _))
+            if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transactions_commit_together_and_failed_writes_are_undone_alone() {
+        let mut database = Database::open_in_memory().unwrap();
+        let too_long = Prompt::new("big".to_string(), "x".repeat(100_001));
+
+        let committed = database
+            .transaction(|database| {
+                database.create_prompt(&Prompt::new("a".to_string(), "A".to_string()))?;
+                assert!(database.create_prompt(&too_long).is_err());
+                database.create_prompt(&Prompt::new("b".to_string(), "B".to_string()))?;
+                Ok(false)
+            })
+            .unwrap();
+        assert!(!committed);
+        assert!(!database.prompt_exists("a").unwrap());
+
+        database
+            .transaction(|database| {
+                database.create_prompt(&Prompt::new("a".to_string(), "A".to_string()))?;
+                assert!(database.create_prompt(&too_long).is_err());
+                database.create_prompt(&Prompt::new("b".to_string(), "B".to_string()))?;
+                Ok(true)
+            })
+            .unwrap();
+        assert!(database.prompt_exists("a").unwrap() && database.prompt_exists("b").unwrap());
+        assert!(!database.prompt_exists("big").unwrap());
+    }
+
+    #[test]
+    fn test_imported_prompts_match_by_id_before_name() {
+        let mut database = Database::open_in_memory().unwrap();
+        let original = Prompt::new("a".to_string(), "A".to_string());
+        database.create_prompt(&original).unwrap();
+        database.create_prompt(&Prompt::new("b".to_string(), "B".to_string())).unwrap();
+
+        let renamed = Prompt { name: "b".to_string(), ..original.clone() };
+        assert_eq!(database.matching_prompt(&renamed).unwrap().unwrap().name, "a");
+        let other = Prompt::new("b".to_string(), "B2".to_string());
+        assert_eq!(database.matching_prompt(&other).unwrap().unwrap().content, "B");
+        assert!(database.matching_prompt(&Prompt::new("c".to_string(), "C".to_string())).unwrap().is_none());
+
+        database.update_prompt(&Prompt { name: "a2".to_string(), ..original }).unwrap();
+        assert!(database.prompt_exists("a2").unwrap() && !database.prompt_exists("a").unwrap());
+    }
+
+    #[test]
+    fn test_encrypted_content_is_sealed_and_searchable_by_name_only() {
+        let mut database = Database::open_in_memory().unwrap();
+        let client = models::Variable { default_value: Some("Acme".to_string()), ..models::Variable::new("client".to_string()) };
+        let prompt = Prompt::new("client-brief".to_string(), "Write to {{client}} about invoices".to_string())
+            .with_variables(vec![client]);
+        database.create_prompt(&prompt).unwrap();
+
+        assert_eq!(database.enable_encryption("correct horse").unwrap(), 1);
+        let stored: Vec<u8> = database
+            .conn
+            .query_row("SELECT content FROM prompts", [], |row| row.get(0))
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&stored).contains("invoices"));
+        assert!(database.search_prompts("invoices", 10, false).unwrap().is_empty());
+        assert_eq!(database.search_prompts("client", 10, false).unwrap().len(), 1);
+
+        let read = database.get_prompt("client-brief").unwrap();
+        assert_eq!(read.content, prompt.content);
+        assert_eq!(read.variables[0].default_value.as_deref(), Some("Acme"));
+
+        database.disable_encryption().unwrap();
+        assert!(!database.is_encrypted().unwrap());
+        assert_eq!(database.search_prompts("invoices", 10, false).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_flags_and_last_render_are_listed() {
+        let mut database = Database::open_in_memory().unwrap();
+        let review = Prompt::new("review".to_string(), "Review".to_string());
+        database.create_prompt(&review).unwrap();
+        database.create_prompt(&Prompt::new("summary".to_string(), "Summarize".to_string())).unwrap();
+
+        database.set_pinned("review", true).unwrap();
+        database.set_favorite("review", true).unwrap();
+        database.set_favorite("review", false).unwrap();
+        assert!(matches!(database.set_pinned("missing", true), Err(crate::error::PromptedsError::PromptNotFound { .. })));
+
+        let event = |rendered| UsageEvent { prompt_id: review.id, rendered, copied: false, variables: Vec::new() };
+        database.record_usage(&event(false)).unwrap();
+        let listed = database.list_prompts(None, None).unwrap();
+        let review_summary = listed.iter().find(|p| p.name == "review").unwrap();
+        assert!(review_summary.pinned && !review_summary.favorite);
+        // Printing the raw template is not a render, so it does not make the prompt recent
+        assert!(listed.iter().all(|p| p.last_used_at.is_none()));
+
+        database.record_usage(&event(true)).unwrap();
+        let listed = database.list_prompts(None, None).unwrap();
+        assert!(listed.iter().find(|p| p.name == "review").unwrap().last_used_at.is_some());
+        assert!(listed.iter().find(|p| p.name == "summary").unwrap().last_used_at.is_none());
+    }
+
+    #[test]
+    fn test_prompts_matching_query_and_all_tags() {
+        let mut database = Database::open_in_memory().unwrap();
+        let tagged = |name: &str, content: &str, tags: &[&str]| {
+            Prompt::new(name.to_string(), content.to_string()).with_tags(tags.iter().map(|t| t.to_string()).collect())
+        };
+        database.create_prompt(&tagged("review", "Review this code", &["code", "review"])).unwrap();
+        database.create_prompt(&tagged("lint", "Lint this code", &["code"])).unwrap();
+        database.create_prompt(&tagged("essay", "Review this essay", &["writing"])).unwrap();
+
+        let names = |query: Option<&str>, tags: &[&str]| {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            let mut names: Vec<String> = database
+                .list_prompts_matching(query, &tags, None)
+                .unwrap()
+                .into_iter()
+                .map(|p| p.name)
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(None, &[]), ["essay", "lint", "review"]);
+        assert_eq!(names(Some("review"), &[]), ["essay", "review"]);
+        assert_eq!(names(None, &["code"]), ["lint", "review"]);
+        assert_eq!(names(Some("review"), &["code"]), ["review"]);
+        assert_eq!(names(None, &["code", "writing"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_fuzzy_search_and_suggestions_forgive_typos() {
+        let mut database = Database::open_in_memory().unwrap();
+        for name in ["code-review", "commit-message", "summary"] {
+            database.create_prompt(&Prompt::new(name.to_string(), "Some text".to_string())).unwrap();
+        }
+
+        let results = database.search_prompts_fuzzy("cod-reveiw", 5).unwrap();
+        assert_eq!(results.first().map(|r| r.prompt.name.as_str()), Some("code-review"));
+
+        let error = database.get_prompt("code-reveiw").unwrap_err();
+        assert!(matches!(
+            &error,
+            crate::error::PromptedsError::PromptNotFound { suggestions, .. } if suggestions.first().map(String::as_str) == Some("code-review")
+        ));
+        assert!(error.to_string().ends_with("Did you mean: code-review?"), "{}", error);
+        assert!(matches!(database.resolve_prompt_name("zzz"), Err(crate::error::PromptedsError::PromptNotFound { .. })));
+    }
+
+    #[test]
+    fn test_failed_replace_keeps_the_old_prompt() {
+        let mut database = Database::open_in_memory().unwrap();
+        database.create_prompt(&Prompt::new("review".to_string(), "Review".to_string())).unwrap();
+
+        let too_long = Prompt::new("review".to_string(), "x".repeat(100_001));
+        assert!(database.replace_prompt("review", &too_long).is_err());
+        assert_eq!(database.get_prompt("review").unwrap().content, "Review");
+
+        let replacement = Prompt::new("review".to_string(), "Review again".to_string());
+        database.replace_prompt("review", &replacement).unwrap();
+        assert_eq!(database.get_prompt("review").unwrap().id, replacement.id);
+    }
+
+    #[test]
+    fn test_connection_options_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ConnectionOptions { busy_timeout: Duration::from_millis(1234), wal_mode: false, backups: None };
+        let database = Database::open(dir.path().join("prompts.db"), &options).unwrap();
+        let timeout: i64 = database.conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0)).unwrap();
+        let mode: String = database.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!((timeout, mode.as_str()), (1234, "delete"));
+
+        let wal = Database::open(dir.path().join("wal.db"), &ConnectionOptions::default()).unwrap();
+        let mode: String = wal.conn.query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+    }
+
+    #[test]
+    fn test_project_prompts_never_run_secret_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.json");
+        let variable = models::Variable {
+            secret: true,
+            source: Some("cmd:touch /tmp/pwned_by_project".to_string()),
+            ..models::Variable::new("token".to_string())
+        };
+        let prompt = Prompt::new("review".to_string(), "Use {{token}}".to_string()).with_variables(vec![variable]);
+        let data = models::ExportData::new(vec![prompt], Vec::new());
+        std::fs::write(&path, interchange::to_string(&data, interchange::DataFormat::Json, true).unwrap()).unwrap();
+
+        let database = Database::open_in_memory().unwrap().with_project_layer(&path).unwrap();
+        let read = database.get_prompt("review").unwrap();
+        assert!(read.variables[0].secret);
+        assert_eq!(read.variables[0].source, None);
+    }
+
+    #[test]
+    fn test_only_the_newest_renders_are_kept() {
+        let mut database = Database::open_in_memory().unwrap().with_render_history(2);
+        let prompt = Prompt::new("note".to_string(), "text".to_string());
+        database.create_prompt(&prompt).unwrap();
+        let id = prompt.id;
+        for text in ["one", "two", "three"] {
+            database.record_render(&id, text).unwrap();
+        }
+
+        assert_eq!(database.recent_render(1).unwrap().as_deref(), Some("three"));
+        assert_eq!(database.recent_render(2).unwrap().as_deref(), Some("two"));
+        assert!(database.recent_render(3).unwrap().is_none());
+
+        database.delete_prompt("note").unwrap();
+        assert!(database.recent_render(1).unwrap().is_none());
+
+        let mut off = Database::open_in_memory().unwrap();
+        off.create_prompt(&prompt).unwrap();
+        off.record_render(&id, "one").unwrap();
+        assert!(off.recent_render(1).unwrap().is_none());
+    }
+}
@@ -18,6 +18,14 @@ pub struct Variable {
     pub name: String,
     pub description: Option<String>,
     pub default_value: Option<String>,
+    /// Asked for without echo and never stored as a default, exported or
+    /// shown in verbose output
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret: bool,
+    /// Where a secret's value is read from when rendering: `env:NAME` or
+    /// `cmd:COMMAND`, see `template::SecretSource`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +35,28 @@ pub struct PromptSummary {
     pub tag_count: usize,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub favorite: bool,
+    /// When the prompt was last rendered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Layer the prompt was read from
+    #[serde(default)]
+    pub source: PromptSource,
+}
+
+/// Where a prompt comes from. Project prompts take precedence over library
+/// prompts of the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptSource {
+    /// The user's active library database
+    #[default]
+    Library,
+    /// The `.edisonprompt/` directory of the current project
+    Project,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,13 +64,86 @@ pub struct SearchResult {
     pub prompt: Prompt,
     pub score: f64,
     pub highlighted_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreBreakdown>,
+}
+
+/// Components that make up a search result's score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    /// Weighted BM25 relevance (higher is better)
+    pub text: f64,
+    /// Multiplier from how recently the prompt was updated
+    pub recency: f64,
+    /// Multiplier from how often the prompt has been used
+    pub usage: f64,
+    /// Number of recorded uses
+    pub use_count: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportData {
+    /// Layout of the file, see `interchange::FORMAT_VERSION`
+    pub format_version: u32,
+    /// edisonprompt version that wrote the file
     pub version: String,
     pub exported_at: DateTime<Utc>,
     pub prompts: Vec<Prompt>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub saved_searches: Vec<SavedSearch>,
+}
+
+/// A named query (a "collection") whose results are recomputed every time
+/// it runs, so newly matching prompts show up automatically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    /// FTS5 query; `None` matches every prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Prompts must carry all of these tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Sort field: name, created or updated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One fetch of a prompt. Only variable names are kept, never their values.
+#[derive(Debug, Clone)]
+pub struct UsageEvent {
+    pub prompt_id: Uuid,
+    /// False when the raw template was printed
+    pub rendered: bool,
+    pub copied: bool,
+    pub variables: Vec<String>,
+}
+
+impl ExportData {
+    /// An export of `prompts`; secret variables never carry a default
+    pub fn new(mut prompts: Vec<Prompt>, saved_searches: Vec<SavedSearch>) -> Self {
+        for variable in prompts.iter_mut().flat_map(|p| p.variables.iter_mut()) {
+            if variable.secret {
+                variable.default_value = None;
+            }
+        }
+        Self {
+            format_version: super::interchange::FORMAT_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Utc::now(),
+            prompts,
+            saved_searches,
+        }
+    }
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f64 {
+        self.text * self.recency * self.usage
+    }
 }
 
 impl Prompt {
@@ -74,6 +177,8 @@ impl Variable {
             name,
             description: None,
             default_value: None,
+            secret: false,
+            source: None,
         }
     }
     
@@ -86,4 +191,12 @@ impl Variable {
         self.default_value = Some(default_value);
         self
     }
+    
+    /// Mark the variable secret, dropping any default
+    pub fn with_secret(mut self, source: Option<String>) -> Self {
+        self.secret = true;
+        self.source = source;
+        self.default_value = None;
+        self
+    }
 }
\ No newline at end of file
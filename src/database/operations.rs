@@ -83,7 +83,7 @@ impl<'a> DatabaseOperations<'a> {
                 row.get::<_, String>(3)?,
                 row.get::<_, String>(4)?,
             ))
-        }).map_err(|_| PromptedsError::PromptNotFound { name: name.to_string() })?;
+        }).map_err(|_| PromptedsError::PromptNotFound { name: name.to_string(), suggestions: Vec::new() })?;
 
         let id = Uuid::parse_str(&prompt_data.0)?;
         let created_at = DateTime::parse_from_rfc3339(&prompt_data.3)?.with_timezone(&Utc);
@@ -175,7 +175,7 @@ impl<'a> DatabaseOperations<'a> {
         )?;
 
         if rows_affected == 0 {
-            return Err(PromptedsError::PromptNotFound { name: name.to_string() });
+            return Err(PromptedsError::PromptNotFound { name: name.to_string(), suggestions: Vec::new() });
         }
 
         Ok(())
@@ -224,6 +224,10 @@ impl<'a> DatabaseOperations<'a> {
                 tag_count: row.get::<_, i64>(4)? as usize,
                 created_at,
                 updated_at,
+                pinned: false,
+                favorite: false,
+                last_used_at: None,
+                source: Default::default(),
             })
         })?;
 
@@ -1,12 +1,11 @@
 use crate::error::Result;
 use rusqlite::Connection;
 
-pub const SCHEMA_VERSION: i32 = 1;
+pub const SCHEMA_VERSION: i32 = 8;
 
 pub const INIT_SQL: &str = r#"
 -- Enable required pragmas
 PRAGMA foreign_keys = ON;
-PRAGMA journal_mode = WAL;
 PRAGMA synchronous = NORMAL;
 PRAGMA cache_size = 1000;
 PRAGMA temp_store = MEMORY;
@@ -83,8 +82,150 @@ CREATE TABLE IF NOT EXISTS schema_version (
 INSERT OR IGNORE INTO schema_version (version) VALUES (1);
 "#;
 
+/// Incremental migrations applied on top of `INIT_SQL`, in version order.
+/// Each entry must record its own version in `schema_version`.
+pub const MIGRATIONS: &[(i32, &str)] = &[
+    (2, r#"
+-- One row per render or raw fetch; variable names only, never their values
+CREATE TABLE IF NOT EXISTS usage_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    used_at DATETIME NOT NULL,
+    rendered BOOLEAN NOT NULL,
+    copied BOOLEAN NOT NULL,
+    variables TEXT NOT NULL DEFAULT '[]',
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_events_prompt ON usage_events(prompt_id);
+CREATE INDEX IF NOT EXISTS idx_usage_events_used_at ON usage_events(used_at);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (2);
+"#),
+    (3, r#"
+-- Embedding vectors for semantic similarity search
+CREATE TABLE IF NOT EXISTS prompt_embeddings (
+    prompt_id TEXT PRIMARY KEY,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (prompt_id) REFERENCES prompts (id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (3);
+"#),
+    (4, r#"
+-- Saved searches ("collections"), re-run on every use
+CREATE TABLE IF NOT EXISTS saved_searches (
+    name TEXT PRIMARY KEY,
+    query TEXT,
+    tags TEXT NOT NULL DEFAULT '[]',
+    sort TEXT,
+    result_limit INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (4);
+"#),
+    (5, r#"
+-- Personal markers; kept out of prompts so they do not change updated_at
+CREATE TABLE IF NOT EXISTS prompt_flags (
+    prompt_id TEXT PRIMARY KEY,
+    pinned BOOLEAN NOT NULL DEFAULT 0,
+    favorite BOOLEAN NOT NULL DEFAULT 0,
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (5);
+"#),
+    (6, r#"
+-- Key parameters of an encrypted library; content and variable defaults
+-- are then stored as sealed BLOBs
+CREATE TABLE IF NOT EXISTS encryption (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    salt BLOB NOT NULL,
+    memory_kib INTEGER NOT NULL,
+    iterations INTEGER NOT NULL,
+    parallelism INTEGER NOT NULL,
+    check_value BLOB NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The full-text index reads prompts through a view that leaves out sealed
+-- content, so encrypted prompts are found by name only
+DROP TRIGGER IF EXISTS prompts_fts_insert;
+DROP TRIGGER IF EXISTS prompts_fts_delete;
+DROP TRIGGER IF EXISTS prompts_fts_update;
+DROP TABLE IF EXISTS prompts_fts;
+
+CREATE VIEW IF NOT EXISTS prompts_fts_source AS
+    SELECT rowid AS prompt_rowid, name,
+           CASE WHEN typeof(content) = 'blob' THEN '' ELSE content END AS content
+    FROM prompts;
+
+CREATE VIRTUAL TABLE prompts_fts USING fts5(
+    name, content, content='prompts_fts_source', content_rowid='prompt_rowid'
+);
+
+CREATE TRIGGER prompts_fts_insert AFTER INSERT ON prompts BEGIN
+    INSERT INTO prompts_fts(rowid, name, content) VALUES (
+        new.rowid, new.name, CASE WHEN typeof(new.content) = 'blob' THEN '' ELSE new.content END);
+END;
+
+CREATE TRIGGER prompts_fts_delete AFTER DELETE ON prompts BEGIN
+    INSERT INTO prompts_fts(prompts_fts, rowid, name, content) VALUES (
+        'delete', old.rowid, old.name, CASE WHEN typeof(old.content) = 'blob' THEN '' ELSE old.content END);
+END;
+
+CREATE TRIGGER prompts_fts_update AFTER UPDATE ON prompts BEGIN
+    INSERT INTO prompts_fts(prompts_fts, rowid, name, content) VALUES (
+        'delete', old.rowid, old.name, CASE WHEN typeof(old.content) = 'blob' THEN '' ELSE old.content END);
+    INSERT INTO prompts_fts(rowid, name, content) VALUES (
+        new.rowid, new.name, CASE WHEN typeof(new.content) = 'blob' THEN '' ELSE new.content END);
+END;
+
+INSERT INTO prompts_fts(prompts_fts) VALUES ('rebuild');
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (6);
+"#),
+    (7, r#"
+-- Secret variables are resolved when rendering; their values are never stored
+ALTER TABLE variables ADD COLUMN secret BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE variables ADD COLUMN source TEXT;
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (7);
+"#),
+    (8, r#"
+-- Recent renders for `get --paste-last`, sealed like content when the
+-- library is encrypted; only the newest `clipboard.history` are kept
+CREATE TABLE IF NOT EXISTS render_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    prompt_id TEXT NOT NULL,
+    rendered_at DATETIME NOT NULL,
+    content TEXT NOT NULL,
+    FOREIGN KEY (prompt_id) REFERENCES prompts(id) ON DELETE CASCADE
+);
+
+INSERT OR IGNORE INTO schema_version (version) VALUES (8);
+"#),
+];
+
 pub fn initialize_database(conn: &Connection) -> Result<()> {
     conn.execute_batch(INIT_SQL)?;
+    migrate(conn)?;
+    Ok(())
+}
+
+pub fn migrate(conn: &Connection) -> Result<()> {
+    let current_version = get_schema_version(conn)?;
+
+    for (version, sql) in MIGRATIONS {
+        if *version > current_version {
+            conn.execute_batch(&format!("BEGIN;\n{}\nCOMMIT;", sql))?;
+        }
+    }
+
     Ok(())
 }
 
@@ -100,4 +241,24 @@ pub fn get_schema_version(conn: &Connection) -> Result<i32> {
 pub fn needs_migration(conn: &Connection) -> Result<bool> {
     let current_version = get_schema_version(conn)?;
     Ok(current_version < SCHEMA_VERSION)
-}
\ No newline at end of file
+}
+
+/// Problems found by SQLite's `PRAGMA integrity_check`, including the
+/// full-text index; empty when the database is intact
+pub fn integrity_problems(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let problems = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(problems.into_iter().filter(|p| p != "ok").collect())
+}
+
+/// Number of prompts, and of documents in the full-text index, which
+/// differ when the index is out of sync
+pub fn search_index_counts(conn: &Connection) -> Result<(usize, usize)> {
+    let (prompts, indexed): (i64, i64) = conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM prompts), (SELECT COUNT(*) FROM prompts_fts_docsize)",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    Ok((prompts as usize, indexed as usize))
+}
@@ -1,73 +1,269 @@
-use crate::database::models::{Prompt, SearchResult};
-use crate::error::Result;
+use crate::config::SearchConfig;
+use crate::database::crypto::{self, Stored, Vault};
+use crate::database::models::{Prompt, ScoreBreakdown, SearchResult};
+use crate::error::{PromptedsError, Result};
 use rusqlite::{params, Connection};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+/// Minimum trigram similarity for a fuzzy match or a name suggestion
+pub const FUZZY_THRESHOLD: f64 = 0.3;
+
+/// Split text into lowercase trigrams, padding each word so that short
+/// words and word boundaries still contribute (same scheme as pg_trgm).
+pub fn trigrams(text: &str) -> HashSet<String> {
+    let mut grams = HashSet::new();
+
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let padded: Vec<char> = format!("  {} ", word.to_lowercase()).chars().collect();
+        for window in padded.windows(3) {
+            grams.insert(window.iter().collect());
+        }
+    }
+
+    grams
+}
+
+/// Jaccard similarity of the trigram sets of `a` and `b`, in `0.0..=1.0`
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    jaccard(&trigrams(a), &trigrams(b))
+}
+
+/// Similarity derived from the optimal string alignment distance, in
+/// `0.0..=1.0`. Catches short transpositions ("emial") that trigrams miss.
+pub fn edit_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+
+    1.0 - rows[a.len()][b.len()] as f64 / longest as f64
+}
+
+/// Score how well `query` matches a prompt name: trigram overlap, edit
+/// similarity, and edit similarity against a same-length prefix of the name
+/// so that "emial" still finds "email-template".
+fn name_similarity(query: &str, query_grams: &HashSet<String>, name: &str) -> f64 {
+    let mut score = jaccard(query_grams, &trigrams(name)).max(edit_similarity(query, name));
+
+    let query_len = query.chars().count();
+    if query_len >= 3 {
+        let prefix: String = name.chars().take(query_len).collect();
+        score = score.max(edit_similarity(query, &prefix) * 0.9);
+    }
+
+    score
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Best similarity between the query and any run of consecutive words in
+/// `content` that is as long as the query itself.
+fn best_window_similarity(query_grams: &HashSet<String>, query_words: usize, content: &str) -> f64 {
+    let words: Vec<&str> = content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    words
+        .windows(query_words.max(1).min(words.len().max(1)))
+        .map(|window| jaccard(query_grams, &trigrams(&window.join(" "))))
+        .fold(0.0, f64::max)
+}
+
+/// Weights and boosts applied when ordering full-text search results
+#[derive(Debug, Clone)]
+pub struct Ranking {
+    /// BM25 weight for the `name` column
+    pub name_weight: f64,
+    /// BM25 weight for the `content` column
+    pub content_weight: f64,
+    /// Half-life of the recency boost in days; `0` disables it
+    pub recency_half_life_days: f64,
+    /// Strength of the usage boost; `0` disables it
+    pub usage_boost: f64,
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Self::from_config(&SearchConfig::default())
+    }
+}
+
+impl Ranking {
+    /// The ranking set in `[search]`
+    pub fn from_config(search: &SearchConfig) -> Self {
+        // "rank" is FTS5's default ordering: bm25 with every column weighted equally
+        let (name_weight, content_weight) = if search.ranking == "rank" {
+            (1.0, 1.0)
+        } else {
+            (search.name_weight, search.content_weight)
+        };
+        Self {
+            name_weight,
+            content_weight,
+            recency_half_life_days: search.recency_half_life_days,
+            usage_boost: search.usage_boost,
+        }
+    }
+
+    pub fn has_boosts(&self) -> bool {
+        self.recency_half_life_days > 0.0 || self.usage_boost > 0.0
+    }
+
+    /// Combine a raw `bm25()` value (lower is better) with the configured
+    /// recency and usage boosts.
+    pub fn breakdown(
+        &self,
+        bm25: f64,
+        updated_at: DateTime<Utc>,
+        use_count: u64,
+        now: DateTime<Utc>,
+    ) -> ScoreBreakdown {
+        let recency = if self.recency_half_life_days > 0.0 {
+            let age_days = (now - updated_at).num_seconds().max(0) as f64 / 86_400.0;
+            1.0 + 0.5f64.powf(age_days / self.recency_half_life_days)
+        } else {
+            1.0
+        };
+
+        let usage = if self.usage_boost > 0.0 {
+            1.0 + self.usage_boost * (use_count as f64).ln_1p()
+        } else {
+            1.0
+        };
+
+        ScoreBreakdown {
+            text: -bm25,
+            recency,
+            usage,
+            use_count,
+        }
+    }
+}
 
 pub struct SearchEngine<'a> {
     conn: &'a Connection,
+    vault: Option<&'a Vault>,
 }
 
 impl<'a> SearchEngine<'a> {
     pub fn new(conn: &'a Connection) -> Self {
-        Self { conn }
+        Self { conn, vault: None }
     }
 
-    pub fn search(&self, query: &str, limit: usize, highlight: bool) -> Result<Vec<SearchResult>> {
-        let sql = if highlight {
-            "SELECT p.id, p.name, p.content, p.created_at, p.updated_at, 
-                    fts.rank,
-                    highlight(prompts_fts, 0, '<mark>', '</mark>') as highlighted_name,
-                    highlight(prompts_fts, 1, '<mark>', '</mark>') as highlighted_content
-             FROM prompts_fts fts
-             INNER JOIN prompts p ON p.rowid = fts.rowid
-             WHERE prompts_fts MATCH ?1
-             ORDER BY rank
-             LIMIT ?2"
+    /// Decrypt the content of matches in an encrypted library with `vault`
+    pub fn with_vault(mut self, vault: &'a Vault) -> Self {
+        self.vault = Some(vault);
+        self
+    }
+
+    pub fn search(&self, query: &str, limit: usize, highlight: bool, ranking: &Ranking) -> Result<Vec<SearchResult>> {
+        let highlight_columns = if highlight {
+            "highlight(prompts_fts, 0, '<mark>', '</mark>') as highlighted_name,
+             highlight(prompts_fts, 1, '<mark>', '</mark>') as highlighted_content"
         } else {
-            "SELECT p.id, p.name, p.content, p.created_at, p.updated_at, 
-                    fts.rank, '' as highlighted_name, '' as highlighted_content
+            "'' as highlighted_name, '' as highlighted_content"
+        };
+
+        let sql = format!(
+            "SELECT p.id, p.name, p.content, p.created_at, p.updated_at,
+                    bm25(prompts_fts, ?3, ?4) as text_rank,
+                    {},
+                    (SELECT COUNT(*) FROM usage_events e WHERE e.prompt_id = p.id) as use_count
              FROM prompts_fts fts
              INNER JOIN prompts p ON p.rowid = fts.rowid
              WHERE prompts_fts MATCH ?1
-             ORDER BY rank
-             LIMIT ?2"
-        };
+             ORDER BY text_rank
+             LIMIT ?2",
+            highlight_columns
+        );
+
+        // Boosts can reorder results, so every match has to be scored before truncating
+        let sql_limit = if ranking.has_boosts() { -1 } else { limit as i64 };
 
-        let mut stmt = self.conn.prepare(sql)?;
-        let rows = stmt.query_map(params![query, limit as i64], |row| {
-            let id = Uuid::parse_str(&row.get::<_, String>(0)?).unwrap();
-            let created_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                .unwrap().with_timezone(&Utc);
-            let updated_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                .unwrap().with_timezone(&Utc);
-            
-            let highlighted_content = if highlight {
-                let highlighted: String = row.get(7)?;
-                if !highlighted.is_empty() {
-                    Some(highlighted)
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            params![query, sql_limit, ranking.name_weight, ranking.content_weight],
+            |row| {
+                let id = Uuid::parse_str(&row.get::<_, String>(0)?).unwrap();
+                let created_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
+                    .unwrap().with_timezone(&Utc);
+                let updated_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
+                    .unwrap().with_timezone(&Utc);
+
+                let highlighted_content = if highlight {
+                    let highlighted: String = row.get(7)?;
+                    if !highlighted.is_empty() {
+                        Some(highlighted)
+                    } else {
+                        None
+                    }
                 } else {
                     None
-                }
-            } else {
-                None
-            };
+                };
 
-            Ok((
-                id,
-                row.get::<_, String>(1)?, // name
-                row.get::<_, String>(2)?, // content
-                created_at,
-                updated_at,
-                row.get::<_, f64>(5)?, // rank
-                highlighted_content,
-            ))
-        })?;
+                Ok((
+                    id,
+                    row.get::<_, String>(1)?, // name
+                    row.get::<_, Stored>(2)?, // content
+                    created_at,
+                    updated_at,
+                    row.get::<_, f64>(5)?, // bm25
+                    highlighted_content,
+                    row.get::<_, i64>(8)?, // use count
+                ))
+            },
+        )?;
 
-        let mut results = Vec::new();
+        let now = Utc::now();
+        let mut scored = Vec::new();
         for row in rows {
             let data = row?;
-            
+            let breakdown = ranking.breakdown(data.5, data.4, data.7.max(0) as u64, now);
+            scored.push((data, breakdown));
+        }
+
+        scored.sort_by(|a, b| b.1.total().total_cmp(&a.1.total()));
+        scored.truncate(limit);
+
+        let mut results = Vec::new();
+        for (data, breakdown) in scored {
             // Get variables and tags for the full prompt
             let variables = self.get_prompt_variables(&data.0)?;
             let tags = self.get_prompt_tags(&data.0)?;
@@ -75,7 +271,7 @@ impl<'a> SearchEngine<'a> {
             let prompt = Prompt {
                 id: data.0,
                 name: data.1,
-                content: data.2,
+                content: self.reveal(data.2, &crypto::content_context(&data.0))?,
                 variables,
                 tags,
                 created_at: data.3,
@@ -84,30 +280,148 @@ impl<'a> SearchEngine<'a> {
 
             results.push(SearchResult {
                 prompt,
-                score: data.5,
+                score: breakdown.total(),
                 highlighted_content: data.6,
+                explanation: Some(breakdown),
             });
         }
 
         Ok(results)
     }
 
+    /// Typo-tolerant search scoring prompts by trigram similarity against
+    /// their name and against word windows of their content.
+    pub fn fuzzy_search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let query_grams = trigrams(query);
+        let query_words = query.split_whitespace().count();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, content, created_at, updated_at FROM prompts"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Stored>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut scored = Vec::new();
+        for row in rows {
+            let data = row?;
+            let name_score = name_similarity(query, &query_grams, &data.1);
+            // Content matches count slightly less than a name match of equal
+            // quality; encrypted content is not searched
+            let content_score = match &data.2 {
+                Stored::Text(content) => best_window_similarity(&query_grams, query_words, content) * 0.9,
+                Stored::Sealed(_) => 0.0,
+            };
+            let score = name_score.max(content_score);
+
+            if score >= FUZZY_THRESHOLD {
+                scored.push((data, score));
+            }
+        }
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(limit);
+
+        let mut results = Vec::new();
+        for (data, score) in scored {
+            let id = Uuid::parse_str(&data.0)?;
+            let prompt = Prompt {
+                id,
+                name: data.1,
+                content: self.reveal(data.2, &crypto::content_context(&id))?,
+                variables: self.get_prompt_variables(&id)?,
+                tags: self.get_prompt_tags(&id)?,
+                created_at: DateTime::parse_from_rfc3339(&data.3)?.with_timezone(&Utc),
+                updated_at: DateTime::parse_from_rfc3339(&data.4)?.with_timezone(&Utc),
+            };
+
+            results.push(SearchResult {
+                prompt,
+                score,
+                highlighted_content: None,
+                explanation: None,
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Prompt names that look like `name`, best match first
+    pub fn suggest_names(&self, name: &str, limit: usize) -> Result<Vec<String>> {
+        let name_grams = trigrams(name);
+
+        let mut scored: Vec<(String, f64)> = self
+            .all_names()?
+            .into_iter()
+            .filter_map(|candidate| {
+                let score = name_similarity(name, &name_grams, &candidate);
+                (score >= FUZZY_THRESHOLD).then_some((candidate, score))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(scored.into_iter().take(limit).map(|(name, _)| name).collect())
+    }
+
+    /// Prompt names starting with `prefix`, sorted alphabetically
+    pub fn names_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut names: Vec<String> = self
+            .all_names()?
+            .into_iter()
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn all_names(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT name FROM prompts")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut names = Vec::new();
+        for row in rows {
+            names.push(row?);
+        }
+
+        Ok(names)
+    }
+
+    fn reveal(&self, stored: Stored, context: &str) -> Result<String> {
+        match (self.vault, stored) {
+            (Some(vault), stored) => vault.reveal(self.conn, stored, context),
+            (None, Stored::Text(text)) => Ok(text),
+            (None, Stored::Sealed(_)) => Err(PromptedsError::LibraryLocked),
+        }
+    }
+
     fn get_prompt_variables(&self, prompt_id: &Uuid) -> Result<Vec<crate::database::models::Variable>> {
         let mut stmt = self.conn.prepare(
-            "SELECT name, description, default_value FROM variables WHERE prompt_id = ?1"
+            "SELECT name, description, default_value, secret, source FROM variables WHERE prompt_id = ?1"
         )?;
 
         let rows = stmt.query_map(params![prompt_id.to_string()], |row| {
-            Ok(crate::database::models::Variable {
-                name: row.get(0)?,
-                description: row.get(1)?,
-                default_value: row.get(2)?,
-            })
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<Stored>>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
         })?;
 
         let mut variables = Vec::new();
         for row in rows {
-            variables.push(row?);
+            let (name, description, default_value, secret, source) = row?;
+            let default_value = default_value
+                .map(|stored| self.reveal(stored, &crypto::default_context(prompt_id, &name)))
+                .transpose()?;
+            variables.push(crate::database::models::Variable { name, description, default_value, secret, source });
         }
 
         Ok(variables)
@@ -121,7 +435,7 @@ impl<'a> SearchEngine<'a> {
         )?;
 
         let rows = stmt.query_map(params![prompt_id.to_string()], |row| {
-            Ok(row.get::<_, String>(0)?)
+            row.get::<_, String>(0)
         })?;
 
         let mut tags = Vec::new();
@@ -131,4 +445,75 @@ impl<'a> SearchEngine<'a> {
 
         Ok(tags)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigram_similarity() {
+        assert_eq!(trigram_similarity("code-review", "code-review"), 1.0);
+        assert!(trigram_similarity("code-reveiw", "code-review") >= FUZZY_THRESHOLD);
+        assert!(trigram_similarity("email", "code-review") < FUZZY_THRESHOLD);
+        assert_eq!(trigram_similarity("", "anything"), 0.0);
+    }
+
+    #[test]
+    fn test_name_similarity_handles_transposed_prefix() {
+        let query = "emial";
+        assert!(name_similarity(query, &trigrams(query), "email-template") >= FUZZY_THRESHOLD);
+        assert!(name_similarity(query, &trigrams(query), "code-review") < FUZZY_THRESHOLD);
+        assert_eq!(edit_similarity("abc", "abc"), 1.0);
+        assert_eq!(edit_similarity("ab", "ba"), 0.5);
+    }
+
+    #[test]
+    fn test_ranking_boosts() {
+        let now = Utc::now();
+        let plain = Ranking::default();
+        assert!(!plain.has_boosts());
+        assert_eq!(plain.breakdown(-2.0, now, 10, now).total(), 2.0);
+
+        let boosted = Ranking {
+            recency_half_life_days: 30.0,
+            usage_boost: 1.0,
+            ..Ranking::default()
+        };
+        let fresh = boosted.breakdown(-2.0, now, 0, now);
+        let stale = boosted.breakdown(-2.0, now - chrono::Duration::days(30), 0, now);
+        assert_eq!(fresh.recency, 2.0);
+        assert_eq!(stale.recency, 1.5);
+
+        let used = boosted.breakdown(-2.0, now, 20, now);
+        assert!(used.total() > fresh.total());
+    }
+
+    #[test]
+    fn test_ranking_from_config() {
+        let plain = Ranking::default();
+        assert_eq!((plain.name_weight, plain.content_weight), (10.0, 1.0));
+
+        let config = SearchConfig { ranking: "rank".to_string(), usage_boost: 0.5, ..SearchConfig::default() };
+        let ranking = Ranking::from_config(&config);
+        assert_eq!((ranking.name_weight, ranking.content_weight), (1.0, 1.0));
+        assert_eq!(ranking.usage_boost, 0.5);
+    }
+
+    #[test]
+    fn test_trigrams_are_case_insensitive() {
+        assert_eq!(trigrams("Email"), trigrams("email"));
+    }
+
+    #[test]
+    fn test_window_similarity_tolerates_typos() {
+        let query = "pull reqest";
+        let score = best_window_similarity(
+            &trigrams(query),
+            2,
+            "Please summarize the pull request below in three bullets",
+        );
+        assert!(score >= FUZZY_THRESHOLD);
+        assert_eq!(best_window_similarity(&trigrams(query), 2, "pull reqest"), 1.0);
+    }
+}
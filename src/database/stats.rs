@@ -0,0 +1,170 @@
+use crate::error::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageStats {
+    /// Start of the reporting window
+    pub since: DateTime<Utc>,
+    pub library: LibraryStats,
+    /// Uses within the window
+    pub total_uses: u64,
+    pub copies: u64,
+    /// Most used prompts within the window
+    pub top: Vec<PromptUsage>,
+    /// Prompts that have never been used, oldest first
+    pub unused: Vec<String>,
+    pub unused_count: usize,
+    /// One entry per day of the window, including days without use
+    pub daily: Vec<DailyUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryStats {
+    pub prompts: usize,
+    pub tags: usize,
+    pub variables: usize,
+    pub database_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptUsage {
+    pub name: String,
+    pub uses: u64,
+    pub copies: u64,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub uses: u64,
+}
+
+/// Summarize usage over the last `days` days, listing at most `limit`
+/// prompts in the top and unused lists
+pub fn collect(conn: &Connection, days: u32, limit: usize) -> Result<UsageStats> {
+    let now = Utc::now();
+    let since = now - Duration::days(days.saturating_sub(1) as i64);
+    let since = since.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let since_str = since.to_rfc3339();
+
+    let (total_uses, copies): (u64, u64) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(copied), 0) FROM usage_events WHERE used_at >= ?1",
+        [&since_str],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT p.name, COUNT(*) AS uses, COALESCE(SUM(e.copied), 0), MAX(e.used_at)
+         FROM usage_events e
+         JOIN prompts p ON p.id = e.prompt_id
+         WHERE e.used_at >= ?1
+         GROUP BY e.prompt_id
+         ORDER BY uses DESC, p.name
+         LIMIT ?2",
+    )?;
+    let top = stmt
+        .query_map(rusqlite::params![since_str, limit as i64], |row| {
+            let last_used: Option<String> = row.get(3)?;
+            Ok(PromptUsage {
+                name: row.get(0)?,
+                uses: row.get(1)?,
+                copies: row.get(2)?,
+                last_used_at: last_used
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|d| d.with_timezone(&Utc)),
+            })
+        })?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT p.name FROM prompts p
+         WHERE NOT EXISTS (SELECT 1 FROM usage_events e WHERE e.prompt_id = p.id)
+         ORDER BY p.created_at, p.name",
+    )?;
+    let unused = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let unused_count = unused.len();
+
+    let mut stmt = conn.prepare(
+        "SELECT substr(used_at, 1, 10), COUNT(*) FROM usage_events
+         WHERE used_at >= ?1
+         GROUP BY substr(used_at, 1, 10)",
+    )?;
+    let counts: HashMap<String, u64> = stmt
+        .query_map([&since_str], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<std::result::Result<_, _>>()?;
+    let daily = since
+        .date_naive()
+        .iter_days()
+        .take_while(|date| *date <= now.date_naive())
+        .map(|date| DailyUsage {
+            date,
+            uses: counts.get(&date.to_string()).copied().unwrap_or(0),
+        })
+        .collect();
+
+    Ok(UsageStats {
+        since,
+        library: library_stats(conn)?,
+        total_uses,
+        copies,
+        top,
+        unused: unused.into_iter().take(limit).collect(),
+        unused_count,
+        daily,
+    })
+}
+
+fn library_stats(conn: &Connection) -> Result<LibraryStats> {
+    let count = |sql: &str| -> Result<usize> {
+        let n: i64 = conn.query_row(sql, [], |row| row.get(0))?;
+        Ok(n as usize)
+    };
+
+    let page_count = count("PRAGMA page_count")? as u64;
+    let page_size = count("PRAGMA page_size")? as u64;
+
+    Ok(LibraryStats {
+        prompts: count("SELECT COUNT(*) FROM prompts")?,
+        tags: count("SELECT COUNT(DISTINCT tag_id) FROM prompt_tags")?,
+        variables: count("SELECT COUNT(*) FROM variables")?,
+        database_bytes: page_count * page_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema;
+
+    #[test]
+    fn test_collect_counts_uses_and_unused() {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::initialize_database(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO prompts (id, name, content) VALUES ('a', 'used', 'x'), ('b', 'idle', 'y');",
+        )
+        .unwrap();
+        let now = Utc::now().to_rfc3339();
+        for copied in [true, false] {
+            conn.execute(
+                "INSERT INTO usage_events (prompt_id, used_at, rendered, copied) VALUES ('a', ?1, 1, ?2)",
+                rusqlite::params![now, copied],
+            )
+            .unwrap();
+        }
+
+        let stats = collect(&conn, 7, 10).unwrap();
+        assert_eq!(stats.library.prompts, 2);
+        assert_eq!((stats.total_uses, stats.copies), (2, 1));
+        assert_eq!(stats.top[0].name, "used");
+        assert_eq!(stats.unused, vec!["idle"]);
+        assert_eq!(stats.daily.len(), 7);
+        assert_eq!(stats.daily.last().unwrap().uses, 2);
+    }
+}
@@ -0,0 +1,427 @@
+//! Git-backed sync of a library. Prompts are written one file per prompt,
+//! `prompts/<id>.json`, to a git working copy; pulling merges the remote's
+//! prompts with the local ones by id, with a three-way merge against the
+//! last commit both sides share.
+
+use crate::error::{PromptedsError, Result};
+use crate::template::TemplateEngine;
+use crate::utils;
+use super::models::{Prompt, Variable};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use uuid::Uuid;
+
+/// Branch the library is kept on, locally and on the remote
+pub const BRANCH: &str = "main";
+const REMOTE: &str = "origin";
+const PROMPTS_DIR: &str = "prompts";
+
+/// Prompts by id
+pub type Prompts = BTreeMap<Uuid, Prompt>;
+
+/// A git working copy holding one library
+pub struct Repo {
+    dir: PathBuf,
+}
+
+/// Result of merging the remote's prompts with the local ones
+pub struct Merge {
+    pub prompts: Prompts,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A prompt whose changes could not be merged, and how it was settled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    pub resolution: String,
+}
+
+impl Repo {
+    /// The working copy in `dir`, which `init` must have set up
+    pub fn open(dir: &Path) -> Result<Self> {
+        if !dir.join(".git").exists() {
+            return Err(sync_error("sync is not set up for this library; run 'sync init <remote>' first".to_string()));
+        }
+        Ok(Self { dir: dir.to_path_buf() })
+    }
+
+    /// Create a working copy in `dir` tracking `remote`, a git URL or the
+    /// path of a repository. Nothing is checked out, so the first pull
+    /// merges the remote with the library rather than replacing it.
+    pub fn init(dir: &Path, remote: &str) -> Result<Self> {
+        if dir.join(".git").exists() {
+            let repo = Self { dir: dir.to_path_buf() };
+            let current = repo.git(&["remote", "get-url", REMOTE])?;
+            return Err(sync_error(format!("sync is already set up with {}", current.trim())));
+        }
+        fs::create_dir_all(dir)?;
+        let repo = Self { dir: dir.to_path_buf() };
+
+        // Local paths are resolved now, as git runs inside the working copy
+        let remote = match Path::new(remote).canonicalize() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => remote.to_string(),
+        };
+        repo.git(&["init", "--quiet"])?;
+        repo.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+        repo.git(&["remote", "add", REMOTE, &remote])?;
+        // Commits need an author even where git has not been set up
+        if repo.git(&["config", "user.email"]).is_err() {
+            repo.git(&["config", "user.name", "edisonprompt"])?;
+            repo.git(&["config", "user.email", "edisonprompt@localhost"])?;
+        }
+        Ok(repo)
+    }
+
+    pub fn remote_url(&self) -> Result<String> {
+        Ok(self.git(&["remote", "get-url", REMOTE])?.trim().to_string())
+    }
+
+    /// Fetch the remote branch; its commit, or `None` if it has none yet
+    pub fn fetch(&self) -> Result<Option<String>> {
+        self.git(&["fetch", "--quiet", REMOTE])?;
+        self.commit_of(&format!("refs/remotes/{}/{}", REMOTE, BRANCH))
+    }
+
+    /// Current commit, or `None` before the first one
+    pub fn head(&self) -> Result<Option<String>> {
+        self.commit_of("HEAD")
+    }
+
+    fn commit_of(&self, rev: &str) -> Result<Option<String>> {
+        match self.git(&["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)]) {
+            Ok(commit) => Ok(Some(commit.trim().to_string())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Whether `ancestor` is `commit` or one of its ancestors
+    pub fn is_ancestor(&self, ancestor: &str, commit: &str) -> Result<bool> {
+        Ok(self.git(&["merge-base", "--is-ancestor", ancestor, commit]).is_ok())
+    }
+
+    /// Last commit shared by `a` and `b`, if their histories meet
+    pub fn merge_base(&self, a: &str, b: &str) -> Result<Option<String>> {
+        match self.git(&["merge-base", a, b]) {
+            Ok(commit) => Ok(Some(commit.trim().to_string())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Replace the prompt files in the working copy with `prompts`
+    pub fn write(&self, prompts: &Prompts) -> Result<()> {
+        let dir = self.dir.join(PROMPTS_DIR);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(&dir)?;
+        for (id, prompt) in prompts {
+            let text = serde_json::to_string_pretty(prompt)?;
+            fs::write(dir.join(format!("{}.json", id)), format!("{}\n", text))?;
+        }
+        Ok(())
+    }
+
+    /// Commit everything in the working copy; false if nothing changed
+    pub fn commit(&self, message: &str) -> Result<bool> {
+        self.git(&["add", "--all"])?;
+        let merging = self.commit_of("MERGE_HEAD")?.is_some();
+        if !merging && self.git(&["status", "--porcelain"])?.trim().is_empty() {
+            return Ok(false);
+        }
+        self.git(&["commit", "--quiet", "--message", message])?;
+        Ok(true)
+    }
+
+    /// Prompts as of `rev`
+    pub fn read(&self, rev: &str) -> Result<Prompts> {
+        let mut prompts = Prompts::new();
+        let files = self.git(&["ls-tree", "-r", "--name-only", rev, "--", PROMPTS_DIR])?;
+        for file in files.lines().filter(|f| f.ends_with(".json")) {
+            let text = self.git(&["show", &format!("{}:{}", rev, file)])?;
+            let prompt: Prompt = serde_json::from_str(&text)
+                .map_err(|e| sync_error(format!("{} at {}: {}", file, &rev[..rev.len().min(12)], e)))?;
+            prompts.insert(prompt.id, prompt);
+        }
+        Ok(prompts)
+    }
+
+    /// Move to `commit` when the local history is part of it
+    pub fn fast_forward(&self, commit: &str) -> Result<()> {
+        match self.head()? {
+            Some(_) => self.git(&["merge", "--quiet", "--ff-only", commit])?,
+            None => self.git(&["reset", "--quiet", "--hard", commit])?,
+        };
+        Ok(())
+    }
+
+    /// Start a merge commit with `commit` as second parent, leaving the
+    /// files to be written by the caller
+    pub fn start_merge(&self, commit: &str) -> Result<()> {
+        self.git(&["merge", "--quiet", "--no-commit", "--strategy", "ours", "--allow-unrelated-histories", commit])?;
+        Ok(())
+    }
+
+    pub fn push(&self) -> Result<()> {
+        self.git(&["push", "--quiet", REMOTE, &format!("HEAD:refs/heads/{}", BRANCH)])?;
+        Ok(())
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .map_err(|e| sync_error(format!("cannot run git: {}", e)))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(sync_error(format!("git {} failed: {}", args[0], stderr.trim())));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// `prompts` by id, in the form they are stored in the working copy
+pub fn by_id(prompts: Vec<Prompt>) -> Prompts {
+    prompts
+        .into_iter()
+        .map(|mut prompt| {
+            prompt.tags.sort();
+            (prompt.id, prompt)
+        })
+        .collect()
+}
+
+/// Whether two prompts are stored identically
+pub fn same(a: &Prompt, b: &Prompt) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Merge `ours` and `theirs` by prompt id, against `base`. A prompt
+/// changed on one side takes that change; a prompt changed on both has its
+/// content merged line by line and its tags combined. When both changed the
+/// same lines or the name, the local version stays and the remote one is
+/// added as a copy. Names are kept unique, local prompts keeping theirs.
+pub fn merge(base: &Prompts, ours: &Prompts, theirs: &Prompts) -> Merge {
+    let mut merged = Prompts::new();
+    let mut conflicts = Vec::new();
+    let mut copies = Vec::new();
+
+    let ids: std::collections::BTreeSet<&Uuid> = ours.keys().chain(theirs.keys()).collect();
+    for id in ids {
+        let changed = |side: &Prompt| !base.get(id).is_some_and(|b| same(b, side));
+        match (ours.get(id), theirs.get(id)) {
+            (Some(o), Some(t)) if same(o, t) || !changed(t) => {
+                merged.insert(*id, o.clone());
+            }
+            (Some(o), Some(t)) if !changed(o) => {
+                merged.insert(*id, t.clone());
+            }
+            (Some(o), Some(t)) => match merge_prompt(base.get(id), o, t) {
+                Some(prompt) => {
+                    merged.insert(*id, prompt);
+                }
+                None => {
+                    merged.insert(*id, o.clone());
+                    let copy = Prompt { id: Uuid::new_v4(), name: format!("{}-conflict", t.name), ..t.clone() };
+                    copies.push((o.name.clone(), copy));
+                }
+            },
+            (Some(o), None) if base.contains_key(id) && changed(o) => {
+                conflicts.push(Conflict {
+                    name: o.name.clone(),
+                    resolution: "changed here but deleted on the remote; kept".to_string(),
+                });
+                merged.insert(*id, o.clone());
+            }
+            (None, Some(t)) if base.contains_key(id) && changed(t) => {
+                conflicts.push(Conflict {
+                    name: t.name.clone(),
+                    resolution: "deleted here but changed on the remote; restored".to_string(),
+                });
+                merged.insert(*id, t.clone());
+            }
+            // Added on one side, or deleted on one side and unchanged on the other
+            (Some(o), None) if !base.contains_key(id) => {
+                merged.insert(*id, o.clone());
+            }
+            (None, Some(t)) if !base.contains_key(id) => {
+                merged.insert(*id, t.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // Local prompts keep their names; new ones and those renamed on the
+    // remote get a free one
+    let kept_name = |id: &Uuid, prompt: &Prompt| ours.get(id).is_some_and(|o| o.name == prompt.name);
+    let mut taken: Vec<String> = merged.iter().filter(|(id, p)| kept_name(id, p)).map(|(_, p)| p.name.clone()).collect();
+    let renamed: Vec<Uuid> = merged.iter().filter(|(id, p)| !kept_name(id, p)).map(|(id, _)| *id).collect();
+    for id in renamed {
+        let prompt = merged.get_mut(&id).expect("id comes from the map");
+        let name = free_name(&prompt.name, &taken);
+        if name != prompt.name {
+            conflicts.push(Conflict {
+                name: prompt.name.clone(),
+                resolution: format!("a different prompt here has this name; the remote one is now '{}'", name),
+            });
+            prompt.name = name;
+        }
+        taken.push(prompt.name.clone());
+    }
+    for (local_name, mut copy) in copies {
+        copy.name = free_name(&copy.name, &taken);
+        taken.push(copy.name.clone());
+        conflicts.push(Conflict {
+            name: local_name,
+            resolution: format!("changed on both sides; kept the local version, the remote one is now '{}'", copy.name),
+        });
+        merged.insert(copy.id, copy);
+    }
+
+    Merge { prompts: merged, conflicts }
+}
+
+/// Both sides' changes to one prompt, or `None` if they clash
+fn merge_prompt(base: Option<&Prompt>, ours: &Prompt, theirs: &Prompt) -> Option<Prompt> {
+    let base = base?;
+    let name = pick(&base.name, &ours.name, &theirs.name)?;
+    let (content, conflicts) = utils::merge3(&base.content, &ours.content, &theirs.content, "local", "remote");
+    if conflicts > 0 {
+        return None;
+    }
+
+    // A tag stays unless one side removed it, and additions from both are kept
+    let mut tags: Vec<String> = ours
+        .tags
+        .iter()
+        .chain(&theirs.tags)
+        .filter(|tag| {
+            let kept_by = |side: &Prompt| side.tags.contains(tag) || !base.tags.contains(tag);
+            kept_by(ours) && kept_by(theirs)
+        })
+        .cloned()
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    // Variables follow the merged content; definitions prefer the side
+    // that changed them
+    let definitions = if variables_key(ours) == variables_key(base) { &theirs.variables } else { &ours.variables };
+    let variables = TemplateEngine::new()
+        .extract_variables(&content)
+        .ok()?
+        .into_iter()
+        .map(|name| {
+            definitions
+                .iter()
+                .chain(&ours.variables)
+                .chain(&theirs.variables)
+                .find(|v| v.name == name)
+                .cloned()
+                .unwrap_or_else(|| Variable::new(name))
+        })
+        .collect();
+
+    Some(Prompt {
+        name,
+        content,
+        tags,
+        variables,
+        updated_at: ours.updated_at.max(theirs.updated_at),
+        ..ours.clone()
+    })
+}
+
+/// The value after both sides' changes, or `None` if they changed it differently
+fn pick<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || base == theirs {
+        Some(ours.clone())
+    } else if base == ours {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+/// `name`, or `name-1`, `name-2`... if it is taken
+fn free_name(name: &str, taken: &[String]) -> String {
+    let mut candidate = name.to_string();
+    let mut counter = 1;
+    while taken.contains(&candidate) {
+        candidate = format!("{}-{}", name, counter);
+        counter += 1;
+    }
+    candidate
+}
+
+/// Variables of `prompt` in a comparable form
+fn variables_key(prompt: &Prompt) -> Vec<(&str, Option<&str>, Option<&str>)> {
+    prompt
+        .variables
+        .iter()
+        .map(|v| (v.name.as_str(), v.description.as_deref(), v.default_value.as_deref()))
+        .collect()
+}
+
+fn sync_error(details: String) -> PromptedsError {
+    PromptedsError::SyncError { details }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompt(name: &str, content: &str, tags: &[&str]) -> Prompt {
+        Prompt::new(name.to_string(), content.to_string()).with_tags(tags.iter().map(|t| t.to_string()).collect())
+    }
+
+    #[test]
+    fn test_prompts_merge_by_id() {
+        let edited = prompt("edited", "one\ntwo\nthree", &["a", "b"]);
+        let clashing = prompt("clashing", "text", &[]);
+        let deleted = prompt("deleted", "gone", &[]);
+        let base = by_id(vec![edited.clone(), clashing.clone(), deleted.clone()]);
+
+        let ours = by_id(vec![
+            Prompt { content: "ONE\ntwo\nthree".to_string(), tags: vec!["a".to_string()], ..edited.clone() },
+            Prompt { content: "local text".to_string(), ..clashing.clone() },
+            prompt("new", "local", &[]),
+        ]);
+        let theirs = by_id(vec![
+            Prompt { name: "renamed".to_string(), content: "one\ntwo\nTHREE".to_string(), tags: vec!["a".to_string(), "b".to_string(), "c".to_string()], ..edited.clone() },
+            Prompt { content: "remote text".to_string(), ..clashing.clone() },
+            deleted,
+            prompt("new", "remote", &[]),
+        ]);
+
+        let result = merge(&base, &ours, &theirs);
+        let merged = &result.prompts[&edited.id];
+        assert_eq!((merged.name.as_str(), merged.content.as_str()), ("renamed", "ONE\ntwo\nTHREE"));
+        assert_eq!(merged.tags, ["a", "c"]);
+        assert_eq!(result.prompts[&clashing.id].content, "local text");
+
+        let mut names: Vec<&str> = result.prompts.values().map(|p| p.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["clashing", "clashing-conflict", "new", "new-1", "renamed"]);
+        assert_eq!(result.conflicts.len(), 2);
+    }
+
+    #[test]
+    fn test_remote_renames_never_take_a_local_name() {
+        let moved = prompt("draft", "text", &[]);
+        let local = prompt("final", "local", &[]);
+        let base = by_id(vec![moved.clone(), local.clone()]);
+        let ours = base.clone();
+        let theirs = by_id(vec![Prompt { name: "final".to_string(), ..moved.clone() }, local.clone()]);
+
+        let result = merge(&base, &ours, &theirs);
+        assert_eq!(result.prompts[&local.id].name, "final");
+        assert_eq!(result.prompts[&moved.id].name, "final-1");
+        assert_eq!(result.conflicts.len(), 1);
+    }
+}
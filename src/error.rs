@@ -14,6 +14,9 @@ pub enum PromptedsError {
     #[error("Clipboard error: {0}")]
     Clipboard(#[from] arboard::Error),
     
+    #[error("Database is locked by another process; try again, or raise database.timeout_ms")]
+    DatabaseBusy,
+    
     #[error("Configuration deserialization error: {0}")]
     ConfigDe(#[from] toml::de::Error),
     
@@ -35,12 +38,36 @@ pub enum PromptedsError {
     #[error("Date parsing error: {0}")]
     DateParse(#[from] chrono::ParseError),
     
-    #[error("Prompt '{name}' not found")]
-    PromptNotFound { name: String },
+    #[error("Prompt '{name}' not found{}", format_suggestions(.suggestions))]
+    PromptNotFound { name: String, suggestions: Vec<String> },
+    
+    #[error("Prompt name '{name}' is ambiguous, it matches: {}", .matches.join(", "))]
+    AmbiguousPromptName { name: String, matches: Vec<String> },
+    
+    #[error("Prompt '{name}' was changed by someone else while you were editing it")]
+    PromptConflict { name: String },
     
     #[error("Prompt '{name}' already exists")]
     PromptAlreadyExists { name: String },
     
+    #[error("No kept render to paste ({back} back); renders are kept when clipboard.history is above 0")]
+    RenderNotKept { back: usize },
+    
+    #[error("Saved search '{name}' not found")]
+    SavedSearchNotFound { name: String },
+    
+    #[error("Library '{name}' not found. Available libraries: {}", .available.join(", "))]
+    LibraryNotFound { name: String, available: Vec<String> },
+    
+    #[error("Library '{name}' already exists")]
+    LibraryAlreadyExists { name: String },
+    
+    #[error("Library '{name}' is the current library")]
+    SameLibrary { name: String },
+    
+    #[error("Prompt '{name}' comes from the project file {}; change it there", .path.display())]
+    ProjectPrompt { name: String, path: std::path::PathBuf },
+    
     #[error("Invalid prompt name: {reason}")]
     InvalidPromptName { reason: String },
     
@@ -53,6 +80,12 @@ pub enum PromptedsError {
     #[error("Invalid variable name '{name}': {reason}")]
     InvalidVariableName { name: String, reason: String },
     
+    #[error("Cannot read secret variable '{name}': {details}")]
+    SecretUnavailable { name: String, details: String },
+    
+    #[error("Prompt '{name}' looks like it holds secrets or personal data: {findings}. Remove them, allow them in scan.allowlist, or pass --allow-secrets")]
+    SecretsDetected { name: String, findings: String },
+    
     #[error("Editor error: {details}")]
     EditorError { details: String },
     
@@ -62,6 +95,36 @@ pub enum PromptedsError {
     #[error("Export error: {details}")]
     ExportError { details: String },
     
+    #[error("Backup error: {details}")]
+    BackupError { details: String },
+    
+    #[error("Sync error: {details}")]
+    SyncError { details: String },
+    
+    #[error("Encryption error: {details}")]
+    EncryptionError { details: String },
+    
+    #[error("The library is encrypted and locked; set EDISONPROMPT_PASSPHRASE, or run eval \"$(edisonprompt encryption unlock)\" in a terminal")]
+    LibraryLocked,
+    
+    #[error("Embedding error: {details}")]
+    EmbeddingError { details: String },
+    
+    #[error("Invalid value for '{key}': {details}")]
+    ConfigValue { key: String, details: String },
+    
+    #[error("Invalid configuration:{}", format_issues(.issues))]
+    ConfigValidation { issues: Vec<crate::config::Issue> },
+    
+    #[error("{count} health check(s) failed")]
+    HealthCheckFailed { count: usize },
+    
+    #[error(
+        "{count} item(s) could not be imported; {}",
+        if *rolled_back { "nothing was imported" } else { "the rest were imported" }
+    )]
+    ImportFailed { count: usize, rolled_back: bool },
+    
     #[error("Configuration directory not found or inaccessible")]
     ConfigDirError,
     
@@ -71,6 +134,18 @@ pub enum PromptedsError {
 
 pub type Result<T> = std::result::Result<T, PromptedsError>;
 
+fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(". Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
+fn format_issues(issues: &[crate::config::Issue]) -> String {
+    issues.iter().map(|issue| format!("\n  {}", issue)).collect()
+}
+
 impl PromptedsError {
     pub fn exit_code(&self) -> i32 {
         match self {
@@ -78,12 +153,23 @@ impl PromptedsError {
             PromptedsError::PromptAlreadyExists { .. } => 2,
             PromptedsError::InvalidPromptName { .. } => 3,
             PromptedsError::TemplateValidation { .. } => 4,
+            PromptedsError::SecretsDetected { .. } => 4,
             PromptedsError::MissingVariable { .. } => 5,
+            PromptedsError::SecretUnavailable { .. } => 5,
             PromptedsError::InvalidVariableName { .. } => 6,
+            PromptedsError::AmbiguousPromptName { .. } => 7,
+            PromptedsError::SavedSearchNotFound { .. } => 1,
+            PromptedsError::RenderNotKept { .. } => 1,
+            PromptedsError::LibraryNotFound { .. } => 1,
+            PromptedsError::LibraryAlreadyExists { .. } => 2,
+            PromptedsError::PromptConflict { .. } => 8,
             PromptedsError::Database(_) => 10,
+            PromptedsError::DatabaseBusy => 10,
             PromptedsError::Io(_) => 11,
             PromptedsError::ConfigDe(_) => 12,
             PromptedsError::ConfigSer(_) => 12,
+            PromptedsError::ConfigValue { .. } => 12,
+            PromptedsError::ConfigValidation { .. } => 12,
             PromptedsError::ConfigDirError => 13,
             PromptedsError::DataDirError => 14,
             _ => 99,
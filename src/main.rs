@@ -1,11 +1,16 @@
 use clap::Parser;
 use edisonprompt::{
-    cli::{Cli, Commands},
+    cli::{Cli, Commands, OutputFormat},
+    cli::output::OutputFormatter,
     cli::commands::*,
-    ConfigManager, Database, ClipboardManager,
+    config::Issue,
+    ConfigManager, Database, ClipboardManager, PromptedsError,
     error::Result,
+    database::{backup, embeddings, search::Ranking, ConnectionOptions},
+    template::scan::Scanner,
     utils,
 };
+use std::time::Duration;
 use std::process;
 
 fn main() {
@@ -18,18 +23,57 @@ fn main() {
 fn run() -> Result<()> {
     let args = Cli::parse();
     
+    // The helper started by `get --clear-after` needs no configuration
+    if let Commands::ClipboardRestore = args.command {
+        return ClipboardManager::run_restore(std::io::stdin().lock());
+    }
+    
     // Initialize configuration
-    let config_manager = if let Some(config_path) = args.config {
+    let mut config_manager = if let Some(config_path) = args.config {
         ConfigManager::with_custom_config(config_path)?
     } else {
         ConfigManager::new()?
+    }
+    .with_library(args.library)
+    .with_project(&std::env::current_dir()?)?;
+    if args.no_color {
+        config_manager = config_manager.with_cli_override("output.color", false.into(), "--no-color")?;
+    }
+    
+    // Config management and the doctor must work even when the configuration
+    // is broken, so they can be used to diagnose and repair it
+    let command = match args.command {
+        Commands::Config { action } => return ConfigCommand::execute(&mut config_manager, action),
+        Commands::Doctor { fix, format } => {
+            let config = config_manager.config();
+            let format = format.unwrap_or_else(|| OutputFormat::from_config(&config.output.format));
+            let library = config_manager.active_library();
+            return DoctorCommand::execute(
+                &config_manager,
+                || open_database(&config_manager, &library),
+                fix,
+                format,
+                config.output.color,
+            );
+        }
+        command => command,
+    };
+    check_config(&config_manager)?;
+    
+    // Library management must work even when the active library is broken
+    let command = match command {
+        Commands::Library { action } => return LibraryCommand::execute(&mut config_manager, action),
+        command => command,
     };
     
     let config = config_manager.config();
     
     // Initialize database
-    let db_path = config_manager.get_database_path();
-    let mut database = Database::new(db_path)?;
+    let library = config_manager.active_library();
+    let mut database = open_database(&config_manager, &library)?;
+    if let Some(prompts_file) = config_manager.project_prompts_file() {
+        database = database.with_project_layer(&prompts_file)?;
+    }
     
     // Initialize clipboard manager
     let mut clipboard = ClipboardManager::new(
@@ -38,33 +82,80 @@ fn run() -> Result<()> {
     );
     
     // Determine if colors should be used
-    let use_color = !args.no_color && config.output.color;
+    let use_color = config.output.color;
+    let formatter = OutputFormatter::new(use_color).with_widths(&config.output.table_widths);
+    
+    // Flags override these only when given
+    let default_format = OutputFormat::from_config(&config.output.format);
+    let format = |format: Option<OutputFormat>| format.unwrap_or_else(|| default_format.clone());
     
     // Execute command
-    match args.command {
-        Commands::Add { name, interactive, tags, force } => {
-            AddCommand::execute(&mut database, &mut clipboard, name, interactive, tags, force)?;
+    match command {
+        Commands::Add { name, interactive, tags, force, secrets, allow_secrets } => {
+            let scanner = Scanner::new(&config.scan, allow_secrets)?;
+            let options = AddOptions { interactive, tags, force, secrets };
+            AddCommand::execute(&mut database, &mut clipboard, &scanner, name, options)?;
         }
         
-        Commands::Get { name, variables, copy, raw } => {
-            GetCommand::execute(&database, &mut clipboard, name, variables, copy, raw)?;
+        Commands::Get { name, variables, copy, raw, clear_after, paste_last } => {
+            let options = GetOptions { variables, copy, raw, clear_after, paste_last, verbose: args.verbose };
+            GetCommand::execute(&database, &mut clipboard, name, options)?;
         }
         
-        Commands::List { tag, format, limit, sort, names_only } => {
-            ListCommand::execute(&database, tag, format, limit, sort, names_only, use_color)?;
+        Commands::List { tag, collection, format: format_flag, limit, sort, names_only, pinned, favorites } => {
+            ListCommand::execute(
+                &database,
+                ListOptions { tag, collection, limit, sort, names_only, pinned, favorites },
+                format(format_flag),
+                &formatter,
+            )?;
+        }
+        
+        Commands::Search { query, saved, highlight, no_highlight, fuzzy, semantic, explain, limit, format: format_flag } => {
+            // Only plain full-text search can highlight
+            let highlight = highlight || (!no_highlight && !fuzzy && !semantic && config.search.highlight);
+            SearchCommand::execute(
+                &database,
+                query,
+                SearchOptions { saved, highlight, fuzzy, semantic, explain, limit, default_limit: config.search.limit },
+                format(format_flag),
+                &formatter,
+            )?;
         }
         
-        Commands::Search { query, highlight, limit, format } => {
-            SearchCommand::execute(&database, query, highlight, limit, format, use_color)?;
+        Commands::Similar { name, limit, format: format_flag } => {
+            SimilarCommand::execute(&database, name, limit, format(format_flag), &formatter)?;
         }
         
-        Commands::Edit { name, yes } => {
+        Commands::Reindex => {
+            ReindexCommand::execute(&mut database)?;
+        }
+        
+        Commands::Pin { name } => {
+            PinCommand::execute(&database, name, true)?;
+        }
+        
+        Commands::Unpin { name } => {
+            PinCommand::execute(&database, name, false)?;
+        }
+        
+        Commands::Favorite { name, remove } => {
+            FavoriteCommand::execute(&database, name, remove)?;
+        }
+        
+        Commands::Recent { limit, format: format_flag } => {
+            RecentCommand::execute(&database, limit, format(format_flag), &formatter)?;
+        }
+        
+        Commands::Edit { name, yes, allow_secrets } => {
             EditCommand::execute(
                 &mut database,
                 config.editor.command.as_deref(),
                 &config.editor.fallback,
+                &Scanner::new(&config.scan, allow_secrets)?,
                 name,
                 yes,
+                use_color,
             )?;
         }
         
@@ -72,12 +163,69 @@ fn run() -> Result<()> {
             DeleteCommand::execute(&mut database, name, yes, force)?;
         }
         
-        Commands::Export { output, tag, pretty } => {
-            ExportCommand::execute(&database, output, tag, pretty)?;
+        Commands::Export { output, schema: true, .. } => {
+            ExportCommand::write_schema(output)?;
+        }
+        
+        Commands::Export { output, tag, pretty, format, encrypt, schema: false } => {
+            ExportCommand::execute(&database, output, tag, pretty, format.map(Into::into), encrypt)?;
+        }
+        
+        Commands::Import { input, merge, dry_run, format, from, continue_on_error, json, allow_secrets } => {
+            let options = ImportOptions {
+                merge,
+                dry_run,
+                continue_on_error,
+                json,
+                color: use_color,
+                editor: EditCommand::editor(config.editor.command.as_deref(), &config.editor.fallback),
+                scanner: Scanner::new(&config.scan, allow_secrets)?,
+            };
+            ImportCommand::execute(&mut database, input, format.map(Into::into), from.map(Into::into), options)?;
+        }
+        
+        Commands::Backup { path, compress } => {
+            let compress = compress || (path.is_none() && config.backup.compress);
+            let path = path.unwrap_or_else(|| {
+                config_manager.backup_dir(&library).join(backup::file_name(None, compress))
+            });
+            BackupCommand::execute(&database, path, compress)?;
+        }
+        
+        Commands::Restore { file, yes } => {
+            RestoreCommand::execute(&mut database, &library, file, yes)?;
+        }
+        
+        Commands::Collection { action } => {
+            CollectionCommand::execute(&database, action, default_format.clone())?;
         }
         
-        Commands::Import { input, merge, dry_run } => {
-            ImportCommand::execute(&mut database, input, merge, dry_run)?;
+        Commands::Dedupe { threshold, merge, yes, format: format_flag } => {
+            DedupeCommand::execute(&mut database, threshold, merge, yes, format(format_flag), use_color)?;
+        }
+        
+        Commands::Library { .. } | Commands::Config { .. } | Commands::Doctor { .. } | Commands::ClipboardRestore => {
+            unreachable!("handled before opening the database")
+        }
+        
+        Commands::Sync { action } => {
+            SyncCommand::execute(&mut database, &config_manager.sync_dir(&library), action)?;
+        }
+        
+        Commands::Encryption { action } => {
+            EncryptionCommand::execute(&mut database, action)?;
+        }
+        
+        Commands::Copy { names, to_library, move_prompts, force } => {
+            if to_library == config_manager.active_library() {
+                return Err(PromptedsError::SameLibrary { name: to_library });
+            }
+            let mut target = open_database(&config_manager, &to_library)?;
+            CopyCommand::execute(&mut database, &mut target, &to_library, names, move_prompts, force)?;
+        }
+        
+        Commands::Stats { days, limit, format: format_flag } => {
+            StatsCommand::execute(&database, days, limit, format(format_flag), config.usage.track, use_color)?;
         }
         
         Commands::Completions { shell } => {
@@ -86,4 +234,37 @@ fn run() -> Result<()> {
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Warn about questionable settings and stop on invalid ones. Warnings go to
+/// stderr so they do not end up in piped output.
+fn check_config(config_manager: &ConfigManager) -> Result<()> {
+    let (errors, warnings): (Vec<Issue>, Vec<Issue>) =
+        config_manager.validate(false).into_iter().partition(Issue::is_error);
+    for warning in &warnings {
+        utils::eprint_warning(&warning.to_string());
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(PromptedsError::ConfigValidation { issues: errors })
+    }
+}
+
+fn open_database(config_manager: &ConfigManager, library: &str) -> Result<Database> {
+    let config = config_manager.config();
+    let options = ConnectionOptions {
+        busy_timeout: Duration::from_millis(config.database.timeout_ms),
+        wal_mode: config.database.wal_mode,
+        backups: (config.backup.keep > 0).then(|| backup::Rotation {
+            dir: config_manager.backup_dir(library),
+            keep: config.backup.keep,
+            compress: config.backup.compress,
+        }),
+    };
+    Ok(Database::open(config_manager.library_path(library)?, &options)?
+        .with_ranking(Ranking::from_config(&config.search))
+        .with_embedder(embeddings::from_config(&config.search.embedder)?)
+        .with_usage_tracking(config.usage.track)
+        .with_render_history(config.clipboard.history))
+}
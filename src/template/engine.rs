@@ -64,11 +64,11 @@ impl TemplateEngine {
         // Register template
         let template_name = uuid::Uuid::new_v4().to_string();
         self.handlebars.register_template_string(&template_name, template)
-            .map_err(|e| PromptedsError::TemplateCompilation(e))?;
+            .map_err(PromptedsError::TemplateCompilation)?;
         
         // Render with variables
         let result = self.handlebars.render(&template_name, variables)
-            .map_err(|e| PromptedsError::Template(e))?;
+            .map_err(PromptedsError::Template)?;
         
         Ok(result)
     }
@@ -1,3 +1,6 @@
 pub mod engine;
+pub mod scan;
+pub mod secrets;
 
-pub use engine::TemplateEngine;
\ No newline at end of file
+pub use engine::TemplateEngine;
+pub use secrets::SecretSource;
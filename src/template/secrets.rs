@@ -0,0 +1,153 @@
+use crate::database::models::Prompt;
+use crate::error::{PromptedsError, Result};
+use std::fmt;
+use std::process::{Command, Stdio};
+
+/// Shown in place of a secret value
+pub const REDACTED: &str = "********";
+
+/// Where the value of a secret variable is read from when a prompt is
+/// rendered, so it never has to be typed or stored
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    /// `env:NAME`: an environment variable
+    Env(String),
+    /// `cmd:COMMAND`: the first line a shell command prints, e.g.
+    /// `cmd:pass show openai`
+    Command(String),
+}
+
+impl SecretSource {
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        match text.split_once(':') {
+            Some(("env", name)) if !name.trim().is_empty() => Ok(SecretSource::Env(name.trim().to_string())),
+            Some(("cmd", command)) if !command.trim().is_empty() => Ok(SecretSource::Command(command.trim().to_string())),
+            _ => Err(format!("'{}' is not a secret source; use env:NAME or cmd:COMMAND", text)),
+        }
+    }
+
+    /// Where the value came from, for verbose output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SecretSource::Env(_) => "environment",
+            SecretSource::Command(_) => "command",
+        }
+    }
+
+    /// The value of `variable` read from this source
+    pub fn resolve(&self, variable: &str) -> Result<String> {
+        let unavailable = |details: String| PromptedsError::SecretUnavailable {
+            name: variable.to_string(),
+            details,
+        };
+        match self {
+            SecretSource::Env(name) => std::env::var(name).map_err(|_| unavailable(format!("{} is not set", name))),
+            SecretSource::Command(command) => {
+                // stderr and stdin stay attached so tools like pass can ask
+                // for their own passphrase
+                let output = shell(command)
+                    .stdout(Stdio::piped())
+                    .output()
+                    .map_err(|e| unavailable(format!("cannot run '{}': {}", command, e)))?;
+                if !output.status.success() {
+                    return Err(unavailable(format!("'{}' failed with {}", command, output.status)));
+                }
+                let stdout = String::from_utf8(output.stdout)
+                    .map_err(|_| unavailable(format!("'{}' did not print text", command)))?;
+                Ok(stdout.lines().next().unwrap_or_default().to_string())
+            }
+        }
+    }
+}
+
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretSource::Env(name) => write!(f, "env:{}", name),
+            SecretSource::Command(command) => write!(f, "cmd:{}", command),
+        }
+    }
+}
+
+/// Drop the `cmd:` sources of `prompt`'s secret variables, which would run
+/// on the next `get`, unless `trusted` (the local copy) already has the
+/// same source for that variable. Prompts from a project file, an import
+/// or a sync remote go through this, so only commands set locally with
+/// `add` ever run. Returns the names of the variables whose source was
+/// dropped; their values are asked for instead.
+pub fn drop_untrusted_commands(prompt: &mut Prompt, trusted: Option<&Prompt>) -> Vec<String> {
+    let mut dropped = Vec::new();
+    for variable in &mut prompt.variables {
+        let is_command = variable.source.as_deref().is_some_and(|source| {
+            matches!(SecretSource::parse(source), Ok(SecretSource::Command(_)) | Err(_))
+        });
+        let known = trusted.is_some_and(|local| {
+            local.variables.iter().any(|v| v.name == variable.name && v.secret && v.source == variable.source)
+        });
+        if is_command && !known {
+            variable.source = None;
+            dropped.push(variable.name.clone());
+        }
+    }
+    dropped
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_parse_and_resolve() {
+        assert_eq!(SecretSource::parse("env:HOME"), Ok(SecretSource::Env("HOME".to_string())));
+        assert_eq!(SecretSource::parse("cmd: pass show x").unwrap().to_string(), "cmd:pass show x");
+        assert!(SecretSource::parse("file:/tmp/key").is_err());
+        assert!(SecretSource::parse("env:").is_err());
+
+        let missing = SecretSource::Env("EDISONPROMPT_TEST_UNSET".to_string()).resolve("token");
+        assert!(missing.unwrap_err().to_string().contains("EDISONPROMPT_TEST_UNSET is not set"));
+        #[cfg(unix)]
+        assert_eq!(
+            SecretSource::Command("printf 'hunter2\\nuser: me\\n'".to_string()).resolve("token").unwrap(),
+            "hunter2"
+        );
+    }
+
+    #[test]
+    fn test_untrusted_command_sources_are_dropped() {
+        let secret = |name: &str, source: &str| crate::database::models::Variable {
+            secret: true,
+            source: Some(source.to_string()),
+            ..crate::database::models::Variable::new(name.to_string())
+        };
+        let incoming = Prompt::new("deploy".to_string(), "{{token}} {{key}} {{home}}".to_string()).with_variables(vec![
+            secret("token", "cmd:pass show token"),
+            secret("key", "cmd:touch /tmp/pwned"),
+            secret("home", "env:HOME"),
+        ]);
+        let local = Prompt::new("deploy".to_string(), String::new())
+            .with_variables(vec![secret("token", "cmd:pass show token")]);
+
+        let mut prompt = incoming.clone();
+        assert_eq!(drop_untrusted_commands(&mut prompt, Some(&local)), ["key"]);
+        let sources: Vec<Option<&str>> = prompt.variables.iter().map(|v| v.source.as_deref()).collect();
+        assert_eq!(sources, [Some("cmd:pass show token"), None, Some("env:HOME")]);
+
+        let mut prompt = incoming;
+        assert_eq!(drop_untrusted_commands(&mut prompt, None), ["token", "key"]);
+        assert!(prompt.variables.iter().all(|v| v.secret));
+    }
+}
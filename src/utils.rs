@@ -2,6 +2,9 @@ use crate::error::Result;
 use colored::*;
 use std::io::{self, Write};
 
+/// First line of a conflict marked by `merge3` or `mark_conflicts`
+pub const CONFLICT_START: &str = "<<<<<<< ";
+
 pub fn print_success(message: &str) {
     println!("{} {}", "✅".green(), message);
 }
@@ -14,6 +17,12 @@ pub fn print_warning(message: &str) {
     println!("{} {}", "⚠️".yellow(), message.yellow());
 }
 
+/// Like `print_warning`, but on stderr, for warnings that must not mix with
+/// a command's output
+pub fn eprint_warning(message: &str) {
+    eprintln!("{} {}", "⚠️".yellow(), message.yellow());
+}
+
 pub fn print_info(message: &str) {
     println!("{} {}", "ℹ️".blue(), message);
 }
@@ -29,6 +38,34 @@ pub fn confirm(message: &str) -> Result<bool> {
     Ok(input == "y" || input == "yes")
 }
 
+/// Ask for one of `choices`, answered by its first letter or in full;
+/// returns its index. Asks again until the answer matches.
+pub fn choose(message: &str, choices: &[&str]) -> Result<usize> {
+    let options: Vec<String> = choices.iter().map(|c| format!("[{}]{}", &c[..1], &c[1..])).collect();
+    loop {
+        print!("{} {}: ", message, options.join(", "));
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no answer on stdin").into());
+        }
+        let input = input.trim().to_lowercase();
+        let answer = choices
+            .iter()
+            .position(|c| input == *c || (input.len() == 1 && c.starts_with(input.as_str())));
+        if let Some(index) = answer {
+            return Ok(index);
+        }
+    }
+}
+
+/// Ask for a secret on the terminal without echoing it; fails when there
+/// is no terminal
+pub fn read_secret(message: &str) -> Result<String> {
+    Ok(rpassword::prompt_password(format!("{}: ", message))?)
+}
+
 pub fn format_duration(seconds: f64) -> String {
     if seconds < 1.0 {
         format!("{:.1}ms", seconds * 1000.0)
@@ -41,6 +78,16 @@ pub fn format_duration(seconds: f64) -> String {
     }
 }
 
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
 pub fn truncate_string(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
         s.to_string()
@@ -53,4 +100,158 @@ pub fn strip_ansi(s: &str) -> String {
     // Simple ANSI escape sequence removal
     let re = regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap();
     re.replace_all(s, "").to_string()
-}
\ No newline at end of file
+}
+/// Unified line diff between two texts, colored when `color` is set
+pub fn format_diff(old: &str, new: &str, old_label: &str, new_label: &str, color: bool) -> String {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let unified = diff
+        .unified_diff()
+        .context_radius(3)
+        .missing_newline_hint(false)
+        .header(old_label, new_label)
+        .to_string();
+
+    if color {
+        color_diff(&unified)
+    } else {
+        unified
+    }
+}
+
+/// Color the lines of a unified diff
+pub fn color_diff(unified: &str) -> String {
+    unified
+        .lines()
+        .map(|line| {
+            if line.starts_with("+++") || line.starts_with("---") {
+                line.bold().to_string()
+            } else if line.starts_with('+') {
+                line.green().to_string()
+            } else if line.starts_with('-') {
+                line.red().to_string()
+            } else if line.starts_with("@@") {
+                line.cyan().to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Line-based three-way merge of two edits of `base`. Changes made on only
+/// one side are taken as they are; where both sides changed the same lines
+/// differently, both versions are kept between git-style conflict markers.
+/// Returns the merged text and the number of conflicts.
+pub fn merge3(base: &str, ours: &str, theirs: &str, ours_label: &str, theirs_label: &str) -> (String, usize) {
+    let base: Vec<&str> = base.lines().collect();
+    let ours: Vec<&str> = ours.lines().collect();
+    let theirs: Vec<&str> = theirs.lines().collect();
+    let ours_match = matching_lines(&base, &ours);
+    let theirs_match = matching_lines(&base, &theirs);
+
+    let mut merged: Vec<String> = Vec::new();
+    let mut conflicts = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while i < base.len() || j < ours.len() || k < theirs.len() {
+        // Next base line that both sides kept, or the end of all three
+        let (b, oj, tk) = (i..base.len())
+            .find_map(|b| Some((b, ours_match[b]?, theirs_match[b]?)))
+            .unwrap_or((base.len(), ours.len(), theirs.len()));
+
+        if (b, oj, tk) == (i, j, k) {
+            merged.push(base[i].to_string());
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+
+        let (base_chunk, ours_chunk, theirs_chunk) = (&base[i..b], &ours[j..oj], &theirs[k..tk]);
+        let lines = |chunk: &[&str]| chunk.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        if ours_chunk == base_chunk || ours_chunk == theirs_chunk {
+            merged.extend(lines(theirs_chunk));
+        } else if theirs_chunk == base_chunk {
+            merged.extend(lines(ours_chunk));
+        } else {
+            conflicts += 1;
+            push_conflict(&mut merged, ours_label, ours_chunk, theirs_label, theirs_chunk);
+        }
+        (i, j, k) = (b, oj, tk);
+    }
+
+    (merged.join("\n"), conflicts)
+}
+
+/// `ours` and `theirs` with every region where they differ marked as a
+/// conflict, like `merge3` without a common base; returns the text and
+/// the number of conflicts
+pub fn mark_conflicts(ours: &str, theirs: &str, ours_label: &str, theirs_label: &str) -> (String, usize) {
+    let ours: Vec<&str> = ours.lines().collect();
+    let theirs: Vec<&str> = theirs.lines().collect();
+    let mut merged: Vec<String> = Vec::new();
+    let mut conflicts = 0;
+    let (mut j, mut k) = (0, 0);
+    let ops = similar::capture_diff_slices(similar::Algorithm::Myers, &ours, &theirs);
+    let equal = ops.iter().filter_map(|op| match *op {
+        similar::DiffOp::Equal { old_index, new_index, len } => Some((old_index, new_index, len)),
+        _ => None,
+    });
+    for (oj, tk, len) in equal.chain(std::iter::once((ours.len(), theirs.len(), 0))) {
+        if (oj, tk) != (j, k) {
+            conflicts += 1;
+            push_conflict(&mut merged, ours_label, &ours[j..oj], theirs_label, &theirs[k..tk]);
+        }
+        merged.extend(ours[oj..oj + len].iter().map(|l| l.to_string()));
+        (j, k) = (oj + len, tk + len);
+    }
+
+    (merged.join("\n"), conflicts)
+}
+
+fn push_conflict(merged: &mut Vec<String>, ours_label: &str, ours: &[&str], theirs_label: &str, theirs: &[&str]) {
+    merged.push(format!("{}{}", CONFLICT_START, ours_label));
+    merged.extend(ours.iter().map(|l| l.to_string()));
+    merged.push("=======".to_string());
+    merged.extend(theirs.iter().map(|l| l.to_string()));
+    merged.push(format!(">>>>>>> {}", theirs_label));
+}
+
+/// For each line of `base`, the index of the same line in `other` if the
+/// diff between them keeps it
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, base, other) {
+        if let similar::DiffOp::Equal { old_index, new_index, len } = op {
+            for n in 0..len {
+                matches[old_index + n] = Some(new_index + n);
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge3_combines_separate_edits_and_marks_overlapping_ones() {
+        let base = "intro\nkeep\nmiddle\nkeep\nend";
+
+        let (merged, conflicts) = merge3(base, "INTRO\nkeep\nmiddle\nkeep\nend", "intro\nkeep\nmiddle\nkeep\nEND", "a", "b");
+        assert_eq!(conflicts, 0);
+        assert_eq!(merged, "INTRO\nkeep\nmiddle\nkeep\nEND");
+
+        let (merged, conflicts) = merge3(base, "intro\nkeep\nmine\nkeep\nend", "intro\nkeep\ntheirs\nkeep\nend", "a", "b");
+        assert_eq!(conflicts, 1);
+        assert_eq!(merged, "intro\nkeep\n<<<<<<< a\nmine\n=======\ntheirs\n>>>>>>> b\nkeep\nend");
+    }
+
+    #[test]
+    fn test_mark_conflicts_keeps_common_lines() {
+        let (marked, conflicts) = mark_conflicts("same\nmine\nsame\nextra", "same\ntheirs\nsame", "a", "b");
+        assert_eq!(conflicts, 2);
+        assert_eq!(marked, "same\n<<<<<<< a\nmine\n=======\ntheirs\n>>>>>>> b\nsame\n<<<<<<< a\nextra\n=======\n>>>>>>> b");
+    }
+}